@@ -15,8 +15,8 @@ fi
 
 cp -r $models/Geometry .
 
-root=$(osimperf-install-info root)
-echo "$(osimperf-install-info)"
+root=$(osimperf-cli install-info root)
+echo "$(osimperf-cli install-info)"
 if [ -z $root ]; then
 	echo "ERROR: no install found"
 	exit 1
@@ -17,7 +17,7 @@ fi
 
 cp -r $models/Geometry .
 
-root=$(osimperf-install-info root)
+root=$(osimperf-cli install-info root)
 if [ -z $root ]; then
 	echo "ERROR: no install found"
 	exit 1
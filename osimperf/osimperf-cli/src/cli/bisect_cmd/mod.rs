@@ -0,0 +1,349 @@
+use super::{
+    arg_or_env_var, prefix_path, InstallCommand, ReadBenchTestSetup, RecordCommand, ResultInfo,
+};
+use crate::git::{self, format_date, Commit};
+use crate::{read_json, write_json, Estimator, RESULT_INFO_FILE_NAME};
+use anyhow::{bail, ensure, Context, Result};
+use clap::Args;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    env::current_dir,
+    fs::create_dir_all,
+    path::{absolute, Path, PathBuf},
+};
+
+static BISECT_STATE_FILE_NAME: &str = "osimperf-bisect.json";
+
+/// OSimPerf bisect command for finding the commit that introduced a slowdown.
+///
+/// Installs and benchmarks commits between the good and bad commit, and reports the first commit
+/// that is slower than the good commit by more than the threshold. Installs and results are kept
+/// in the root directory, such that an interrupted bisect can be resumed by rerunning the command
+/// with the same options. Commits that failed to install or benchmark are retried when resuming.
+#[derive(Debug, Args)]
+pub struct BisectCommand {
+    /// Last commit known to be fast.
+    #[arg(long, short)]
+    good: String,
+
+    /// First commit known to be slow.
+    #[arg(long, short)]
+    bad: String,
+
+    /// Path to benchmark config file.
+    #[arg(long, short)]
+    config: PathBuf,
+
+    /// Slowdown relative to the good commit (percentage) that counts as a regression.
+    #[arg(long, short, default_value_t = 5.)]
+    threshold: f64,
+
+    /// Path to opensim-core repo.
+    #[arg(long, short)]
+    opensim: Option<PathBuf>,
+
+    /// Directory for placing the installs, defaults to current directory.
+    #[arg(long, short)]
+    root: Option<PathBuf>,
+
+    /// Name of project.
+    #[arg(long, short, default_value = "opensim")]
+    name: String,
+
+    /// Path to install script.
+    #[arg(long, short)]
+    installer: Option<PathBuf>,
+
+    /// Path to build dir.
+    #[arg(long)]
+    build: Option<PathBuf>,
+
+    /// Number of test iterations.
+    #[arg(long)]
+    iter: Option<usize>,
+
+    /// Statistic of the durations to compare.
+    #[arg(long, short, value_enum, default_value_t = Estimator::Mean)]
+    estimator: Estimator,
+}
+
+/// Progress of a bisect, stored to allow resuming.
+///
+/// Only successful steps are stored, such that failed commits are retried when resuming.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct BisectState {
+    settings: BisectSettings,
+    steps: Vec<BisectStep>,
+}
+
+/// Settings of a bisect, the stored steps are only reused if these did not change.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct BisectSettings {
+    /// Benchmark config used for all steps.
+    config: PathBuf,
+    good: String,
+    bad: String,
+    threshold: f64,
+    iter: Option<usize>,
+    estimator: Estimator,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct BisectStep {
+    commit: String,
+    date: String,
+    /// Benchmark duration estimate.
+    duration: f64,
+}
+
+impl BisectCommand {
+    pub fn run(&self) -> Result<()> {
+        ensure!(
+            self.threshold > 0.,
+            "threshold must be positive: threshold = {}",
+            self.threshold
+        );
+
+        let source = arg_or_env_var(self.opensim.clone(), "OSPC_OPENSIM_SRC")?
+            .context("failed to get path to opensim-source")?;
+        let config = super::absolute_path(&self.config)?;
+        let root = match self.root.as_ref() {
+            Some(root) => absolute(root)?,
+            None => current_dir()?,
+        };
+        create_dir_all(&root)?;
+
+        let commits = collect_commits(&source, &self.good, &self.bad)?;
+        info!(
+            "Bisecting {} commits from {} to {}",
+            commits.len(),
+            commits.first().unwrap().hash(),
+            commits.last().unwrap().hash()
+        );
+
+        // Read previous progress, if it was using the same settings.
+        let settings = BisectSettings {
+            config: config.clone(),
+            good: commits.first().unwrap().hash().clone(),
+            bad: commits.last().unwrap().hash().clone(),
+            threshold: self.threshold,
+            iter: self.iter,
+            estimator: self.estimator,
+        };
+        let state_path = root.join(BISECT_STATE_FILE_NAME);
+        let previous = read_json::<BisectState>(&state_path).ok();
+        if previous.as_ref().is_some_and(|s| s.settings != settings) {
+            warn!("Discarding previous bisect progress, as it was using different settings");
+        }
+        let mut state = previous
+            .filter(|s| s.settings == settings)
+            .unwrap_or_else(|| BisectState {
+                settings,
+                steps: Vec::new(),
+            });
+        if !state.steps.is_empty() {
+            info!("Resuming bisect with {} completed steps", state.steps.len());
+        }
+
+        // Restore the checked out branch or commit afterwards.
+        let branch = git::read_current_branch(&source)?;
+        let head = git::read_current_commit(&source)?;
+
+        let result = self.bisect(&source, &config, &root, &commits, &mut state, &state_path);
+
+        if branch == "HEAD" {
+            git::checkout_commit(&source, &head)?;
+        } else {
+            git::switch_branch(&source, &branch)?;
+        }
+
+        result
+    }
+
+    fn bisect(
+        &self,
+        source: &Path,
+        config: &Path,
+        root: &Path,
+        commits: &[Commit],
+        state: &mut BisectState,
+        state_path: &Path,
+    ) -> Result<()> {
+        let mut measure = |commit: &Commit| -> Result<Option<f64>> {
+            let duration =
+                if let Some(step) = state.steps.iter().find(|s| &s.commit == commit.hash()) {
+                    debug!("Found previous step {:?}", step);
+                    Some(step.duration)
+                } else {
+                    let duration = self
+                        .install_and_record(source, config, root, commit)
+                        .map_err(|err| warn!("Skipping commit {}: {err:#}", commit.hash()))
+                        .ok();
+                    // Failed steps are not stored, such that resuming retries them.
+                    if let Some(duration) = duration {
+                        state.steps.push(BisectStep {
+                            commit: commit.hash().clone(),
+                            date: commit.date_str().to_owned(),
+                            duration,
+                        });
+                        write_json(state_path, state)?;
+                    }
+                    duration
+                };
+            info!(
+                "Commit {} ({}): {}",
+                commit.hash(),
+                commit.date_str(),
+                duration
+                    .map(|dt| format!("{dt:.3}"))
+                    .unwrap_or("skipped".to_owned())
+            );
+            Ok(duration)
+        };
+
+        let good = commits.first().unwrap();
+        let bad = commits.last().unwrap();
+        let Some(reference) = measure(good)? else {
+            bail!("failed to benchmark good commit {}", good.hash());
+        };
+        let is_slower = |duration: f64| (duration - reference) / reference * 100. > self.threshold;
+
+        let Some(duration) = measure(bad)? else {
+            bail!("failed to benchmark bad commit {}", bad.hash());
+        };
+        ensure!(
+            is_slower(duration),
+            "bad commit {} is not slower than good commit {}: {:.3} vs {:.3}",
+            bad.hash(),
+            good.hash(),
+            duration,
+            reference
+        );
+
+        // Commits that failed to build or run are dropped, similar to `git bisect skip`.
+        let mut candidates = commits.iter().collect::<Vec<&Commit>>();
+        let (mut lo, mut hi) = (0, candidates.len() - 1);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            match measure(candidates[mid])? {
+                Some(duration) if is_slower(duration) => hi = mid,
+                Some(_) => lo = mid,
+                None => {
+                    candidates.remove(mid);
+                    hi -= 1;
+                }
+            }
+        }
+
+        let first_bad = candidates[hi];
+        let duration = state
+            .steps
+            .iter()
+            .find(|s| s.commit == *first_bad.hash())
+            .map(|s| s.duration)
+            .context("missing bisect step of first slow commit")?;
+        println!(
+            "First slow commit: {} ({}), {:.3} vs {:.3} ({:+.1}%)",
+            first_bad.hash(),
+            first_bad.date_str(),
+            duration,
+            reference,
+            (duration - reference) / reference * 100.
+        );
+        // Skipped commits directly before the first bad commit could also be the culprit.
+        let last_good = candidates[lo];
+        let between = commits
+            .iter()
+            .skip_while(|c| c.hash() != last_good.hash())
+            .skip(1)
+            .take_while(|c| c.hash() != first_bad.hash())
+            .collect::<Vec<&Commit>>();
+        if !between.is_empty() {
+            println!("The slowdown could also be caused by skipped commits:");
+            for c in between {
+                println!("{} ({})", c.hash(), c.date_str());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Installs the commit, and benchmarks it, returning the estimated duration.
+    fn install_and_record(
+        &self,
+        source: &Path,
+        config: &Path,
+        root: &Path,
+        commit: &Commit,
+    ) -> Result<f64> {
+        git::checkout_commit(source, commit.hash())?;
+
+        let install_root = root.join(format!(
+            "install_{}_{}_{}",
+            self.name,
+            format_date(&commit.date()),
+            commit.hash()
+        ));
+        InstallCommand::new(
+            self.name.clone(),
+            self.installer.as_ref().map(absolute).transpose()?,
+            source.to_path_buf(),
+            install_root.clone(),
+            self.build.as_ref().map(absolute).transpose()?,
+        )
+        .run()
+        .context("failed to install")?;
+
+        // Put the install on the path for the record command, and restore the paths afterwards
+        // such that the next commit does not use the libraries of this install.
+        let path = std::env::var("PATH")?;
+        let ld_library_path = std::env::var("LD_LIBRARY_PATH").ok();
+        prefix_path(
+            &["PATH"],
+            &install_root.join("bin").to_str().unwrap().to_owned(),
+        )?;
+        prefix_path(
+            &["LD_LIBRARY_PATH"],
+            &install_root.join("lib").to_str().unwrap().to_owned(),
+        )?;
+        let result = RecordCommand::new(config.to_path_buf(), self.iter).run();
+        std::env::set_var("PATH", path);
+        match ld_library_path {
+            Some(value) => std::env::set_var("LD_LIBRARY_PATH", value),
+            None => std::env::remove_var("LD_LIBRARY_PATH"),
+        }
+        result.context("failed to record")?;
+
+        let name = read_json::<ReadBenchTestSetup>(config)?.name;
+        let result_info = ResultInfo::read(
+            &install_root
+                .join("results")
+                .join(name)
+                .join(RESULT_INFO_FILE_NAME),
+        )?;
+        result_info
+            .durations
+            .get_estimate(self.estimator)
+            .context("no benchmark durations recorded")
+    }
+}
+
+/// Returns the commits from good to bad, oldest first.
+fn collect_commits(source: &Path, good: &str, bad: &str) -> Result<Vec<Commit>> {
+    let good = git::resolve_commit(source, good)?;
+    let bad = git::resolve_commit(source, bad)?;
+    ensure!(
+        git::is_ancestor(source, &good, &bad)?,
+        "good commit {good} is not an ancestor of bad commit {bad}"
+    );
+
+    let mut commits = git::get_ancestry_path(source, &good, &bad)?;
+    ensure!(
+        !commits.is_empty(),
+        "good and bad commit must differ: {good}"
+    );
+    commits.push(Commit::new(good.clone(), git::get_date(source, &good)?));
+    commits.reverse();
+    Ok(commits)
+}
@@ -0,0 +1,105 @@
+use rand::prelude::*;
+
+/// Percentile bootstrap confidence interval of the relative change in mean duration.
+#[derive(Copy, Clone, Debug)]
+pub struct BootstrapInterval {
+    /// Lower bound of the relative change in percent.
+    pub lower: f64,
+    /// Upper bound of the relative change in percent.
+    pub upper: f64,
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+fn resampled_mean(samples: &[f64], rng: &mut impl Rng) -> f64 {
+    (0..samples.len())
+        .map(|_| *samples.choose(rng).unwrap())
+        .sum::<f64>()
+        / samples.len() as f64
+}
+
+/// Computes the `1 - alpha` confidence interval of `(candidate - baseline) / baseline * 100`.
+pub fn bootstrap_interval(
+    baseline: &[f64],
+    candidate: &[f64],
+    alpha: f64,
+    resamples: usize,
+) -> Option<BootstrapInterval> {
+    bootstrap_interval_with_rng(
+        baseline,
+        candidate,
+        alpha,
+        resamples,
+        &mut rand::thread_rng(),
+    )
+}
+
+fn bootstrap_interval_with_rng(
+    baseline: &[f64],
+    candidate: &[f64],
+    alpha: f64,
+    resamples: usize,
+    rng: &mut impl Rng,
+) -> Option<BootstrapInterval> {
+    if baseline.is_empty() || candidate.is_empty() || resamples == 0 || mean(baseline) <= 0. {
+        return None;
+    }
+
+    let mut changes: Vec<f64> = (0..resamples)
+        .map(|_| {
+            let b = resampled_mean(baseline, rng);
+            let c = resampled_mean(candidate, rng);
+            (c - b) / b * 100.
+        })
+        .filter(|x| x.is_finite())
+        .collect();
+    if changes.is_empty() {
+        return None;
+    }
+    changes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let quantile = |q: f64| {
+        let index = (q * (changes.len() - 1) as f64).round() as usize;
+        changes[index.min(changes.len() - 1)]
+    };
+
+    Some(BootstrapInterval {
+        lower: quantile(alpha / 2.),
+        upper: quantile(1. - alpha / 2.),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn interval_contains_true_change() {
+        // Symmetric samples around 100 and 110: a change of 10% in both mean and median.
+        let noise = [-3., -2., -1., -0.5, 0., 0., 0.5, 1., 2., 3.];
+        let baseline = noise.map(|x| 100. + x);
+        let candidate = noise.map(|x| 110. + x);
+        let mut rng = StdRng::seed_from_u64(42);
+        let ci = bootstrap_interval_with_rng(&baseline, &candidate, 0.05, 2000, &mut rng).unwrap();
+        assert!(ci.lower < 10. && 10. < ci.upper, "{:?}", ci);
+        assert!(ci.lower > 0., "{:?}", ci);
+    }
+
+    #[test]
+    fn identical_constant_samples_give_zero_change() {
+        let samples = [2.; 5];
+        let mut rng = StdRng::seed_from_u64(0);
+        let ci = bootstrap_interval_with_rng(&samples, &samples, 0.05, 100, &mut rng).unwrap();
+        assert_eq!((ci.lower, ci.upper), (0., 0.));
+    }
+
+    #[test]
+    fn empty_samples_give_none() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(bootstrap_interval_with_rng(&[], &[1.], 0.05, 100, &mut rng).is_none());
+        assert!(bootstrap_interval_with_rng(&[1.], &[1.], 0.05, 0, &mut rng).is_none());
+    }
+}
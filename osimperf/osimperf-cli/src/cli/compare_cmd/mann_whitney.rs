@@ -0,0 +1,200 @@
+/// One-sided p-values of the Mann-Whitney U test.
+#[derive(Copy, Clone, Debug)]
+pub struct MannWhitney {
+    /// Probability of observing a U at least this large if both samples are equally distributed.
+    pub p_slower: f64,
+    /// Probability of observing a U at least this small if both samples are equally distributed.
+    pub p_faster: f64,
+}
+
+/// Above this total sample count the normal approximation is used instead of the exact distribution.
+const EXACT_MAX_SAMPLES: usize = 30;
+
+/// Runs the Mann-Whitney U test on two samples.
+///
+/// Uses the exact distribution of U for small samples without ties, and the normal
+/// approximation with tie and continuity correction otherwise.
+pub fn mann_whitney(baseline: &[f64], candidate: &[f64]) -> Option<MannWhitney> {
+    if baseline.is_empty() || candidate.is_empty() {
+        return None;
+    }
+
+    let n = baseline.len();
+    let m = candidate.len();
+
+    // Pool samples, marking the candidate samples.
+    let mut pooled: Vec<(f64, bool)> = baseline
+        .iter()
+        .map(|&x| (x, false))
+        .chain(candidate.iter().map(|&x| (x, true)))
+        .collect();
+    pooled.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // Assign average ranks to tied groups.
+    let mut rank_sum = 0.;
+    let mut tie_correction = 0.;
+    let mut has_ties = false;
+    let mut i = 0;
+    while i < pooled.len() {
+        let mut j = i + 1;
+        while j < pooled.len() && pooled[j].0 == pooled[i].0 {
+            j += 1;
+        }
+        let t = (j - i) as f64;
+        if j - i > 1 {
+            has_ties = true;
+            tie_correction += t.powi(3) - t;
+        }
+        // Ranks are one-based.
+        let rank = (i + j + 1) as f64 / 2.;
+        rank_sum += rank * pooled[i..j].iter().filter(|x| x.1).count() as f64;
+        i = j;
+    }
+
+    // Number of (candidate, baseline) pairs where the candidate is slower.
+    let u = rank_sum - (m * (m + 1)) as f64 / 2.;
+
+    let (p_slower, p_faster) = if !has_ties && n + m <= EXACT_MAX_SAMPLES {
+        exact_p_values(n, m, u.round() as usize)
+    } else {
+        normal_p_values(n, m, u, tie_correction)
+    };
+
+    Some(MannWhitney { p_slower, p_faster })
+}
+
+/// Counts the sequences of `m` candidates and `n` baselines for every value of U.
+fn exact_distribution(n: usize, m: usize) -> Vec<f64> {
+    let u_max = n * m;
+    // count[i][j][u]: sequences of i candidates and j baselines with statistic u.
+    let mut count = vec![vec![vec![0f64; u_max + 1]; n + 1]; m + 1];
+    for i in 0..=m {
+        for j in 0..=n {
+            if i == 0 || j == 0 {
+                count[i][j][0] = 1.;
+                continue;
+            }
+            for u in 0..=i * j {
+                // The largest element is either a candidate, exceeding all j baselines...
+                let a = if u >= j { count[i - 1][j][u - j] } else { 0. };
+                // ... or a baseline.
+                let b = count[i][j - 1][u];
+                count[i][j][u] = a + b;
+            }
+        }
+    }
+    count.swap_remove(m).swap_remove(n)
+}
+
+fn exact_p_values(n: usize, m: usize, u: usize) -> (f64, f64) {
+    let dist = exact_distribution(n, m);
+    let total: f64 = dist.iter().sum();
+    let p_slower = dist[u..].iter().sum::<f64>() / total;
+    let p_faster = dist[..=u].iter().sum::<f64>() / total;
+    (p_slower, p_faster)
+}
+
+fn normal_p_values(n: usize, m: usize, u: f64, tie_correction: f64) -> (f64, f64) {
+    let n_total = (n + m) as f64;
+    let mean = (n * m) as f64 / 2.;
+    let var = (n * m) as f64 / 12.
+        * ((n_total + 1.) - tie_correction / (n_total * (n_total - 1.)));
+    if var <= 0. {
+        // All samples are equal.
+        return (1., 1.);
+    }
+    let sigma = var.sqrt();
+    let p_slower = 1. - normal_cdf((u - 0.5 - mean) / sigma);
+    let p_faster = normal_cdf((u + 0.5 - mean) / sigma);
+    (p_slower.clamp(0., 1.), p_faster.clamp(0., 1.))
+}
+
+/// Cumulative distribution function of the standard normal distribution.
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function, with fractional error below 1.2e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+    let r = t * (-z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+        .exp();
+    if x >= 0. {
+        r
+    } else {
+        2. - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{a} != {b}");
+    }
+
+    #[test]
+    fn exact_distribution_of_three_by_three() {
+        assert_eq!(
+            exact_distribution(3, 3),
+            vec![1., 1., 2., 3., 3., 3., 3., 2., 1., 1.]
+        );
+    }
+
+    #[test]
+    fn exact_p_values_match_table() {
+        // Critical value of U for n = m = 5 at 5% one-sided significance is 4.
+        let (_, p_faster) = exact_p_values(5, 5, 4);
+        assert_close(p_faster, 12. / 252.);
+        let (_, p_faster) = exact_p_values(5, 5, 5);
+        assert_close(p_faster, 19. / 252.);
+    }
+
+    #[test]
+    fn exact_test_of_separated_samples() {
+        let test = mann_whitney(&[1., 2., 3.], &[4., 5., 6.]).unwrap();
+        assert_close(test.p_slower, 1. / 20.);
+        assert_close(test.p_faster, 1.);
+
+        let test = mann_whitney(&[3., 4.], &[1., 2.]).unwrap();
+        assert_close(test.p_slower, 1.);
+        assert_close(test.p_faster, 1. / 6.);
+    }
+
+    #[test]
+    fn ties_use_normal_approximation() {
+        // Candidate rank sum is 23, U = 13, with two groups of three ties.
+        let test = mann_whitney(&[1., 2., 2., 3.], &[2., 3., 3., 4.]).unwrap();
+        assert_close(test.p_slower, 0.0860168545);
+        assert_close(test.p_faster, 0.9524598066);
+    }
+
+    #[test]
+    fn identical_samples_give_p_one() {
+        let test = mann_whitney(&[1.; 4], &[1.; 4]).unwrap();
+        assert_close(test.p_slower, 1.);
+        assert_close(test.p_faster, 1.);
+    }
+
+    #[test]
+    fn empty_samples_give_none() {
+        assert!(mann_whitney(&[], &[1.]).is_none());
+        assert!(mann_whitney(&[1.], &[]).is_none());
+    }
+
+    #[test]
+    fn normal_cdf_known_values() {
+        assert_close(normal_cdf(0.), 0.5);
+        assert_close(normal_cdf(1.959963984540054), 0.975);
+        assert_close(normal_cdf(-1.959963984540054), 0.025);
+    }
+}
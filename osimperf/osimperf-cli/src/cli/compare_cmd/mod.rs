@@ -0,0 +1,499 @@
+mod bootstrap;
+mod mann_whitney;
+
+use bootstrap::bootstrap_interval;
+use mann_whitney::mann_whitney;
+
+use super::ResultInfo;
+use crate::{find_file_by_name, PerfCounters};
+use anyhow::{bail, ensure, Context, Result};
+use clap::{Args, ValueEnum};
+use log::{debug, info, warn};
+use std::{
+    io::{LineWriter, Write},
+    path::PathBuf,
+};
+
+/// OSimPerf compare command for detecting performance regressions.
+///
+/// Pairs benchmark results by name and build variant, and tests per benchmark if the candidate durations differ
+/// significantly from the baseline durations. Exits with an error if any benchmark became
+/// significantly slower.
+#[derive(Debug, Args)]
+pub struct CompareCommand {
+    /// Baseline result files, or directories to search for result files.
+    #[arg(long, short, required = true, num_args = 1..)]
+    baseline: Vec<PathBuf>,
+
+    /// Candidate result files, or directories to search for result files.
+    #[arg(long, short, required = true, num_args = 1..)]
+    candidate: Vec<PathBuf>,
+
+    /// Statistical test used for deciding significance.
+    #[arg(long, short, value_enum, default_value_t = CompareMethod::MannWhitney)]
+    method: CompareMethod,
+
+    /// Measurement to compare.
+    #[arg(long, value_enum, default_value_t = CompareMetric::Duration)]
+    metric: CompareMetric,
+
+    /// Significance level.
+    #[arg(long, short, default_value_t = 0.05)]
+    alpha: f64,
+
+    /// Minimum change in mean (percentage) for a difference to count.
+    #[arg(long, default_value_t = 0.)]
+    min_change: f64,
+
+    /// Number of bootstrap resamples.
+    #[arg(long, default_value_t = 10000)]
+    resamples: usize,
+
+    /// Compare results that were recorded with different cpu isolation or frequency settings.
+    #[arg(long)]
+    ignore_isolation: bool,
+
+    /// Output path.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CompareMethod {
+    /// One-sided Mann-Whitney U test.
+    MannWhitney,
+    /// Percentile bootstrap confidence interval of the change in mean.
+    Bootstrap,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CompareMetric {
+    /// Wall time durations, excluding outliers.
+    Duration,
+    /// Instruction counts (requires `record --perf-counters`).
+    Instructions,
+    /// Cpu cycle counts (requires `record --perf-counters`).
+    Cycles,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Verdict {
+    Slower,
+    Faster,
+    Unchanged,
+    /// Too few samples to decide.
+    Insufficient,
+    /// Benchmark only present in one of the sets.
+    Missing,
+    /// Recorded with different cpu isolation or frequency settings.
+    Incomparable,
+    /// Failed validation or timed out, e.g. the simulation output diverged from the reference.
+    Invalid,
+}
+
+impl Verdict {
+    fn to_str(self) -> &'static str {
+        match self {
+            Self::Slower => "SLOWER",
+            Self::Faster => "faster",
+            Self::Unchanged => "unchanged",
+            Self::Insufficient => "insufficient samples",
+            Self::Missing => "missing",
+            Self::Incomparable => "different isolation",
+            Self::Invalid => "INVALID",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Comparison {
+    name: String,
+    baseline: Option<ResultInfo>,
+    candidate: Option<ResultInfo>,
+    /// Change in mean, relative to the baseline (percentage).
+    change: Option<f64>,
+    /// Test statistic description: p-value or confidence interval.
+    statistic: Option<String>,
+    verdict: Verdict,
+}
+
+impl CompareCommand {
+    pub fn run(&self) -> Result<()> {
+        ensure!(
+            self.alpha > 0. && self.alpha < 1.,
+            "significance level must be between zero and one: alpha = {}",
+            self.alpha
+        );
+
+        let baseline = collect_results(&self.baseline).context("failed to read baseline")?;
+        let candidate = collect_results(&self.candidate).context("failed to read candidate")?;
+        info!(
+            "Comparing {} baseline results to {} candidate results.",
+            baseline.len(),
+            candidate.len()
+        );
+
+        let comparisons = pair_results(&baseline, &candidate)
+            .drain(..)
+            .map(|(name, b, c)| self.compare(&name, b, c))
+            .collect::<Vec<Comparison>>();
+
+        if let Some(path) = self.out.as_ref() {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("failed to open file for writing at path = {:?}", path))?;
+            print_comparisons(&comparisons, self.method, self.metric, file)?;
+        } else {
+            print_comparisons(&comparisons, self.method, self.metric, std::io::stdout())?;
+        }
+
+        let slower = comparisons
+            .iter()
+            .filter(|c| c.verdict == Verdict::Slower)
+            .map(|c| c.name.as_str())
+            .collect::<Vec<&str>>();
+        if !slower.is_empty() {
+            bail!("significant slowdown detected: {}", slower.join(", "));
+        }
+        let invalid = comparisons
+            .iter()
+            .filter(|c| c.verdict == Verdict::Invalid)
+            .map(|c| c.name.as_str())
+            .collect::<Vec<&str>>();
+        if !invalid.is_empty() {
+            bail!("invalid results detected: {}", invalid.join(", "));
+        }
+
+        Ok(())
+    }
+
+    fn compare(
+        &self,
+        name: &str,
+        baseline: Option<&ResultInfo>,
+        candidate: Option<&ResultInfo>,
+    ) -> Comparison {
+        let mut out = Comparison {
+            name: name.to_owned(),
+            baseline: baseline.cloned(),
+            candidate: candidate.cloned(),
+            change: None,
+            statistic: None,
+            verdict: Verdict::Missing,
+        };
+
+        let (b, c) = match (baseline, candidate) {
+            (Some(b), Some(c)) => {
+                // Never report an invalid or failed result as a speedup.
+                if !b.is_valid() || !c.is_valid() {
+                    warn!("{name}: failed validation, not comparing durations");
+                    out.verdict = Verdict::Invalid;
+                    return out;
+                }
+                if b.failed() || c.failed() {
+                    warn!("{name}: benchmark failed, not comparing durations");
+                    out.verdict = Verdict::Invalid;
+                    return out;
+                }
+                if b.timed_out() || c.timed_out() {
+                    warn!("{name}: benchmark timed out, not comparing durations");
+                    out.verdict = Verdict::Invalid;
+                    return out;
+                }
+                let same_settings =
+                    b.isolation == c.isolation && b.cpu_frequency == c.cpu_frequency;
+                if !same_settings && !self.ignore_isolation {
+                    warn!(
+                        "{name}: recorded with different isolation settings: {:?} {:?} vs {:?} {:?}",
+                        b.isolation, b.cpu_frequency, c.isolation, c.cpu_frequency
+                    );
+                    out.verdict = Verdict::Incomparable;
+                    return out;
+                }
+                (samples(b, self.metric), samples(c, self.metric))
+            }
+            _ => return out,
+        };
+
+        out.change = mean(&b)
+            .zip(mean(&c))
+            .map(|(b, c)| (c - b) / b * 100.);
+
+        if b.len() < 2 || c.len() < 2 {
+            out.verdict = Verdict::Insufficient;
+            return out;
+        }
+
+        let change = out.change.unwrap_or(0.);
+        let (slower, faster) = match self.method {
+            CompareMethod::MannWhitney => {
+                let test = mann_whitney(&b, &c).expect("samples should not be empty");
+                debug!("{name}: {:?}", test);
+                out.statistic = Some(format!(
+                    "p={:.3}",
+                    if change >= 0. {
+                        test.p_slower
+                    } else {
+                        test.p_faster
+                    }
+                ));
+                (test.p_slower < self.alpha, test.p_faster < self.alpha)
+            }
+            CompareMethod::Bootstrap => {
+                let Some(ci) = bootstrap_interval(&b, &c, self.alpha, self.resamples) else {
+                    out.verdict = Verdict::Insufficient;
+                    return out;
+                };
+                debug!("{name}: {:?}", ci);
+                out.statistic = Some(format!("[{:.1}%, {:.1}%]", ci.lower, ci.upper));
+                (ci.lower > self.min_change, ci.upper < -self.min_change)
+            }
+        };
+
+        out.verdict = if slower && change > self.min_change {
+            Verdict::Slower
+        } else if faster && change < -self.min_change {
+            Verdict::Faster
+        } else {
+            Verdict::Unchanged
+        };
+        out
+    }
+}
+
+fn samples(result: &ResultInfo, metric: CompareMetric) -> Vec<f64> {
+    let counts = |count: fn(&PerfCounters) -> Option<u64>| {
+        result
+            .perf_counters
+            .iter()
+            .flatten()
+            .filter_map(count)
+            .map(|x| x as f64)
+            .collect()
+    };
+    match metric {
+        CompareMetric::Duration => result
+            .durations
+            .inliers()
+            .iter()
+            .map(|dt| dt.as_secs_f64())
+            .collect(),
+        CompareMetric::Instructions => counts(|c| c.instructions),
+        CompareMetric::Cycles => counts(|c| c.cycles),
+    }
+}
+
+fn mean(samples: &[f64]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    Some(samples.iter().sum::<f64>() / samples.len() as f64)
+}
+
+fn stddev(samples: &[f64]) -> Option<f64> {
+    let mean = mean(samples)?;
+    if samples.len() < 2 {
+        return None;
+    }
+    let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64;
+    Some(var.sqrt())
+}
+
+/// Benchmark name, with the build variant if any.
+fn label(result: &ResultInfo) -> String {
+    match result.variant.as_ref() {
+        Some(variant) => format!("{} ({})", result.name, variant),
+        None => result.name.clone(),
+    }
+}
+
+/// Pairs results by benchmark name and build variant, sorted by label.
+fn pair_results<'a>(
+    baseline: &'a [ResultInfo],
+    candidate: &'a [ResultInfo],
+) -> Vec<(String, Option<&'a ResultInfo>, Option<&'a ResultInfo>)> {
+    let key = |r: &'a ResultInfo| (&r.name, &r.variant);
+    let mut keys = baseline
+        .iter()
+        .chain(candidate.iter())
+        .map(key)
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+
+    keys.drain(..)
+        .map(|k| {
+            let b = baseline.iter().find(|&r| key(r) == k);
+            let c = candidate.iter().find(|&r| key(r) == k);
+            let name = label(b.or(c).expect("key should come from a result"));
+            (name, b, c)
+        })
+        .collect()
+}
+
+/// Reads all result files, searching any directories for result files.
+fn collect_results(paths: &[PathBuf]) -> Result<Vec<ResultInfo>> {
+    let mut files = Vec::new();
+    for path in paths.iter() {
+        if path.is_dir() {
+            files.extend(find_file_by_name(path, ResultInfo::filename()));
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut results: Vec<ResultInfo> = Vec::new();
+    for file in files.iter() {
+        let result = ResultInfo::read(file)?;
+        if let Some(prev) = results
+            .iter()
+            .find(|r| r.name == result.name && r.variant == result.variant)
+        {
+            bail!(
+                "found multiple results for benchmark {}: {} ({}) and {} ({})",
+                label(&result),
+                prev.opensim_name,
+                prev.date,
+                result.opensim_name,
+                result.date,
+            );
+        }
+        results.push(result);
+    }
+    if results.is_empty() {
+        warn!("No results found in {:?}", paths);
+    }
+    Ok(results)
+}
+
+fn write_install(s: &mut String, result: &Option<ResultInfo>, metric: CompareMetric) {
+    if let Some(r) = result.as_ref() {
+        let x = samples(r, metric);
+        // Show counts in billions.
+        let scale = match metric {
+            CompareMetric::Duration => 1.,
+            CompareMetric::Instructions | CompareMetric::Cycles => 1e-9,
+        };
+        s.push_str(&format!(
+            " {:.3} ({:.3}, {}X) |",
+            mean(&x).unwrap_or(f64::NAN) * scale,
+            stddev(&x).unwrap_or(f64::NAN) * scale,
+            x.len(),
+        ));
+    } else {
+        s.push_str(" |");
+    }
+}
+
+fn print_comparisons(
+    comparisons: &[Comparison],
+    method: CompareMethod,
+    metric: CompareMetric,
+    buf: impl Write,
+) -> Result<()> {
+    let mut buf = LineWriter::new(buf);
+
+    let statistic = match method {
+        CompareMethod::MannWhitney => "p-value",
+        CompareMethod::Bootstrap => "CI",
+    };
+    buf.write_all(
+        format!("| |Baseline|Candidate|Change|{statistic}|Verdict|\n").as_bytes(),
+    )?;
+    buf.write_all(b"|---|---|---|---|---|---|\n")?;
+
+    let mut line = String::new();
+    for c in comparisons.iter() {
+        line.clear();
+        line.push('|');
+        line.push_str(&c.name);
+        line.push('|');
+        write_install(&mut line, &c.baseline, metric);
+        write_install(&mut line, &c.candidate, metric);
+        if let Some(change) = c.change {
+            line.push_str(&format!(" {:.1}%", change));
+        }
+        line.push_str(" |");
+        if let Some(statistic) = c.statistic.as_ref() {
+            line.push(' ');
+            line.push_str(statistic);
+        }
+        line.push_str(" | ");
+        line.push_str(c.verdict.to_str());
+        line.push_str(" |\n");
+        buf.write_all(line.as_bytes())?;
+    }
+
+    buf.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use osimperf_results::Outcome;
+
+    fn result(name: &str, variant: Option<&str>, opensim_name: &str) -> ResultInfo {
+        let mut result = ResultInfo::new(
+            name.to_owned(),
+            opensim_name.to_owned(),
+            "abc123".to_owned(),
+            "2023-01-01".to_owned(),
+        );
+        result.variant = variant.map(str::to_owned);
+        result
+    }
+
+    #[test]
+    fn pairs_results_by_name_and_variant() {
+        let baseline = [
+            result("Arm26", None, "base"),
+            result("Arm26", Some("debug"), "base"),
+            result("Gait", None, "base"),
+        ];
+        let candidate = [
+            result("Arm26", Some("debug"), "cand"),
+            result("Arm26", None, "cand"),
+        ];
+        let pairs = pair_results(&baseline, &candidate)
+            .into_iter()
+            .map(|(name, b, c)| {
+                (
+                    name,
+                    b.map(|r| r.opensim_name.as_str()),
+                    c.map(|r| r.opensim_name.as_str()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            [
+                ("Arm26".to_owned(), Some("base"), Some("cand")),
+                ("Arm26 (debug)".to_owned(), Some("base"), Some("cand")),
+                ("Gait".to_owned(), Some("base"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn timed_out_result_is_invalid() {
+        let cmd = CompareCommand {
+            baseline: Vec::new(),
+            candidate: Vec::new(),
+            method: CompareMethod::MannWhitney,
+            metric: CompareMetric::Duration,
+            alpha: 0.05,
+            min_change: 0.,
+            resamples: 100,
+            ignore_isolation: false,
+            out: None,
+        };
+        let baseline = result("Arm26", None, "base");
+        let mut candidate = result("Arm26", None, "cand");
+        candidate.outcome = Outcome::TimedOut;
+
+        let comparison = cmd.compare("Arm26", Some(&baseline), Some(&candidate));
+        assert_eq!(comparison.verdict, Verdict::Invalid);
+        let comparison = cmd.compare("Arm26", Some(&candidate), Some(&baseline));
+        assert_eq!(comparison.verdict, Verdict::Invalid);
+    }
+}
@@ -0,0 +1,134 @@
+use super::plot_cmd::{csv_lines, plain_lines};
+use crate::glob_to_regex;
+use crate::parse_logs::{ChannelDiff, Data, Diff};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use std::cmp::Ordering;
+use std::path::PathBuf;
+
+/// Compares the channels of two storage, motion, marker or csv files.
+#[derive(Debug, Args)]
+pub struct DiffCommand {
+    /// File to check, e.g. the opensim log of a result.
+    a: PathBuf,
+
+    /// Reference file.
+    b: PathBuf,
+
+    /// Only show channels matching this glob.
+    #[arg(long, short)]
+    channels: Option<String>,
+
+    /// Sort by this column, worst channels first.
+    #[arg(long, short, value_enum, default_value_t = DiffSort::Relative)]
+    sort: DiffSort,
+
+    /// Reverse the order.
+    #[arg(long)]
+    reverse: bool,
+
+    /// Only show this number of channels.
+    #[arg(long, short)]
+    limit: Option<usize>,
+
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = DiffFormat::Table)]
+    format: DiffFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffSort {
+    Label,
+    Rms,
+    Max,
+    Relative,
+    Overlap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    /// Aligned plain text.
+    Table,
+    Csv,
+    Json,
+}
+
+impl DiffCommand {
+    pub fn run(&self) -> Result<()> {
+        let a = Data::read_file(&self.a)?;
+        let b = Data::read_file(&self.b)?;
+        let mut diff = Diff::new(&a, &b);
+
+        if let Some(glob) = self.channels.as_deref() {
+            let regex = glob_to_regex(glob)?;
+            diff.channels.retain(|c| regex.is_match(&c.label));
+        }
+        diff.channels.sort_by(|x, y| self.compare(x, y));
+        if self.reverse {
+            diff.channels.reverse();
+        }
+        if let Some(limit) = self.limit {
+            diff.channels.truncate(limit);
+        }
+
+        if self.format == DiffFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+            return Ok(());
+        }
+
+        let header = [
+            "label",
+            "rms",
+            "max",
+            "time_of_max",
+            "relative",
+            "overlap",
+            "note",
+        ]
+        .map(String::from);
+        let format = |value: Option<f64>| value.map(|x| format!("{:.3e}", x)).unwrap_or_default();
+        let rows = diff
+            .channels
+            .iter()
+            .map(|c| {
+                vec![
+                    c.label.clone(),
+                    format(c.rms),
+                    format(c.max),
+                    c.time_of_max.map(|t| t.to_string()).unwrap_or_default(),
+                    format(c.relative),
+                    c.overlap
+                        .map(|x| format!("{:.0}%", x * 100.))
+                        .unwrap_or_default(),
+                    c.note().unwrap_or_default().to_owned(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let lines = match self.format {
+            DiffFormat::Csv => csv_lines(&header, &rows),
+            _ => plain_lines(&header, &rows),
+        };
+        for line in lines {
+            println!("{line}");
+        }
+        Ok(())
+    }
+
+    /// Orders the worst channels first: largest errors, smallest overlap, missing channels first.
+    fn compare(&self, x: &ChannelDiff, y: &ChannelDiff) -> Ordering {
+        let worst_first = |x: Option<f64>, y: Option<f64>| match (x, y) {
+            (Some(x), Some(y)) => y.total_cmp(&x),
+            (None, None) => Ordering::Equal,
+            (None, _) => Ordering::Less,
+            (_, None) => Ordering::Greater,
+        };
+        match self.sort {
+            DiffSort::Label => Ordering::Equal,
+            DiffSort::Rms => worst_first(x.rms, y.rms),
+            DiffSort::Max => worst_first(x.max, y.max),
+            DiffSort::Relative => worst_first(x.relative, y.relative),
+            DiffSort::Overlap => worst_first(x.overlap.map(|o| -o), y.overlap.map(|o| -o)),
+        }
+        .then_with(|| x.label.cmp(&y.label))
+    }
+}
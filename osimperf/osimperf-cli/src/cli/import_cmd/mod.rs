@@ -0,0 +1,54 @@
+use super::{absolute_path, ResultInfo, ResultStore};
+use crate::{find_file_by_name, RESULT_INFO_FILE_NAME};
+use anyhow::{Context, Result};
+use clap::Args;
+use log::{debug, info, warn};
+use osimperf_results::migrate::LEGACY_MONITOR_FILE_NAME;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct ImportCommand {
+    /// Path to directory in which to search for result files (default is current directory).
+    #[arg(long, short)]
+    results: Option<PathBuf>,
+
+    /// Path to the result store (or set OSIMPERF_DB).
+    #[arg(long)]
+    db: Option<PathBuf>,
+}
+
+impl ImportCommand {
+    pub fn run(&self) -> Result<()> {
+        info!("Start OSimPerf import command");
+        let mut store = ResultStore::open_arg_or_env_var(&self.db)?
+            .context("no result store given: use --db or set OSIMPERF_DB")?;
+        let dir = match self.results.as_ref() {
+            Some(dir) => absolute_path(dir)?,
+            None => std::env::current_dir()?,
+        };
+
+        let mut imported = 0;
+        let mut skipped = 0;
+
+        let mut paths = find_file_by_name(&dir, RESULT_INFO_FILE_NAME);
+        paths.extend(find_file_by_name(&dir, LEGACY_MONITOR_FILE_NAME));
+        paths.sort();
+        for path in paths {
+            debug!("Import {:?}", path);
+            // Results of older schema versions are upgraded when read.
+            match ResultInfo::read(&path) {
+                Ok(result) => {
+                    store.write_result(&path, &result)?;
+                    imported += 1;
+                }
+                Err(err) => {
+                    warn!("Skipping {:?}: {err:#}", path);
+                    skipped += 1;
+                }
+            }
+        }
+
+        info!("Imported {imported} results, skipped {skipped}");
+        Ok(())
+    }
+}
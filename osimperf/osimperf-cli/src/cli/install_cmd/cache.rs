@@ -0,0 +1,249 @@
+use crate::{git::read_tree_hash, Command, CommandTrait, EnvVar};
+use anyhow::Result;
+use log::{debug, info, warn};
+use osimperf_results::config_hash;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, File},
+    path::{Path, PathBuf},
+};
+
+/// Env var pointing to the build cache directory, see [BuildCache].
+pub static BUILD_CACHE_ENV_VAR: &str = "OSPC_BUILD_CACHE";
+
+/// Marks a dependencies install in the cache as complete.
+static DEPENDENCIES_TOUCH_FILE: &str = ".osimperf-dependencies";
+
+/// Env vars of the installer that change how the dependencies are built.
+static BUILD_ENV_VARS: [&str; 7] = [
+    "CC",
+    "CXX",
+    "CFLAGS",
+    "CXXFLAGS",
+    "CMAKE_BUILD_TYPE",
+    "OPENSIM_WITH_MOCO",
+    "OSPC_CMAKE_FLAGS",
+];
+
+/// Hash of the compiler and cmake flags of the dependencies, see [BuildCache].
+///
+/// The env vars of the installer take precedence over those of this process.
+pub fn dependencies_config_hash(env_vars: &[EnvVar], cmake_args: &[String]) -> u64 {
+    let env = BUILD_ENV_VARS.map(|key| {
+        env_vars
+            .iter()
+            .rev()
+            .find(|var| var.key == key)
+            .map(|var| var.value.clone())
+            .or_else(|| std::env::var(key).ok())
+    });
+    config_hash(&(env, cmake_args))
+}
+
+/// How the build cache was used by an install, stored in the install manifest.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Persistent build directory of the branch.
+    pub build_dir: Option<PathBuf>,
+    /// Git tree hash of the `dependencies/` source.
+    pub dependencies_tree: Option<String>,
+    /// The dependencies install of an earlier commit with the same tree hash was reused.
+    pub dependencies_reused: bool,
+    /// Number of compilations served by ccache.
+    pub ccache_hits: Option<u64>,
+    /// Number of compilations not found in ccache.
+    pub ccache_misses: Option<u64>,
+}
+
+/// Build cache shared by installs of different commits.
+///
+/// The cache directory contains a persistent build directory per branch (`build/BRANCH`), the
+/// dependencies installs by tree hash and build config (`dependencies/TREE-CONFIG`, or
+/// `dependencies/VARIANT/TREE-CONFIG` for a build variant), and the ccache (`ccache`). The build
+/// config is the hash of the compiler and cmake flags, see [dependencies_config_hash]. These are
+/// passed to the installer using `OSPC_BUILD_DIR`, `OSPC_OPENSIM_DEPENDENCIES_INSTALL_DIR` and
+/// `CCACHE_DIR`. `OSPC_BUILD_DEPENDENCIES` is set to `ON` if the dependencies are not yet cached.
+pub struct BuildCache {
+    env_vars: Vec<EnvVar>,
+    stats: CacheStats,
+    dependencies: Option<PathBuf>,
+    ccache_before: Option<HashMap<String, u64>>,
+}
+
+impl BuildCache {
+    pub fn new(
+        dir: Option<&Path>,
+        ccache: bool,
+        source: &Path,
+        commit: &str,
+        branch: Option<&str>,
+        variant: Option<&str>,
+        config: u64,
+    ) -> Result<Self> {
+        let mut out = Self {
+            env_vars: Vec::new(),
+            stats: CacheStats::default(),
+            dependencies: None,
+            ccache_before: None,
+        };
+
+        if let Some(dir) = dir {
+            let build_dir = dir
+                .join("build")
+                .join(branch.unwrap_or("detached").replace('/', "_"));
+            create_dir_all(&build_dir)?;
+            out.stats.build_dir = Some(build_dir);
+
+            match read_tree_hash(source, commit, "dependencies") {
+                Ok(tree) => {
+                    // The dependencies are built using the compiler and flags of the variant.
+                    let name = format!("{tree}-{config:016x}");
+                    let install = match variant {
+                        Some(variant) => dir.join("dependencies").join(variant).join(name),
+                        None => dir.join("dependencies").join(name),
+                    };
+                    let reused = install.join(DEPENDENCIES_TOUCH_FILE).exists();
+                    if reused {
+                        info!("Reusing dependencies install {:?}", install);
+                    }
+                    out.env_vars.push(EnvVar::new(
+                        "OSPC_OPENSIM_DEPENDENCIES_INSTALL_DIR",
+                        &install,
+                    ));
+                    out.env_vars.push(EnvVar {
+                        key: "OSPC_BUILD_DEPENDENCIES".to_owned(),
+                        value: if reused { "OFF" } else { "ON" }.to_owned(),
+                    });
+                    out.stats.dependencies_tree = Some(tree);
+                    out.stats.dependencies_reused = reused;
+                    out.dependencies = Some(install);
+                }
+                Err(err) => warn!("Not caching dependencies: {:#}", err),
+            }
+        }
+
+        if ccache {
+            if let Some(dir) = dir {
+                out.env_vars
+                    .push(EnvVar::new("CCACHE_DIR", &dir.join("ccache")));
+            }
+            // Read by cmake when configuring a new build directory.
+            for key in ["CMAKE_C_COMPILER_LAUNCHER", "CMAKE_CXX_COMPILER_LAUNCHER"] {
+                out.env_vars.push(EnvVar {
+                    key: key.to_owned(),
+                    value: "ccache".to_owned(),
+                });
+            }
+            // Statistics are not available before ccache 4.7, which need not fail the install.
+            out.ccache_before = read_ccache_stats(&out.env_vars)
+                .map_err(|err| {
+                    warn!(
+                        "Not collecting ccache statistics (requires ccache 4.7): {:#}",
+                        err
+                    )
+                })
+                .ok();
+        }
+        Ok(out)
+    }
+
+    /// Env vars for the installer, excluding the build directory.
+    pub fn env_vars(&self) -> &[EnvVar] {
+        &self.env_vars
+    }
+
+    /// Persistent build directory of the branch, if any.
+    pub fn build_dir(&self) -> Option<&Path> {
+        self.stats.build_dir.as_deref()
+    }
+
+    /// Dependencies install of the source tree, if any.
+    pub fn dependencies(&self) -> Option<&Path> {
+        self.dependencies.as_deref()
+    }
+
+    /// The dependencies install is complete, and need not be built.
+    pub fn dependencies_reused(&self) -> bool {
+        self.stats.dependencies_reused
+    }
+
+    /// Marks the dependencies as complete after a succesful install, and collects the ccache
+    /// statistics of this install.
+    pub fn finish(mut self) -> Result<CacheStats> {
+        if let Some(dependencies) = self.dependencies.as_ref() {
+            if dependencies.exists() && !self.stats.dependencies_reused {
+                File::create(dependencies.join(DEPENDENCIES_TOUCH_FILE))?;
+                debug!("Cached dependencies install {:?}", dependencies);
+            }
+        }
+        if let Some(before) = self.ccache_before.as_ref() {
+            match read_ccache_stats(&self.env_vars) {
+                Ok(after) => {
+                    let count = |keys: &[&str]| {
+                        keys.iter()
+                            .map(|key| {
+                                after
+                                    .get(*key)
+                                    .unwrap_or(&0)
+                                    .saturating_sub(*before.get(*key).unwrap_or(&0))
+                            })
+                            .sum()
+                    };
+                    self.stats.ccache_hits =
+                        Some(count(&["direct_cache_hit", "preprocessed_cache_hit"]));
+                    self.stats.ccache_misses = Some(count(&["cache_miss"]));
+                }
+                Err(err) => warn!("Failed to read ccache statistics: {:#}", err),
+            }
+        }
+        Ok(self.stats)
+    }
+}
+
+/// Reads the counters of `ccache --print-stats`.
+fn read_ccache_stats(env_vars: &[EnvVar]) -> Result<HashMap<String, u64>> {
+    let output = Command::parse("ccache --print-stats")
+        .set_envs(env_vars)
+        .run()?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('\t')?;
+            Some((key.to_owned(), value.trim().parse().ok()?))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(compiler: &str) -> Vec<EnvVar> {
+        ["CC", "CXX"]
+            .map(|key| EnvVar {
+                key: key.to_owned(),
+                value: compiler.to_owned(),
+            })
+            .to_vec()
+    }
+
+    #[test]
+    fn config_hash_depends_on_compiler_and_flags() {
+        let flags = [String::from("-DCMAKE_BUILD_TYPE=Release")];
+        let gcc = dependencies_config_hash(&env("gcc"), &flags);
+        assert_eq!(gcc, dependencies_config_hash(&env("gcc"), &flags));
+        assert_ne!(gcc, dependencies_config_hash(&env("clang"), &flags));
+        assert_ne!(gcc, dependencies_config_hash(&env("gcc"), &[]));
+    }
+
+    #[test]
+    fn config_hash_uses_last_env_var() {
+        let mut env_vars = env("clang");
+        env_vars.extend(env("gcc"));
+        assert_eq!(
+            dependencies_config_hash(&env_vars, &[]),
+            dependencies_config_hash(&env("gcc"), &[])
+        );
+    }
+}
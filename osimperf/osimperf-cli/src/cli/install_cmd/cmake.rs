@@ -0,0 +1,131 @@
+pub use osimperf_common::cmake::{CMakeConfig, CMakeConfigReader, CompilationTarget};
+
+use crate::EnvVar;
+use anyhow::{Context, Result};
+use log::{debug, info};
+use osimperf_common::{
+    cmake::{CMakeBuilder, CMakeCmds, CMakeConfigurerer, CMakeProgressStreamer},
+    copy_folder,
+};
+use std::{fs::create_dir_all, path::Path, time::Duration};
+
+/// Directory in the install root containing the dependencies install.
+static DEPENDENCIES_INSTALL_DIR: &str = "opensim-core-dependencies-install";
+
+/// Directory in the install root containing the configure and build logs of each target.
+static LOGS_DIR: &str = "logs";
+
+/// Installs opensim-core by running cmake directly, instead of an installer script.
+///
+/// Compiles the dependencies, opensim-core, and the tests in `source/` of the osimperf home. Each
+/// target is built in a subdirectory of the build directory, and its logs are written to
+/// `logs/TARGET` in the install root.
+pub struct NativeInstaller<'a> {
+    /// Path to the opensim-core source.
+    pub source: &'a Path,
+    /// Path to the osimperf home.
+    pub home: &'a Path,
+    pub build: &'a Path,
+    pub install: &'a Path,
+    /// Dependencies install in the build cache, copied into the install.
+    pub cached_dependencies: Option<&'a Path>,
+    /// The cached dependencies are complete, and need not be built.
+    pub dependencies_reused: bool,
+    pub config: &'a CMakeConfig,
+    pub env_vars: &'a [EnvVar],
+    /// Timeout of each configure and build step.
+    pub timeout: Option<Duration>,
+}
+
+impl NativeInstaller<'_> {
+    /// Runs all targets, returning the total duration.
+    pub fn run(&self) -> Result<Duration> {
+        let dependencies = self.install.join(DEPENDENCIES_INSTALL_DIR);
+        let mut duration = Duration::ZERO;
+        match self.cached_dependencies {
+            Some(cached) => {
+                if !self.dependencies_reused {
+                    duration += self.run_target(CompilationTarget::Dependencies, cached)?;
+                }
+                copy_folder(cached, &dependencies)?;
+            }
+            None => {
+                duration += self.run_target(CompilationTarget::Dependencies, &dependencies)?;
+            }
+        }
+        duration += self.run_target(CompilationTarget::OpenSimCore, &dependencies)?;
+        duration += self.run_target(CompilationTarget::TestsSource, &dependencies)?;
+        Ok(duration)
+    }
+
+    fn run_target(&self, target: CompilationTarget, dependencies: &Path) -> Result<Duration> {
+        let (source, install, dependency, cmake_target) = match target {
+            CompilationTarget::Dependencies => (
+                self.source.join("dependencies"),
+                dependencies.to_owned(),
+                None,
+                None,
+            ),
+            CompilationTarget::OpenSimCore => (
+                self.source.to_owned(),
+                self.install.to_owned(),
+                Some(dependencies.to_owned()),
+                Some("install"),
+            ),
+            CompilationTarget::TestsSource => (
+                self.home.join("source"),
+                self.install.to_owned(),
+                Some(self.install.to_owned()),
+                Some("install"),
+            ),
+        };
+
+        let mut args = self.config.cmake_args(target);
+        if target == CompilationTarget::OpenSimCore {
+            args.push(format!(
+                "-DOPENSIM_DEPENDENCIES_DIR={}",
+                dependencies.to_str().unwrap()
+            ));
+        }
+
+        let build = self.build.join(target.to_str());
+        let log_dir = self.install.join(LOGS_DIR).join(target.to_str());
+        create_dir_all(&build)?;
+        create_dir_all(&log_dir)?;
+
+        let mut cmds = CMakeCmds::new(
+            CMakeConfigurerer {
+                source,
+                build: build.clone(),
+                install,
+                dependency,
+                args: args.iter(),
+            },
+            CMakeBuilder {
+                build,
+                target: cmake_target,
+                num_jobs: self.config.num_jobs,
+            },
+        );
+        for var in self.env_vars {
+            cmds.add_env(&var.key, &var.value);
+        }
+        cmds.set_timeout(self.timeout);
+        debug!("Install {}:\n{}", target, cmds.print_pretty());
+
+        info!("Start installing {}", target);
+        let mut progress = CMakeProgressStreamer::new(|p| {
+            info!("Building {}: {}%", target, p);
+            Ok(())
+        });
+        let duration = cmds
+            .run(&mut progress, &log_dir)
+            .with_context(|| format!("failed to install {}, see logs in {:?}", target, log_dir))?;
+        info!(
+            "Finished installing {} in {} minutes.",
+            target,
+            duration.as_secs() / 60
+        );
+        Ok(duration)
+    }
+}
@@ -1,20 +1,29 @@
 use crate::{git::format_date, *};
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use clap::Args;
 use log::{debug, info, log_enabled, trace, warn};
 use serde::{Deserialize, Serialize};
 use std::{
     env::current_dir,
-    fs::{create_dir_all, File},
-    io::Write,
+    fs::{create_dir_all, remove_file},
     path::{absolute, Path, PathBuf},
+    time::Duration,
 };
 
 use super::arg_or_env_var;
 
+mod cache;
+mod cmake;
+mod variant;
+
+use cache::{dependencies_config_hash, BuildCache, CacheStats, BUILD_CACHE_ENV_VAR};
+use cmake::{CMakeConfigReader, CompilationTarget, NativeInstaller};
+use variant::BuildVariant;
+
 /// OSimPerf install command.
 ///
-/// Executes installer script with `OSIMPERF_INSTALL`, `OSIMPERF_OPENSIM_SRC` set.
+/// Executes installer script with `OSIMPERF_INSTALL`, `OSIMPERF_OPENSIM_SRC` set, or runs cmake
+/// directly using `--native`.
 #[derive(Debug, Args)]
 pub struct InstallCommand {
     /// Name of project.
@@ -25,6 +34,17 @@ pub struct InstallCommand {
     #[arg(long, short)]
     installer: Option<PathBuf>,
 
+    /// Install by running cmake directly, instead of an installer script.
+    ///
+    /// Builds the dependencies, opensim-core and the tests in `source/` of the osimperf home,
+    /// using the cmake flags in `compile-flags/`.
+    #[arg(long, conflicts_with = "installer")]
+    native: bool,
+
+    /// Path to the osimperf home used by `--native`, defaults to current directory.
+    #[arg(long, requires = "native")]
+    home: Option<PathBuf>,
+
     /// Path to opensim-core repo.
     #[arg(long, short)]
     opensim: Option<PathBuf>,
@@ -33,36 +53,116 @@ pub struct InstallCommand {
     #[arg(long, short)]
     root: Option<PathBuf>,
 
-    /// Path to build dir.
+    /// Path to build dir (defaults to a temporary directory unique to the install directory).
     #[arg(long, short)]
     build: Option<PathBuf>,
 
     /// Force reinstalling.
     #[arg(long, short)]
     force: bool,
+
+    /// Timeout in minutes, after which the installer is killed.
+    ///
+    /// With `--native`, the timeout applies to each configure and build step instead.
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Directory for caching builds between installs (or set `OSPC_BUILD_CACHE`).
+    ///
+    /// Keeps a build directory per branch, and reuses the dependencies install if
+    /// `dependencies/`, the compiler and the cmake flags did not change.
+    #[arg(long)]
+    cache: Option<PathBuf>,
+
+    /// Compile using ccache, placing the ccache in the cache directory if given.
+    #[arg(long)]
+    ccache: bool,
+
+    /// Json file with build variants, each variant is installed separately.
+    ///
+    /// A variant is a named set of cmake flags and env vars. The name of the variant is added to
+    /// the install directory (or a subdirectory of `--root`), and to the build directory.
+    #[arg(long)]
+    variants: Option<PathBuf>,
+
+    /// Only install these variants from `--variants`.
+    #[arg(long, requires = "variants", value_delimiter = ',')]
+    variant: Vec<String>,
 }
 
 fn map_absolute(relative: &Option<PathBuf>) -> Option<PathBuf> {
     relative
         .as_ref()
-        .map(|p| absolute(p).expect(&format!("Failed to create absolute path to {:?}", p)))
+        .map(|p| {
+            absolute(p).unwrap_or_else(|_| panic!("Failed to create absolute path to {:?}", p))
+        })
 }
 
 impl InstallCommand {
+    pub fn new(
+        name: String,
+        installer: Option<PathBuf>,
+        opensim: PathBuf,
+        root: PathBuf,
+        build: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            name,
+            installer,
+            native: false,
+            home: None,
+            opensim: Some(opensim),
+            root: Some(root),
+            build,
+            force: false,
+            timeout: None,
+            cache: None,
+            ccache: false,
+            variants: None,
+            variant: Vec::new(),
+        }
+    }
+
     pub fn run(&self) -> Result<()> {
+        let Some(path) = self.variants.as_ref() else {
+            return self.install(None);
+        };
+        let variants = BuildVariant::read_all(path, &self.variant)
+            .with_context(|| format!("failed to read build variants from {:?}", path))?;
+        for variant in variants.iter() {
+            info!("Installing build variant {}", variant.name);
+            self.install(Some(variant))
+                .with_context(|| format!("failed to install build variant {}", variant.name))?;
+        }
+        Ok(())
+    }
+
+    fn install(&self, variant: Option<&BuildVariant>) -> Result<()> {
         // Get path to opensim-core source from argument or environmental variable.
         let source = arg_or_env_var(self.opensim.clone(), "OSPC_OPENSIM_SRC")?
             .context("failed to get path to opensim-source")?;
         trace!("Path to OpenSim-core source = {:?}", source);
 
         let commit = crate::common::git::read_current_commit(&source)?;
-        let date = format_date(&crate::common::git::get_date(&source, &commit)?);
+        let commit_date = crate::common::git::get_date(&source, &commit)?;
+        let date = format_date(&commit_date);
 
         let mut install_info = InstallInfo {
             name: self.name.clone(),
             commit: commit.clone(),
             date: date.clone(),
             duration: 0,
+            compiler: None,
+            build_type: None,
+            cmake_flags: Vec::new(),
+            source_url: crate::common::git::read_repo_url(&source).ok(),
+            // Not on a branch if HEAD is detached.
+            branch: crate::common::git::read_current_branch(&source)
+                .ok()
+                .filter(|branch| branch != "HEAD"),
+            size: None,
+            cache: None,
+            variant: variant.map(|v| v.name.clone()),
         };
 
         info!(
@@ -71,68 +171,158 @@ impl InstallCommand {
         );
 
         // Use directory of config file as root for installer.
-        let install_root = if let Some(root) = map_absolute(&self.root) {
-            root
-        } else {
-            current_dir()?.join(format!("install_{}_{}_{}", self.name, date, commit))
+        let install_root = match (map_absolute(&self.root), variant) {
+            (Some(root), Some(variant)) => root.join(&variant.name),
+            (Some(root), None) => root,
+            (None, Some(variant)) => current_dir()?.join(format!(
+                "install_{}_{}_{}_{}",
+                self.name, date, commit, variant.name
+            )),
+            (None, None) => {
+                current_dir()?.join(format!("install_{}_{}_{}", self.name, date, commit))
+            }
         };
 
         trace!("Installer root = {:?}", install_root);
 
         // Check if already installed.
-        if let Ok(prev_version) = Command::parse(&format!(
-            "{}/bin/osimperf-install-info commit",
-            install_root.to_str().unwrap()
-        ))
-        .run_trim()
-        {
-            if !self.force && prev_version == commit {
+        if let Ok(prev) = InstallInfo::read(&install_root) {
+            if !self.force && prev.commit == commit {
                 info!("Found installed commit {} ({}).", commit, date);
-                install_info.duration = Command::parse(&format!(
-                    "{}/bin/osimperf-install-info duration",
-                    install_root.to_str().unwrap()
-                ))
-                .run_trim()?
-                .parse::<u64>()?;
-                install_info.install(&install_root)?;
+                install_info.duration = prev.duration;
+                install_info.compiler = prev.compiler;
+                install_info.build_type = prev.build_type;
+                install_info.cmake_flags = prev.cmake_flags;
+                install_info.size = prev.size.or_else(|| folder_size(&install_root).ok());
+                install_info.cache = prev.cache;
+                install_info.write(&install_root)?;
                 print_prefix_path(&install_root);
                 return Ok(());
             }
             warn!(
                 "Overwriting previously installed commit {} ({}).",
-                prev_version, date
+                prev.commit, date
             );
         }
 
+        // The native install reads the cmake flags from the home directory.
+        let native = if self.native {
+            let home = map_absolute(&self.home).map_or_else(current_dir, Ok)?;
+            let mut config = CMakeConfigReader::read(&home)
+                .with_context(|| format!("failed to read cmake config from home {:?}", home))?
+                .get(&commit_date)
+                .clone();
+            if let Some(variant) = variant {
+                config.add_flags(&variant.cmake_flags);
+            }
+            trace!("CMake config = {:#?}", config);
+            Some((home, config))
+        } else {
+            None
+        };
+
         // Set environmental variables.
         let mut env_vars = vec![EnvVar::new("OSPC_OPENSIM_SRC", &source)];
-        if let Some(build) = map_absolute(&self.build) {
-            env_vars.push(EnvVar::new("OSPC_BUILD_DIR", &build));
+        let variant_env_vars = variant.map(BuildVariant::env_vars).unwrap_or_default();
+        let dependencies_config = dependencies_config_hash(
+            &variant_env_vars,
+            &native
+                .as_ref()
+                .map(|(_, config)| config.cmake_args(CompilationTarget::Dependencies))
+                .unwrap_or_default(),
+        );
+        let cache_dir = map_absolute(
+            &self
+                .cache
+                .clone()
+                .or_else(|| std::env::var_os(BUILD_CACHE_ENV_VAR).map(PathBuf::from)),
+        );
+        let cache = BuildCache::new(
+            cache_dir.as_deref(),
+            self.ccache,
+            &source,
+            &commit,
+            install_info.branch.as_deref(),
+            install_info.variant.as_deref(),
+            dependencies_config,
+        )?;
+        env_vars.extend(cache.env_vars().iter().cloned());
+        env_vars.extend(variant_env_vars);
+        // Build directory used by the installer, for reading the compiler and build type.
+        let mut build_dir =
+            match map_absolute(&self.build).or_else(|| cache.build_dir().map(Path::to_path_buf)) {
+                Some(build) => Some(build),
+                None => arg_or_env_var(None, "OSPC_BUILD_DIR")?.or_else(|| {
+                    self.installer
+                        .is_none()
+                        .then(|| default_build_dir(&install_root))
+                }),
+            };
+        // Each variant has its own build directory.
+        if let (Some(build), Some(variant)) = (build_dir.as_mut(), variant) {
+            build.push(&variant.name);
+        }
+        if let Some(build) = build_dir.as_ref() {
+            env_vars.push(EnvVar::new("OSPC_BUILD_DIR", build));
         }
 
         create_dir_all(&install_root)?;
         debug!("Created install directory {:?}", install_root);
 
-        let installer: String = map_absolute(&self.installer)
-            .map(|p| p.to_str().unwrap().to_owned())
-            .unwrap_or("osimperf-default-install-opensim".to_owned());
-        let cmd = Command::new(installer)
-            .set_envs(&env_vars)
-            .set_run_root(&install_root);
-        debug!("Run installer:\n{}", cmd.print_command());
-
-        let duration = if log_enabled!(log::Level::Trace) {
-            cmd.run_and_stream(&mut std::io::stdout())
+        let timeout = self
+            .timeout
+            .map(|minutes| Duration::from_secs(minutes * 60));
+        let duration = if let Some((home, config)) = native.as_ref() {
+            NativeInstaller {
+                source: &source,
+                home,
+                build: build_dir.as_deref().unwrap(),
+                install: &install_root,
+                cached_dependencies: cache.dependencies(),
+                dependencies_reused: cache.dependencies_reused(),
+                config,
+                env_vars: &env_vars,
+                timeout,
+            }
+            .run()?
         } else {
-            cmd.run_and_time()
-        }
-        .and_then(|output| output.into_duration())
-        .with_context(|| format!("installer failed: {:#?}", cmd.print_command()))?;
+            let installer: String = map_absolute(&self.installer)
+                .map(|p| p.to_str().unwrap().to_owned())
+                .unwrap_or("osimperf-default-install-opensim".to_owned());
+            let mut cmd = Command::new(installer)
+                .set_envs(&env_vars)
+                .set_run_root(&install_root);
+            cmd.set_timeout(timeout);
+            debug!("Run installer:\n{}", cmd.print_command());
+
+            if log_enabled!(log::Level::Trace) {
+                cmd.run_and_stream(&mut std::io::stdout())
+            } else {
+                cmd.run_and_time()
+            }
+            .and_then(|output| output.into_duration())
+            .with_context(|| format!("installer failed: {:#?}", cmd.print_command()))?
+        };
 
         debug!("Installer finished");
 
         install_info.duration = duration.as_secs();
-        install_info.install(&install_root)?;
+        if let Some(build_dir) = build_dir.as_ref() {
+            let build_info = CMakeBuildInfo::read(build_dir);
+            install_info.compiler = build_info.compiler;
+            install_info.build_type = build_info.build_type;
+            install_info.cmake_flags = build_info.flags;
+        }
+        if install_info.compiler.is_none() || install_info.build_type.is_none() {
+            warn!("Could not read compiler and build type from the cmake cache in the build dir: {:?}", build_dir);
+        }
+        install_info.size = folder_size(&install_root)
+            .map_err(|err| warn!("Failed to read the disk size of the install: {:#}", err))
+            .ok();
+        if cache_dir.is_some() || self.ccache {
+            install_info.cache = Some(cache.finish()?);
+        }
+        install_info.write(&install_root)?;
 
         info!(
             "Finished installing {} ({}) in {} minutes.",
@@ -147,21 +337,136 @@ impl InstallCommand {
     }
 }
 
+/// Manifest of an install, written to `bin/osimperf-install-info.json` in the install root.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InstallInfo {
     pub name: String,
     pub commit: String,
     pub date: String,
+    /// Install duration in seconds.
     pub duration: u64,
+    #[serde(default)]
+    pub compiler: Option<String>,
+    #[serde(default)]
+    pub build_type: Option<String>,
+    /// The cmake options of the build, as `-DKEY=VALUE`.
+    #[serde(default)]
+    pub cmake_flags: Vec<String>,
+    /// Url of the `origin` remote of the opensim-core source.
+    #[serde(default)]
+    pub source_url: Option<String>,
+    /// Branch of the opensim-core source, if any.
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Disk size of the install in megabytes.
+    #[serde(default)]
+    pub size: Option<usize>,
+    /// Use of the build cache, if any.
+    #[serde(default)]
+    pub cache: Option<CacheStats>,
+    /// Name of the build variant, if any.
+    #[serde(default)]
+    pub variant: Option<String>,
+}
+
+/// Prefix of the build directory of the default installer script, in the temporary directory.
+static DEFAULT_INSTALLER_BUILD_DIR_PREFIX: &str = "osimperf-opensim-build";
+
+/// Build directory of the default installer script, unique for each install directory.
+fn default_build_dir(install_root: &Path) -> PathBuf {
+    let name = install_root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "{DEFAULT_INSTALLER_BUILD_DIR_PREFIX}-{name}-{:x}",
+        osimperf_results::config_hash(install_root)
+    ))
 }
 
+/// Bash script that older versions installed instead of the manifest.
+pub static LEGACY_INSTALL_INFO_SCRIPT: &str = "osimperf-install-info";
+
 fn print_prefix_path(path: &Path) {
     let dir = path.to_str().unwrap();
     println!("Don't forget to prefix the path:\n{dir}/bin:{dir}/include:$PATH");
 }
 
 impl InstallInfo {
-    pub fn try_read(cmd: &str) -> Result<Self> {
+    /// Path to the manifest of the install at root.
+    pub fn manifest_path(root: &Path) -> PathBuf {
+        root.join("bin").join(INSTALL_INFO_FILE_NAME)
+    }
+
+    /// Reads the manifest of the install at root, or its legacy `osimperf-install-info` script.
+    pub fn read(root: &Path) -> Result<Self> {
+        let manifest = Self::manifest_path(root);
+        if manifest.exists() {
+            return read_json(&manifest);
+        }
+        let script = root.join("bin").join(LEGACY_INSTALL_INFO_SCRIPT);
+        ensure!(script.exists(), "no install found at {:?}", root);
+        debug!("Reading legacy install info from {:?}", script);
+        Self::read_legacy(&quote(script.to_str().unwrap()))
+    }
+
+    /// Finds the first install on PATH, returning its root and manifest.
+    pub fn find_on_path() -> Result<(PathBuf, Self)> {
+        let path = std::env::var_os("PATH").context("PATH is not set")?;
+        let bin = std::env::split_paths(&path)
+            .find(|dir| {
+                dir.join(INSTALL_INFO_FILE_NAME).exists()
+                    || dir.join(LEGACY_INSTALL_INFO_SCRIPT).exists()
+            })
+            .context("no install found on PATH")?;
+        let root = absolute(&bin)?
+            .parent()
+            .context("install bin directory has no parent")?
+            .to_owned();
+        trace!("Found install on PATH: {:?}", root);
+        Ok((root.clone(), Self::read(&root)?))
+    }
+
+    /// Finds the installs in dir, returning the absolute path to their manifest (or legacy
+    /// script), and their manifest.
+    ///
+    /// Skips installs that are being removed, see [remove_folder].
+    pub fn find_all(dir: &Path) -> Result<Vec<(PathBuf, Self)>> {
+        // Older installs have a script instead of the manifest.
+        let mut paths = find_file_by_name(dir, INSTALL_INFO_FILE_NAME);
+        paths.extend(
+            find_file_by_name(dir, LEGACY_INSTALL_INFO_SCRIPT)
+                .into_iter()
+                .filter(|script| !script.with_file_name(INSTALL_INFO_FILE_NAME).exists()),
+        );
+
+        let mut out = Vec::new();
+        for path in paths {
+            let path = absolute(path)?;
+            if path.components().any(|c| {
+                c.as_os_str()
+                    .to_str()
+                    .is_some_and(|c| c.starts_with(REMOVING_FOLDER_PREFIX))
+            }) {
+                continue;
+            }
+            let root = path
+                .parent()
+                .and_then(|bin| bin.parent())
+                .context("install info not in a bin directory")?;
+            let info = Self::read(root)?;
+            out.push((path, info));
+        }
+        Ok(out)
+    }
+
+    /// Root directory of the install, from the path to its manifest.
+    pub fn root_of(manifest: &Path) -> &Path {
+        manifest.parent().and_then(|bin| bin.parent()).unwrap()
+    }
+
+    /// Reads the install info by running the legacy script once per field.
+    fn read_legacy(cmd: &str) -> Result<Self> {
         Ok(Self {
             name: Command::parse(&format!("{cmd} name")).run_trim()?,
             commit: Command::parse(&format!("{cmd} commit")).run_trim()?,
@@ -169,56 +474,45 @@ impl InstallInfo {
             duration: Command::parse(&format!("{cmd} duration"))
                 .run_trim()?
                 .parse::<u64>()?,
+            // Not available for older installs.
+            compiler: Command::parse(&format!("{cmd} compiler"))
+                .run_trim()
+                .ok()
+                .filter(|s| !s.is_empty()),
+            build_type: Command::parse(&format!("{cmd} build_type"))
+                .run_trim()
+                .ok()
+                .filter(|s| !s.is_empty()),
+            cmake_flags: Vec::new(),
+            source_url: None,
+            branch: None,
+            size: None,
+            cache: None,
+            variant: None,
         })
     }
 
-    pub fn install(&self, path: &Path) -> Result<()> {
-        let install_path = path.join("bin").join("osimperf-install-info");
-        println!("install_path = {:?}", install_path);
-
-        let mut line = Vec::<String>::new();
-
-        line.push("#!/bin/bash".to_owned());
-        // line.push("set -eo".to_owned());
-
-        let mut line_opt_a = Vec::<String>::new();
-        let mut line_opt_b = Vec::<String>::new();
-
-        line_opt_a.push(r#"if [ "$#" -eq 1 ] ; then"#.to_owned());
-        let duration = format!("{}", self.duration);
-
-        let prefix_path = "\"$(dirname $0)\"".to_owned();
-        let root = "\"$(dirname $(dirname $0))\"".to_owned();
-        for (key, value) in [
-            ("name", &self.name),
-            ("commit", &self.commit),
-            ("date", &self.date),
-            ("duration", &duration),
-            ("prefix_path", &prefix_path),
-            ("root", &root),
-        ] {
-            line_opt_a.push(format!("  if [ $1 == \"{}\" ] ; then", key));
-            line_opt_a.push(format!("    echo {}", value));
-            line_opt_a.push("    exit 0".to_owned());
-            line_opt_a.push("  fi".to_owned());
-            line_opt_b.push(format!("echo \"{},{}\"", key, value));
+    /// Build info of the install, see [Fingerprint].
+    pub fn build_info(&self) -> CMakeBuildInfo {
+        CMakeBuildInfo {
+            compiler: self.compiler.clone(),
+            build_type: self.build_type.clone(),
+            flags: self.cmake_flags.clone(),
         }
-        line_opt_a.push(r#"  echo "Unknown key passed.""#.to_owned());
-        line_opt_a.push("  exit 1".to_owned());
-        line_opt_a.push("fi".to_owned());
-
-        line.extend(line_opt_a.drain(..));
-        line.extend(line_opt_b.drain(..));
+    }
 
-        let mut file = File::create(&install_path)?;
-        for l in line.iter() {
-            file.write_all(l.as_bytes())?;
-            file.write_all("\n".as_bytes())?;
+    /// Writes the manifest to the install at root, replacing any legacy script.
+    pub fn write(&self, root: &Path) -> Result<()> {
+        let path = Self::manifest_path(root);
+        create_dir_all(path.parent().unwrap())?;
+        write_json(&path, self)?;
+        debug!("Install info written to {:?}", path);
+
+        let script = root.join("bin").join(LEGACY_INSTALL_INFO_SCRIPT);
+        if script.exists() {
+            remove_file(&script)?;
+            debug!("Removed legacy install info script {:?}", script);
         }
-
-        Command::parse(&format!("chmod +x {}", install_path.to_str().unwrap())).run_trim()?;
-        debug!("osimperf-install-info written to {:?}", install_path);
-
         Ok(())
     }
 }
@@ -0,0 +1,84 @@
+use crate::{read_json, EnvVar};
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+/// A named build configuration, e.g. a build type, compiler or optimization flags.
+///
+/// Read from a json file containing a list of variants:
+///
+/// ```json
+/// [
+///     {"name": "release", "cmake_flags": ["-DCMAKE_BUILD_TYPE=Release"]},
+///     {"name": "clang", "env": {"CC": "clang", "CXX": "clang++"}}
+/// ]
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BuildVariant {
+    /// Name of the variant, used in the install and build directories.
+    pub name: String,
+    /// Added cmake options, as `-DKEY=VALUE`.
+    #[serde(default)]
+    pub cmake_flags: Vec<String>,
+    /// Env vars of the installer.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl BuildVariant {
+    /// Reads the variants from file, keeping only the selected names if any.
+    pub fn read_all(path: &Path, names: &[String]) -> Result<Vec<Self>> {
+        let mut variants: Vec<Self> = read_json(path)?;
+        for (i, variant) in variants.iter().enumerate() {
+            ensure!(
+                !variant.name.is_empty() && !variant.name.contains(['/', '\\']),
+                "invalid name of build variant {:?} in {:?}",
+                variant.name,
+                path
+            );
+            // Variants with the same name would install into the same directory.
+            ensure!(
+                variants[..i].iter().all(|v| v.name != variant.name),
+                "duplicate name of build variant {:?} in {:?}",
+                variant.name,
+                path
+            );
+        }
+        for name in names {
+            ensure!(
+                variants.iter().any(|v| &v.name == name),
+                "build variant {name} not found in {:?}",
+                path
+            );
+        }
+        if !names.is_empty() {
+            variants.retain(|v| names.contains(&v.name));
+        }
+        ensure!(
+            !variants.is_empty(),
+            "no build variants found in {:?}",
+            path
+        );
+        Ok(variants)
+    }
+
+    /// Env vars of the installer, including the cmake flags in `OSPC_CMAKE_FLAGS` (one per line).
+    pub fn env_vars(&self) -> Vec<EnvVar> {
+        let mut env_vars = self
+            .env
+            .iter()
+            .map(|(key, value)| EnvVar {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect::<Vec<_>>();
+        env_vars.push(EnvVar {
+            key: CMAKE_FLAGS_ENV_VAR.to_owned(),
+            value: self.cmake_flags.join("\n"),
+        });
+        env_vars
+    }
+}
+
+/// Env var passing the cmake flags of a variant to the installer script.
+static CMAKE_FLAGS_ENV_VAR: &str = "OSPC_CMAKE_FLAGS";
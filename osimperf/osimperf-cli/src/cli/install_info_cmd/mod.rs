@@ -0,0 +1,57 @@
+use super::{absolute_path, InstallInfo};
+use anyhow::{Context, Result};
+use clap::Args;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Prints the manifest of an install, see [InstallInfo].
+///
+/// Reads `bin/osimperf-install-info.json`, or the `osimperf-install-info` script of older installs.
+#[derive(Debug, Args)]
+pub struct InstallInfoCommand {
+    /// Field to print (e.g. `commit` or `root`), prints all fields as json if omitted.
+    key: Option<String>,
+
+    /// Root directory of the install, defaults to the install found on PATH.
+    #[arg(long, short)]
+    install: Option<PathBuf>,
+}
+
+impl InstallInfoCommand {
+    pub fn run(&self) -> Result<()> {
+        let (root, info) = match self.install.as_ref() {
+            Some(path) => {
+                let root = absolute_path(path)?;
+                let info = InstallInfo::read(&root)?;
+                (root, info)
+            }
+            None => InstallInfo::find_on_path()?,
+        };
+
+        let mut fields = serde_json::to_value(&info)?;
+        fields["root"] = json!(root);
+        fields["prefix_path"] = json!(root.join("bin"));
+
+        let Some(key) = self.key.as_ref() else {
+            println!("{}", serde_json::to_string_pretty(&fields)?);
+            return Ok(());
+        };
+        match fields
+            .get(key)
+            .with_context(|| format!("unknown key: {key}"))?
+        {
+            Value::Null => {}
+            Value::String(s) => println!("{s}"),
+            Value::Array(values) => println!(
+                "{}",
+                values
+                    .iter()
+                    .map(|v| v.as_str().map_or_else(|| v.to_string(), String::from))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            value => println!("{value}"),
+        }
+        Ok(())
+    }
+}
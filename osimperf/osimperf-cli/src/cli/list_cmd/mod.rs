@@ -1,9 +1,14 @@
+mod query;
+mod records;
+
 use crate::*;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Args;
-use std::path::{absolute, PathBuf};
+use query::{Query, QueryArgs};
+use records::{compare_values, print_records, ListFormat, Record};
+use std::path::{Path, PathBuf};
 
-use super::{InstallInfo, ResultInfo};
+use super::{InstallInfo, ReadBenchTestSetup, ResultInfo, ResultStore};
 
 #[derive(Debug, Args)]
 pub struct ListCommand {
@@ -11,14 +16,6 @@ pub struct ListCommand {
     #[arg(long, short)]
     install: Option<PathBuf>,
 
-    /// Filter by date.
-    #[arg(long, short)]
-    date: Option<String>,
-
-    /// Filter by commit.
-    #[arg(long, short)]
-    commit: Option<String>,
-
     /// Path to results directory.
     #[arg(long, short)]
     results: Option<PathBuf>,
@@ -27,83 +24,153 @@ pub struct ListCommand {
     #[arg(long, short)]
     tests: Option<PathBuf>,
 
+    /// List the results in the result store at this path, instead of searching for result files.
+    #[arg(long, conflicts_with = "results")]
+    db: Option<PathBuf>,
+
+    /// Print the opensim log of each result, instead of the result path.
     #[arg(long, short)]
     opensim_log: bool,
+
+    /// Output format.
+    #[arg(long, short, value_enum, default_value_t = ListFormat::Path)]
+    format: ListFormat,
+
+    /// Sort by this field.
+    #[arg(long, short)]
+    sort: Option<String>,
+
+    /// Reverse the order.
+    #[arg(long)]
+    reverse: bool,
+
+    /// Fields to print (comma separated), all fields if empty.
+    #[arg(long, value_delimiter = ',')]
+    fields: Vec<String>,
+
+    #[command(flatten)]
+    query: QueryArgs,
 }
 
 impl ListCommand {
     pub fn run(&mut self) -> Result<()> {
-        if (self.install.is_none() && self.results.is_none()) && self.tests.is_none() {
+        if (self.install.is_none() && self.results.is_none())
+            && self.tests.is_none()
+            && self.db.is_none()
+        {
             let dir = std::env::current_dir()?;
             self.install = Some(dir.clone());
             self.results = Some(dir.clone());
             self.tests = Some(dir.clone());
         }
 
+        let query = self.query.parse()?;
+        let mut records = Vec::new();
         if let Some(install) = self.install.as_ref() {
-            let mut arr = Vec::new();
-            for path in find_file_by_name(install, "osimperf-install-info")
-                .drain(..)
-                .map(|path| absolute(path).expect("failed to create absolute path"))
-            {
-                let cmd = path.to_str().unwrap().to_owned();
-                arr.push((
-                    InstallInfo {
-                        name: Command::parse(&format!("{cmd} name")).run_trim()?,
-                        commit: Command::parse(&format!("{cmd} commit")).run_trim()?,
-                        date: Command::parse(&format!("{cmd} date")).run_trim()?,
-                        duration: 0,
-                    },
-                    cmd
-                ));
-            }
-            arr.sort_by(|(a, _), (b, _)| a.date.cmp(&b.date));
-            if let Some(date) = self.date.as_ref() {
-                arr.retain(|(c, _)| &c.date == date);
-            }
-            if let Some(commit) = self.commit.as_ref() {
-                arr.retain(|(c, _)| &c.commit == commit);
-            }
-            for path in arr.iter().rev().map(|(_, p)| p) {
-                println!("{}", path);
+            records.extend(self.sorted(self.list_installs(install)?, &query)?);
+        }
+        if let Some(tests) = self.tests.as_ref() {
+            records.extend(self.sorted(self.list_tests(tests, &query)?, &query)?);
+        }
+        let results = match (self.db.as_ref(), self.results.as_ref()) {
+            (Some(db), _) => Some(self.list_stored_results(db)?),
+            (None, Some(results)) => Some(self.list_results(results, &query)?),
+            (None, None) => None,
+        };
+        if let Some(results) = results {
+            let mut results = self.sorted(results, &query)?;
+            if self.opensim_log && self.format == ListFormat::Path {
+                results = results
+                    .iter()
+                    .filter_map(|r| r.get("opensim_log").as_str().map(PathBuf::from))
+                    .map(|log| Record::path("result", &log))
+                    .collect();
             }
-            return Ok(());
+            records.extend(results);
         }
 
-        if let Some(tests) = self.tests.as_ref() {
-            let mut arr = find_file_by_name(tests, "osimperf-test.conf");
-            arr.sort_by(|a, b| a.to_str().unwrap().cmp(b.to_str().unwrap()));
-            for file in arr.iter() {
-                println!("{}", file.to_str().unwrap());
+        if !self.fields.is_empty() {
+            for record in records.iter_mut() {
+                record.select(&self.fields);
             }
         }
+        print_records(&records, self.format)
+    }
+
+    /// Installs, newest first.
+    fn list_installs(&self, install: &Path) -> Result<Vec<Record>> {
+        let mut arr = InstallInfo::find_all(install)?;
+        arr.sort_by(|(_, a), (_, b)| b.date.cmp(&a.date));
+
+        let mut records = Vec::new();
+        for (path, info) in arr {
+            // Only measure the disk size if it is printed, and missing from the manifest.
+            let size = match self.format {
+                ListFormat::Path => None,
+                _ => info
+                    .size
+                    .map(Ok)
+                    .or_else(|| Some(folder_size(InstallInfo::root_of(&path))))
+                    .transpose()?,
+            };
+            records.push(Record::install(&info, &path, size));
+        }
+        Ok(records)
+    }
 
-        if let Some(results) = self.results.as_ref() {
-            let mut arr = find_file_by_name(results, super::ResultInfo::filename());
-            arr.sort_by(|a, b| a.to_str().unwrap().cmp(b.to_str().unwrap()));
-            if let Some(commit) = self.commit.as_ref() {
-                arr.retain(|path| {
-                    let result_info = read_json::<ResultInfo>(&path).expect(&format!(
-                        "failed to read result info from {}",
-                        path.display()
-                    ));
-                    &result_info.commit == commit
-                });
+    fn list_tests(&self, tests: &Path, query: &Query) -> Result<Vec<Record>> {
+        let mut arr = find_file_by_name(tests, "osimperf-test.conf");
+        arr.sort_by(|a, b| a.to_str().unwrap().cmp(b.to_str().unwrap()));
+        let mut records = Vec::new();
+        for path in arr {
+            if self.format == ListFormat::Path && self.sort.is_none() && !query.is_active() {
+                records.push(Record::path("test", &path));
+                continue;
             }
-            for file in arr.iter() {
-                if self.opensim_log {
-                    let result_info = read_json::<ResultInfo>(&file).expect(&format!(
-                        "failed to read result info from {}",
-                        file.display()
-                    ));
-                    if let Some(log) = result_info.opensim_log {
-                println!("{}", log.to_str().unwrap());
-                    }
-                } else {
-                println!("{}", file.to_str().unwrap());
-                }
+            let setup = read_json::<ReadBenchTestSetup>(&path)
+                .with_context(|| format!("failed to read test config from {}", path.display()))?;
+            records.push(Record::test(&setup, &path)?);
+        }
+        Ok(records)
+    }
+
+    fn list_results(&self, results: &Path, query: &Query) -> Result<Vec<Record>> {
+        let mut arr = find_file_by_name(results, ResultInfo::filename());
+        arr.sort_by(|a, b| a.to_str().unwrap().cmp(b.to_str().unwrap()));
+        let mut records = Vec::new();
+        // Bare paths do not require reading the results.
+        let read = self.format != ListFormat::Path
+            || self.opensim_log
+            || self.sort.is_some()
+            || query.is_active();
+        for path in arr {
+            if !read {
+                records.push(Record::path("result", &path));
+                continue;
             }
+            let result_info = ResultInfo::read(&path)?;
+            records.push(Record::result(&result_info, &path));
+        }
+        Ok(records)
+    }
+
+    fn list_stored_results(&self, db: &Path) -> Result<Vec<Record>> {
+        Ok(ResultStore::open(db)?
+            .read_results()?
+            .iter()
+            .map(|(path, result_info)| Record::result(result_info, path))
+            .collect())
+    }
+
+    /// Filters and sorts the records.
+    fn sorted(&self, mut records: Vec<Record>, query: &Query) -> Result<Vec<Record>> {
+        query.apply(&mut records)?;
+        if let Some(key) = self.sort.as_ref() {
+            records.sort_by(|a, b| compare_values(a.get(key), b.get(key)));
+        }
+        if self.reverse {
+            records.reverse();
         }
-        Ok(())
+        Ok(records)
     }
 }
@@ -0,0 +1,184 @@
+use super::records::Record;
+use crate::cli::arg_or_env_var;
+use crate::git::{self, parse_date, Date};
+use crate::glob_to_regex;
+use anyhow::{Context, Result};
+use clap::Args;
+use regex::Regex;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Filters applied to installs, tests and results.
+///
+/// A date or commit filter only applies to records that have a date or commit, i.e. not to tests.
+#[derive(Debug, Clone, Args)]
+pub struct QueryArgs {
+    /// Filter by date.
+    #[arg(long, short)]
+    date: Option<String>,
+
+    /// Filter by commit.
+    #[arg(long, short)]
+    commit: Option<String>,
+
+    /// Only include commits from this date onwards.
+    #[arg(long)]
+    since: Option<String>,
+
+    /// Only include commits up to and including this date.
+    #[arg(long)]
+    until: Option<String>,
+
+    /// Filter by benchmark or install name (glob, or regex with --regex).
+    #[arg(long, short)]
+    name: Option<String>,
+
+    /// Interpret --name as a regular expression.
+    #[arg(long)]
+    regex: bool,
+
+    /// Only include commits in the range `A..B` of the opensim-core repo.
+    #[arg(long)]
+    commit_range: Option<String>,
+
+    /// Path to opensim-core repo, used for resolving --commit-range.
+    #[arg(long)]
+    opensim: Option<PathBuf>,
+
+    /// Only include the N most recent commits.
+    #[arg(long)]
+    latest: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct Query {
+    date: Option<Date>,
+    commit: Option<String>,
+    since: Option<Date>,
+    until: Option<Date>,
+    name: Option<Regex>,
+    commit_range: Option<Vec<String>>,
+    latest: Option<usize>,
+}
+
+impl QueryArgs {
+    /// Parses the dates and patterns, and resolves the commit range.
+    pub fn parse(&self) -> Result<Query> {
+        let date = |s: &Option<String>| s.as_deref().map(parse_date).transpose();
+        let name = match self.name.as_ref() {
+            Some(pattern) if self.regex => Some(Regex::new(pattern)?),
+            Some(pattern) => Some(glob_to_regex(pattern)?),
+            None => None,
+        };
+        let commit_range = match self.commit_range.as_ref() {
+            Some(range) => {
+                let repo = arg_or_env_var(self.opensim.clone(), "OSPC_OPENSIM_SRC")?
+                    .context("--commit-range requires the path to opensim-core (--opensim)")?;
+                Some(git::get_commit_range(&repo, range)?)
+            }
+            None => None,
+        };
+        Ok(Query {
+            date: date(&self.date)?,
+            commit: self.commit.clone(),
+            since: date(&self.since)?,
+            until: date(&self.until)?,
+            name,
+            commit_range,
+            latest: self.latest,
+        })
+    }
+}
+
+impl Query {
+    /// Returns true if the fields of the records are required for filtering.
+    pub fn is_active(&self) -> bool {
+        self.date.is_some()
+            || self.commit.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.name.is_some()
+            || self.commit_range.is_some()
+            || self.latest.is_some()
+    }
+
+    /// Removes the records that do not match, keeping the order.
+    pub fn apply(&self, records: &mut Vec<Record>) -> Result<()> {
+        let mut matching = Vec::with_capacity(records.len());
+        for record in records.drain(..) {
+            if self.is_match(&record)? {
+                matching.push(record);
+            }
+        }
+        *records = matching;
+
+        if let Some(latest) = self.latest {
+            // Most recent distinct commits, records without a commit are kept.
+            let mut commits: Vec<(Date, &str)> = Vec::new();
+            for record in records.iter() {
+                if let (Some(date), Some(commit)) =
+                    (record_date(record)?, record_str(record, "commit"))
+                {
+                    if !commits.iter().any(|(_, c)| *c == commit) {
+                        commits.push((date, commit));
+                    }
+                }
+            }
+            commits.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+            let commits = commits
+                .iter()
+                .take(latest)
+                .map(|(_, c)| c.to_string())
+                .collect::<Vec<String>>();
+            records.retain(|record| {
+                record_str(record, "commit").is_none_or(|c| commits.iter().any(|x| x == c))
+            });
+        }
+        Ok(())
+    }
+
+    fn is_match(&self, record: &Record) -> Result<bool> {
+        if let Some(name) = self.name.as_ref() {
+            let matches = ["name", "opensim_name"]
+                .iter()
+                .filter_map(|key| record_str(record, key))
+                .any(|s| name.is_match(s));
+            if !matches {
+                return Ok(false);
+            }
+        }
+
+        if let Some(commit) = record_str(record, "commit") {
+            if self.commit.as_ref().is_some_and(|c| c != commit) {
+                return Ok(false);
+            }
+            // Recorded commits may be abbreviated.
+            if let Some(range) = self.commit_range.as_ref() {
+                if !range.iter().any(|hash| hash.starts_with(commit)) {
+                    return Ok(false);
+                }
+            }
+        }
+
+        if let Some(date) = record_date(record)? {
+            if self.date.is_some_and(|d| d != date)
+                || self.since.is_some_and(|d| date < d)
+                || self.until.is_some_and(|d| date > d)
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn record_str<'a>(record: &'a Record, key: &str) -> Option<&'a str> {
+    match record.get(key) {
+        Value::String(s) if !s.is_empty() => Some(s),
+        _ => None,
+    }
+}
+
+fn record_date(record: &Record) -> Result<Option<Date>> {
+    record_str(record, "date").map(parse_date).transpose()
+}
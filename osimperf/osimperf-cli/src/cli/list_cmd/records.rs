@@ -0,0 +1,191 @@
+use super::super::plot_cmd::{csv_lines, plain_lines};
+use super::super::{InstallInfo, ReadBenchTestSetup, ResultInfo};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::path::Path;
+
+/// Output format of `ls`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// Bare paths, one per line.
+    #[default]
+    Path,
+    Json,
+    Csv,
+    /// Aligned plain text.
+    Table,
+}
+
+/// A listed install, test or result, as ordered fields.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub kind: &'static str,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    fn new(kind: &'static str, path: &Path) -> Self {
+        Self {
+            kind,
+            fields: vec![(String::from("path"), json!(path))],
+        }
+    }
+
+    /// A record with only the path.
+    pub fn path(kind: &'static str, path: &Path) -> Self {
+        Self::new(kind, path)
+    }
+
+    fn push(&mut self, key: &str, value: Value) {
+        self.fields.push((key.to_owned(), value));
+    }
+
+    pub fn get(&self, key: &str) -> &Value {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .unwrap_or(&Value::Null)
+    }
+
+    /// Installs, with the install duration in seconds and disk size in megabytes.
+    pub fn install(info: &InstallInfo, path: &Path, size: Option<usize>) -> Self {
+        let mut out = Self::new("install", path);
+        out.push("name", json!(info.name));
+        out.push("commit", json!(info.commit));
+        out.push("date", json!(info.date));
+        out.push("duration", json!(info.duration));
+        out.push("compiler", json!(info.compiler));
+        out.push("build_type", json!(info.build_type));
+        out.push("cmake_flags", json!(info.cmake_flags));
+        out.push("source_url", json!(info.source_url));
+        out.push("branch", json!(info.branch));
+        out.push("cache", json!(info.cache));
+        out.push("variant", json!(info.variant));
+        out.push("size", json!(size));
+        out
+    }
+
+    pub fn test(setup: &ReadBenchTestSetup, path: &Path) -> Result<Self> {
+        let mut out = Self::new("test", path);
+        if let Value::Object(fields) = serde_json::to_value(setup)? {
+            for (key, value) in fields {
+                out.push(&key, value);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Summary of a result, with durations in seconds and peak memory in kilobytes.
+    pub fn result(result: &ResultInfo, path: &Path) -> Self {
+        let durations = &result.durations;
+        let mut out = Self::new("result", path);
+        out.push("name", json!(result.name));
+        out.push("opensim_name", json!(result.opensim_name));
+        out.push("variant", json!(result.variant));
+        out.push("commit", json!(result.commit));
+        out.push("date", json!(result.date));
+        out.push("iterations", json!(durations.len()));
+        out.push("mean", json!(durations.get_mean()));
+        out.push("stddev", json!(durations.get_stddev()));
+        out.push("median", json!(durations.get_median()));
+        out.push("min", json!(durations.get_min()));
+        out.push("max", json!(durations.get_max()));
+        out.push("max_rss", json!(result.max_rss()));
+        out.push("grind", json!(result.grind.map(|d| d.as_secs_f64())));
+        out.push("outcome", json!(result.outcome));
+        out.push("valid", json!(result.is_valid()));
+        out.push(
+            "hostname",
+            json!(result.fingerprint.as_ref().map(|f| &f.hostname)),
+        );
+        out.push("opensim_log", json!(result.opensim_log));
+        out
+    }
+
+    /// Keeps only the selected fields, in the given order.
+    pub fn select(&mut self, fields: &[String]) {
+        self.fields = fields
+            .iter()
+            .map(|key| (key.clone(), self.get(key).clone()))
+            .collect();
+    }
+}
+
+/// Orders numbers numerically and strings alphabetically, with missing values first.
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        _ => value_str(a).cmp(&value_str(b)),
+    }
+}
+
+fn value_str(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Prints the records in the format, tables are printed per kind of record.
+pub fn print_records(records: &[Record], format: ListFormat) -> Result<()> {
+    match format {
+        ListFormat::Path => {
+            for record in records {
+                println!("{}", value_str(record.get("path")));
+            }
+        }
+        ListFormat::Json => {
+            let records = records
+                .iter()
+                .map(|record| {
+                    let mut map = serde_json::Map::new();
+                    map.insert(String::from("kind"), json!(record.kind));
+                    map.extend(record.fields.iter().cloned());
+                    Value::Object(map)
+                })
+                .collect::<Vec<Value>>();
+            println!("{}", serde_json::to_string_pretty(&records)?);
+        }
+        ListFormat::Csv | ListFormat::Table => {
+            let mut kinds: Vec<&str> = Vec::new();
+            for record in records {
+                if !kinds.contains(&record.kind) {
+                    kinds.push(record.kind);
+                }
+            }
+            for (i, kind) in kinds.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                let records = records.iter().filter(|r| &r.kind == kind);
+                let mut header: Vec<String> = Vec::new();
+                for (key, _) in records.clone().flat_map(|r| r.fields.iter()) {
+                    if !header.contains(key) {
+                        header.push(key.clone());
+                    }
+                }
+                let rows = records
+                    .map(|r| header.iter().map(|key| value_str(r.get(key))).collect())
+                    .collect::<Vec<Vec<String>>>();
+                let lines = match format {
+                    ListFormat::Csv => csv_lines(&header, &rows),
+                    _ => plain_lines(&header, &rows),
+                };
+                for line in lines {
+                    println!("{line}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,34 @@
 // mod run_cmd;
+mod bisect_cmd;
+mod compare_cmd;
+mod diff_cmd;
+mod import_cmd;
 mod install_cmd;
+mod install_info_cmd;
 mod list_cmd;
 mod log_cmd;
 mod plot_cmd;
+mod prune_cmd;
 mod record_cmd;
+mod report_cmd;
+mod store;
 
 use anyhow::ensure;
+pub use bisect_cmd::BisectCommand;
+pub use compare_cmd::CompareCommand;
+pub use diff_cmd::DiffCommand;
+pub use import_cmd::ImportCommand;
 pub use install_cmd::{InstallCommand, InstallInfo};
+pub use install_info_cmd::InstallInfoCommand;
 pub use list_cmd::ListCommand;
 pub use log_cmd::LogCommand;
 pub use plot_cmd::PlotCommand;
-pub use record_cmd::{ReadBenchTestSetup, RecordCommand, ResultInfo};
+pub use prune_cmd::PruneCommand;
+pub use record_cmd::{ReadBenchTestSetup, RecordCommand};
+pub use report_cmd::ReportCommand;
+pub use store::ResultStore;
+
+pub use osimperf_results::ResultInfo;
 
 use anyhow::{Context, Result};
 use std::io::Lines;
@@ -18,8 +36,6 @@ use std::io::StdinLock;
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use crate::Command;
-use crate::CommandTrait;
 
 pub fn absolute_path(relative_path: &PathBuf) -> Result<PathBuf> {
     std::fs::canonicalize(relative_path)
@@ -39,11 +55,11 @@ pub fn arg_or_env_var(arg: Option<PathBuf>, key: &str) -> Result<Option<PathBuf>
 
 /// Prefixes selected env vars with path.
 pub fn prefix_path(keys: &[&str], prefix_path: &String) -> Result<()> {
-    ensure!(prefix_path.len() > 0, "Prefix path is empty string.");
+    ensure!(!prefix_path.is_empty(), "Prefix path is empty string.");
     for key in keys {
         let mut value = prefix_path.to_owned();
         if let Ok(e) = std::env::var(key) {
-        value.push_str(":");
+        value.push(':');
         value.push_str(&e);
         }
         std::env::set_var(key, value);
@@ -86,22 +102,9 @@ impl Iterator for ArgOrStdinIter {
 }
 
 /// Substitute occurances of `%H`, and `%n`.
-pub fn substitute_install_info(mut s: String) -> String {
-    for (key, value) in [
-        (
-            "%H",
-            Command::parse("osimperf-install-info commit")
-                .run_trim()
-                .expect("failed to read commit"),
-        ),
-        (
-            "%n",
-            Command::parse("osimperf-install-info name")
-                .run_trim()
-                .expect("failed to read name"),
-        ),
-    ] {
-        s = s.replace(key, &value);
+pub fn substitute_install_info(mut s: String, info: &InstallInfo) -> String {
+    for (key, value) in [("%H", &info.commit), ("%n", &info.name)] {
+        s = s.replace(key, value);
     }
     s
 }
@@ -0,0 +1,226 @@
+use crate::{cli::ResultInfo, git::format_date, git::parse_date, git::Date, Estimator};
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info, warn};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::path::Path;
+
+static FIGURE_WIDTH: u32 = 1024;
+static SUBPLOT_HEIGHT: u32 = 320;
+
+/// A point of the timeline, with the error bar.
+#[derive(Debug)]
+struct Point {
+    date: Date,
+    value: f64,
+    low: f64,
+    high: f64,
+}
+
+/// Results of a benchmark on one machine.
+#[derive(Debug)]
+struct Series {
+    label: String,
+    points: Vec<Point>,
+}
+
+/// Results of a benchmark, one series per machine.
+#[derive(Debug)]
+struct Subplot {
+    name: String,
+    series: Vec<Series>,
+}
+
+/// Draws the duration vs. commit date of each benchmark.
+///
+/// The error bars show the spread belonging to the estimator, or the range of the samples if it
+/// has none. Writes png or svg depending on the file extension, or svg to stdout if no path is
+/// given.
+pub fn print_figure(
+    results: &[ResultInfo],
+    estimator: Estimator,
+    separate_machines: bool,
+    out: Option<&Path>,
+) -> Result<()> {
+    info!("Print results to figure.");
+    let subplots = collect_subplots(results, estimator, separate_machines)?;
+    let size = figure_size(&subplots);
+
+    let extension = out
+        .and_then(|path| path.extension())
+        .and_then(|ext| ext.to_str());
+    match (out, extension) {
+        (Some(path), Some("png")) => {
+            draw_subplots(
+                BitMapBackend::new(path, size).into_drawing_area(),
+                &subplots,
+            )
+            .with_context(|| format!("failed to draw png to {:?}", path))?;
+        }
+        (Some(path), Some("svg")) => {
+            draw_subplots(SVGBackend::new(path, size).into_drawing_area(), &subplots)
+                .with_context(|| format!("failed to draw svg to {:?}", path))?;
+        }
+        (Some(path), _) => bail!(
+            "unsupported figure format {:?}: expected .svg or .png",
+            path
+        ),
+        (None, _) => println!("{}", render_svg(results, estimator, separate_machines)?),
+    }
+    Ok(())
+}
+
+/// Same as [print_figure], but returns the svg.
+pub fn render_svg(
+    results: &[ResultInfo],
+    estimator: Estimator,
+    separate_machines: bool,
+) -> Result<String> {
+    let subplots = collect_subplots(results, estimator, separate_machines)?;
+    let size = figure_size(&subplots);
+    let mut svg = String::new();
+    draw_subplots(
+        SVGBackend::with_string(&mut svg, size).into_drawing_area(),
+        &subplots,
+    )
+    .context("failed to draw svg")?;
+    Ok(svg)
+}
+
+fn collect_subplots(
+    results: &[ResultInfo],
+    estimator: Estimator,
+    separate_machines: bool,
+) -> Result<Vec<Subplot>> {
+    let mut subplots: Vec<Subplot> = Vec::new();
+    for result in results {
+        let durations = &result.durations;
+        let Some(value) = durations.get_estimate(estimator) else {
+            warn!(
+                "Skipping {} of {}: no durations",
+                result.name, result.opensim_name
+            );
+            continue;
+        };
+        let (low, high) = match durations.get_spread(estimator) {
+            Some(spread) => (value - spread, value + spread),
+            None => (
+                durations.get_min().unwrap_or(value),
+                durations.get_max().unwrap_or(value),
+            ),
+        };
+        let point = Point {
+            date: parse_date(&result.date)?,
+            value,
+            low: low.max(0.),
+            high,
+        };
+        debug!("{} of {}: {:?}", result.name, result.opensim_name, point);
+
+        let label = match (
+            result.variant.as_ref(),
+            result.fingerprint.as_ref().filter(|_| separate_machines),
+        ) {
+            (Some(variant), Some(fingerprint)) => format!("{variant} @{}", fingerprint.hostname),
+            (Some(variant), None) => variant.clone(),
+            (None, Some(fingerprint)) => fingerprint.hostname.clone(),
+            (None, None) => String::from("durations"),
+        };
+
+        let subplot = match subplots.iter_mut().position(|s| s.name == result.name) {
+            Some(i) => &mut subplots[i],
+            None => {
+                subplots.push(Subplot {
+                    name: result.name.clone(),
+                    series: Vec::new(),
+                });
+                subplots.last_mut().unwrap()
+            }
+        };
+        match subplot.series.iter_mut().find(|s| s.label == label) {
+            Some(series) => series.points.push(point),
+            None => subplot.series.push(Series {
+                label,
+                points: vec![point],
+            }),
+        }
+    }
+    if subplots.is_empty() {
+        bail!("no results to plot");
+    }
+    for series in subplots.iter_mut().flat_map(|s| s.series.iter_mut()) {
+        series.points.sort_by_key(|p| p.date);
+    }
+    Ok(subplots)
+}
+
+fn figure_size(subplots: &[Subplot]) -> (u32, u32) {
+    (FIGURE_WIDTH, SUBPLOT_HEIGHT * subplots.len() as u32)
+}
+
+fn draw_subplots<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    subplots: &[Subplot],
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE).map_err(|e| anyhow!("{e}"))?;
+    let areas = root.split_evenly((subplots.len(), 1));
+    for (subplot, area) in subplots.iter().zip(areas.iter()) {
+        draw_subplot(area, subplot).map_err(|e| anyhow!("{e}"))?;
+    }
+    root.present().map_err(|e| anyhow!("{e}"))?;
+    Ok(())
+}
+
+fn draw_subplot<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    subplot: &Subplot,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let points = || subplot.series.iter().flat_map(|s| s.points.iter());
+    // Pad the range, such that points are not drawn on the axes.
+    let first = points().map(|p| p.date).min().unwrap() - chrono::Duration::days(1);
+    let last = points().map(|p| p.date).max().unwrap() + chrono::Duration::days(1);
+    let max = points().map(|p| p.high).fold(0., f64::max) * 1.1;
+
+    let mut chart = ChartBuilder::on(area)
+        .caption(&subplot.name, ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(70)
+        .build_cartesian_2d(first..last, 0f64..max.max(f64::EPSILON))?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Commit date")
+        .y_desc("Duration [s]")
+        .x_label_formatter(&format_date)
+        .draw()?;
+
+    for (i, series) in subplot.series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart
+            .draw_series(LineSeries::new(
+                series.points.iter().map(|p| (p.date, p.value)),
+                color.stroke_width(1),
+            ))?
+            .label(&series.label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        chart.draw_series(
+            series
+                .points
+                .iter()
+                .map(|p| ErrorBar::new_vertical(p.date, p.low, p.value, p.high, color.filled(), 8)),
+        )?;
+    }
+
+    if subplot.series.len() > 1 {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+    Ok(())
+}
@@ -1,5 +1,3 @@
-use std::fs;
-
 use super::*;
 
 pub fn print_diffs(table: &Table) -> Result<()> {
@@ -27,16 +25,17 @@ fn and_print_cell_diff(a: Option<&PathBuf>, b: Option<&PathBuf>) -> Option<Resul
 fn print_cell_diff(a: &PathBuf, b: &PathBuf) -> Result<()> {
     println!("file_a = {:?}", a);
     println!("file_b = {:?}", b);
-    let file_a = fs::File::open(a)?;
-    let data_a = crate::parse_logs::Data::read_opensim_file(file_a)?;
-
-    let file_b = fs::File::open(b)?;
-    let data_b = crate::parse_logs::Data::read_opensim_file(file_b)?;
+    let data_a = crate::parse_logs::Data::read_file(a)?;
+    let data_b = crate::parse_logs::Data::read_file(b)?;
 
-    let diff = crate::parse_logs::Diff::new(&data_a, &data_b)?;
-    let sum: f64 = diff.channels.iter().filter_map(|x| x.diff).sum();
+    let diff = crate::parse_logs::Diff::new(&data_a, &data_b);
+    let worst = diff
+        .channels
+        .iter()
+        .filter(|x| x.relative.is_some())
+        .max_by(|x, y| x.relative.partial_cmp(&y.relative).unwrap());
 
-    println!("sum = {}", sum);
+    println!("worst channel = {:?}", worst);
     // println!("diff: {:#?}", diff);
     Ok(())
 }
@@ -1,15 +1,25 @@
+mod figure;
 mod table_iter;
 mod plot;
 mod table;
 
+pub use figure::render_svg;
+pub use table_iter::{CellMetric, Table, TableCell, TableOrientation};
+
+use figure::print_figure;
 use plot::print_csv_plot;
-use table::print_table;
-use table_iter::*;
+pub use table::{csv_lines, plain_lines};
+
+use table::{print_table, TableFormat};
 
 use super::ArgOrStdinIter;
 use super::ResultInfo;
-use anyhow::{Context, Result};
+use super::ResultStore;
+use crate::{CpuFrequency, Estimator, Fingerprint, Isolation};
+use anyhow::{bail, Context, Result};
 use clap::Args;
+use log::warn;
+use regex::Regex;
 use std::{fs::File, path::PathBuf};
 
 #[derive(Debug, Args)]
@@ -18,6 +28,10 @@ pub struct PlotCommand {
     #[arg(long)]
     results: Option<PathBuf>,
 
+    /// Read the results from the result store at this path, instead of the result files.
+    #[arg(long, conflicts_with = "results")]
+    db: Option<PathBuf>,
+
     /// Output path.
     #[arg(long)]
     out: Option<PathBuf>,
@@ -25,12 +39,94 @@ pub struct PlotCommand {
     /// Table.
     #[arg(long, short)]
     table: bool,
+
+    /// Timeline plot of the durations of each benchmark (svg or png, depending on the output path).
+    #[arg(long, short, conflicts_with = "table")]
+    figure: bool,
+
+    /// Only include benchmarks with a matching name (regex).
+    #[arg(long, short)]
+    benchmark: Option<Regex>,
+
+    /// Only include installs with a matching name (regex).
+    #[arg(long, short)]
+    install: Option<Regex>,
+
+    /// Statistic of the durations to show in the table or figure.
+    #[arg(long, short, value_enum, default_value_t = Estimator::Mean)]
+    estimator: Estimator,
+
+    /// Install to compare against, by name, commit, date or build variant.
+    ///
+    /// The reference is shown in the table, even if excluded by `--install`.
+    #[arg(long, short, default_value = "Latest")]
+    reference: String,
+
+    /// Show installs on the rows, and benchmarks on the columns of the table.
+    #[arg(long)]
+    transpose: bool,
+
+    /// Values shown in each cell of the table.
+    #[arg(
+        long,
+        short,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "duration,spread,percentage,log-error"
+    )]
+    cell: Vec<CellMetric>,
+
+    /// Show peak memory, cpu time and instruction count in the table.
+    #[arg(long, short)]
+    usage: bool,
+
+    /// Output format of the table.
+    #[arg(long, value_enum, default_value_t = TableFormat::Markdown)]
+    format: TableFormat,
+
+    /// Plot results recorded on different machines as separate installs.
+    #[arg(long)]
+    separate_machines: bool,
 }
 
 impl PlotCommand {
     pub fn run(&self) -> Result<()> {
-        let reference = "Latest".to_string();
-        let table = Table::new(&self.results, &Some(reference))?;
+        let store = self.db.as_deref().map(ResultStore::open).transpose()?;
+        let filter = ResultFilter {
+            benchmark: self.benchmark.clone(),
+            install: self.install.clone(),
+        };
+        // Only the table compares against the reference.
+        let mut table = Table::new(
+            &self.results,
+            store.as_ref(),
+            &self.table.then(|| self.reference.clone()),
+            &filter,
+            self.separate_machines,
+        )?;
+        if self.transpose {
+            table.orientation = TableOrientation::InstallsOnRow;
+        }
+        let mut metrics = Vec::new();
+        if self.usage {
+            metrics.extend([
+                CellMetric::Memory,
+                CellMetric::CpuTime,
+                CellMetric::Instructions,
+            ]);
+        }
+        metrics.extend(self.cell.iter());
+        check_fingerprints(&table.results, self.separate_machines)?;
+        check_isolation(&table.results);
+
+        if self.figure {
+            return print_figure(
+                &table.results,
+                self.estimator,
+                self.separate_machines,
+                self.out.as_deref(),
+            );
+        }
 
         if let Some(path) = self.out.as_ref() {
             let mut file = File::create(path).with_context(|| {
@@ -40,18 +136,118 @@ impl PlotCommand {
                 )
             })?;
             if self.table {
-                print_table(&table, &mut file)?;
+                print_table(&table, self.estimator, &metrics, self.format, &mut file)?;
             } else {
-                print_csv_plot(&self.results, &mut file)?;
+                print_csv_plot(&table.results, self.separate_machines, &mut file)?;
             }
         } else {
             if self.table {
-                print_table(&table, std::io::stdout())?;
+                print_table(
+                    &table,
+                    self.estimator,
+                    &metrics,
+                    self.format,
+                    std::io::stdout(),
+                )?;
             } else {
-                print_csv_plot(&self.results, std::io::stdout())?;
+                print_csv_plot(&table.results, self.separate_machines, std::io::stdout())?;
             }
         }
 
         Ok(())
     }
 }
+
+/// Selects results by benchmark and install name.
+#[derive(Clone, Debug, Default)]
+pub struct ResultFilter {
+    pub benchmark: Option<Regex>,
+    pub install: Option<Regex>,
+}
+
+impl ResultFilter {
+    pub fn is_match(&self, result: &ResultInfo) -> bool {
+        self.is_benchmark_match(result)
+            && self
+                .install
+                .as_ref()
+                .is_none_or(|re| re.is_match(&result.opensim_name))
+    }
+
+    pub fn is_benchmark_match(&self, result: &ResultInfo) -> bool {
+        self.benchmark
+            .as_ref()
+            .is_none_or(|re| re.is_match(&result.name))
+    }
+}
+
+/// Fails if the results were recorded on different machines, unless they are plotted separately.
+///
+/// Warns if the kernel, compiler or build type differ. Results without a fingerprint are ignored.
+pub fn check_fingerprints(results: &[ResultInfo], separate_machines: bool) -> Result<()> {
+    let mut machines: Vec<&Fingerprint> = Vec::new();
+    let mut environments: Vec<&Fingerprint> = Vec::new();
+    for fingerprint in results.iter().filter_map(|r| r.fingerprint.as_ref()) {
+        if !machines.iter().any(|m| m.same_machine(fingerprint)) {
+            machines.push(fingerprint);
+        }
+        if !environments.iter().any(|e| {
+            e.kernel == fingerprint.kernel
+                && e.compiler == fingerprint.compiler
+                && e.build_type == fingerprint.build_type
+        }) {
+            environments.push(fingerprint);
+        }
+    }
+
+    if machines.len() > 1 && !separate_machines {
+        let names = machines
+            .iter()
+            .map(|m| m.machine_name())
+            .collect::<Vec<String>>();
+        bail!(
+            "results were recorded on different machines: {}\nUse --separate-machines to plot them separately",
+            names.join(", ")
+        );
+    }
+    if environments.len() > 1 {
+        let names = environments
+            .iter()
+            .map(|e| {
+                format!(
+                    "kernel {}, compiler {}, build type {}",
+                    e.kernel,
+                    e.compiler.as_deref().unwrap_or("unknown"),
+                    e.build_type.as_deref().unwrap_or("unknown")
+                )
+            })
+            .collect::<Vec<String>>();
+        warn!(
+            "Results were recorded with different environments:\n{}",
+            names.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// Warns if the results were recorded with different cpu isolation or frequency settings.
+pub fn check_isolation(results: &[ResultInfo]) {
+    let mut settings: Vec<(&Isolation, &CpuFrequency)> = Vec::new();
+    for result in results.iter() {
+        let setting = (&result.isolation, &result.cpu_frequency);
+        if !settings.contains(&setting) {
+            settings.push(setting);
+        }
+    }
+
+    if settings.len() > 1 {
+        let names = settings
+            .iter()
+            .map(|(isolation, cpu_frequency)| format!("{:?} {:?}", isolation, cpu_frequency))
+            .collect::<Vec<String>>();
+        warn!(
+            "Results were recorded with different isolation settings, and might not be comparable:\n{}",
+            names.join("\n")
+        );
+    }
+}
@@ -4,35 +4,49 @@ use crate::{
 };
 use anyhow::{Context, Result};
 use log::{debug, info};
-use std::{
-    io::{LineWriter, Write},
-    path::PathBuf,
-};
+use std::io::{LineWriter, Write};
 
-pub fn print_csv_plot(arg_path: &Option<PathBuf>, buf: impl std::io::Write) -> Result<()> {
+/// Prints the results as csv.
+///
+/// If separating machines, the hostname is appended to the benchmark name.
+pub fn print_csv_plot(
+    results: &[ResultInfo],
+    separate_machines: bool,
+    buf: impl std::io::Write,
+) -> Result<()> {
     info!("Print results to csv plot.");
     let mut file = LineWriter::new(buf);
 
     let mut first_date = None;
-    for path in super::ArgOrStdinIter::new(arg_path) {
-        debug!("path = {:?}", path);
-        let result = crate::read_json::<ResultInfo>(&path)?;
+    for result in results {
         debug!("{:?}", result);
+        let path = &result.name;
 
         let date = parse_date(&result.date)?;
-        let first_date = first_date.get_or_insert_with(|| date.clone());
+        let first_date = first_date.get_or_insert(date);
 
         let days = (date - *first_date).num_days();
-        let name = result.name;
-        for duration in result.durations.get().iter() {
+        let name = match result.fingerprint.as_ref().filter(|_| separate_machines) {
+            Some(fingerprint) => format!("{}@{}", result.name, fingerprint.hostname),
+            None => result.name.clone(),
+        };
+        for (i, duration) in result.durations.get().iter().enumerate() {
             file.write_all(format!("{name},").as_bytes())
                 .with_context(|| format!("Failed to write name {:?}", path))?;
             file.write_all(format!("{days},").as_bytes())
                 .with_context(|| format!("Failed to write number of days to {:?}", path))?;
             file.write_all(format_date(&date).as_bytes())
                 .with_context(|| format!("Failed to write date to {:?}", path))?;
-            file.write_all(format!(",{}\n", duration.as_secs_f64()).as_bytes())
+            file.write_all(format!(",{}", duration.as_secs_f64()).as_bytes())
                 .with_context(|| format!("Failed to write duration to {:?}", path))?;
+            // Peak memory (kB) and cpu time (seconds), empty for older results.
+            let usage = result
+                .resource_usage
+                .get(i)
+                .map(|u| format!(",{},{}\n", u.max_rss, u.cpu_time().as_secs_f64()))
+                .unwrap_or_else(|| String::from(",,\n"));
+            file.write_all(usage.as_bytes())
+                .with_context(|| format!("Failed to write resource usage to {:?}", path))?;
         }
     }
     file.flush()?;
@@ -1,65 +1,171 @@
 use super::*;
-use crate::git::parse_date;
-use crate::git::Date;
 use crate::*;
 use anyhow::Result;
+use clap::ValueEnum;
 use std::io::LineWriter;
-use std::{io::Write, path::PathBuf};
+use std::io::Write;
 
-pub fn print_table(table: &Table, buf: impl std::io::Write) -> Result<()> {
-    let mut buf = LineWriter::new(buf);
+/// Output format of the table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TableFormat {
+    #[default]
+    Markdown,
+    Csv,
+    Json,
+    Latex,
+    /// Aligned plain text.
+    Plain,
+}
 
-    let mut line = String::new();
+pub fn print_table(
+    table: &Table,
+    estimator: Estimator,
+    metrics: &[CellMetric],
+    format: TableFormat,
+    buf: impl std::io::Write,
+) -> Result<()> {
+    let mut buf = LineWriter::new(buf);
 
-    // Print column headers.
-    line.push_str("| |");
-    for row in table {
-        for col in row {
-            line.push_str(&col.col_name);
-            line.push_str("|");
-        }
-        break;
+    if format == TableFormat::Json {
+        serde_json::to_writer_pretty(&mut buf, &json_table(table, estimator, metrics))?;
+        buf.write_all(b"\n")?;
+        return Ok(());
     }
-    line.push_str("\n");
-    buf.write_all(line.as_bytes())?;
 
-    line.clear();
-    line.push_str("|---|");
-    for row in table {
-        for _ in row {
-        line.push_str("---|");
+    // Column headers, followed by the rows.
+    let mut header = vec![String::new()];
+    if let Some(row) = table.into_iter().next() {
+        for cell in row {
+            header.push(cell.col_name.to_owned());
         }
-        break;
     }
-    line.push_str("\n");
-    buf.write_all(line.as_bytes())?;
-
+    let mut rows = Vec::new();
     for row in table {
-        line.clear();
-        line.push_str("|");
-        line.push_str(&row.row_name());
-        line.push_str("|");
+        let mut line = vec![row.row_name().to_owned()];
         for cell in row {
-            cell.write_cell_str(&mut line);
-            line.push_str(" |");
+            line.push(cell.cell_str(metrics, estimator));
         }
-        line.push_str("\n");
+        rows.push(line);
+    }
+
+    let lines = match format {
+        TableFormat::Markdown => markdown_lines(&header, &rows),
+        TableFormat::Csv => csv_lines(&header, &rows),
+        TableFormat::Latex => latex_lines(&header, &rows),
+        TableFormat::Plain => plain_lines(&header, &rows),
+        TableFormat::Json => unreachable!(),
+    };
+    for line in lines {
         buf.write_all(line.as_bytes())?;
+        buf.write_all(b"\n")?;
     }
 
     Ok(())
 }
 
-#[derive(Clone, Debug, Ord, Eq, PartialOrd, PartialEq)]
-struct Row {
-    name: String,
-    date: Date,
+fn markdown_lines(header: &[String], rows: &[Vec<String>]) -> Vec<String> {
+    let line = |cells: &[String]| format!("|{}|", cells.join("|"));
+    let mut lines = vec![
+        format!("| {}", line(&header[1..])),
+        format!("|{}", "---|".repeat(header.len())),
+    ];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines
+}
+
+pub fn csv_lines(header: &[String], rows: &[Vec<String>]) -> Vec<String> {
+    let quote = |cell: &String| {
+        if cell.contains([',', '"', '\n']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.clone()
+        }
+    };
+    std::iter::once(header)
+        .chain(rows.iter().map(|row| row.as_slice()))
+        .map(|cells| cells.iter().map(quote).collect::<Vec<String>>().join(","))
+        .collect()
+}
+
+fn latex_lines(header: &[String], rows: &[Vec<String>]) -> Vec<String> {
+    let escape = |cell: &String| {
+        let mut out = String::new();
+        for c in cell.chars() {
+            match c {
+                '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                '~' => out.push_str("\\textasciitilde{}"),
+                '^' => out.push_str("\\textasciicircum{}"),
+                '\\' => out.push_str("\\textbackslash{}"),
+                _ => out.push(c),
+            }
+        }
+        out
+    };
+    let line = |cells: &[String]| {
+        let cells = cells.iter().map(escape).collect::<Vec<String>>();
+        format!("{} \\\\", cells.join(" & "))
+    };
+    let mut lines = vec![
+        format!("\\begin{{tabular}}{{l{}}}", "r".repeat(header.len() - 1)),
+        String::from("\\hline"),
+        line(header),
+        String::from("\\hline"),
+    ];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.push(String::from("\\hline"));
+    lines.push(String::from("\\end{tabular}"));
+    lines
+}
+
+pub fn plain_lines(header: &[String], rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths = vec![0; header.len()];
+    for cells in std::iter::once(header).chain(rows.iter().map(|row| row.as_slice())) {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    std::iter::once(header)
+        .chain(rows.iter().map(|row| row.as_slice()))
+        .map(|cells| {
+            let mut line = String::new();
+            for (i, (cell, width)) in cells.iter().zip(widths.iter()).enumerate() {
+                // Left align the row names, right align the values.
+                if i == 0 {
+                    line.push_str(&format!("{:<width$}", cell));
+                } else {
+                    line.push_str(&format!("  {:>width$}", cell));
+                }
+            }
+            line.trim_end().to_owned()
+        })
+        .collect()
 }
 
-struct TableCell {
-    row: Row,
-    col: String,
-    opensim_name: String,
-    test_name: String,
-    value: Durations,
+/// The table with a value per metric, or null if not available.
+fn json_table(table: &Table, estimator: Estimator, metrics: &[CellMetric]) -> serde_json::Value {
+    let mut rows = Vec::new();
+    for row in table {
+        let name = row.row_name();
+        let mut cells = Vec::new();
+        for cell in row {
+            let mut values = serde_json::Map::new();
+            values.insert(String::from("column"), cell.col_name.into());
+            values.insert(String::from("flags"), cell.flags().into());
+            for &metric in metrics {
+                values.insert(metric.name(), cell.metric(metric, estimator).into());
+            }
+            cells.push(serde_json::Value::Object(values));
+        }
+        rows.push(serde_json::json!({
+            "name": name,
+            "cells": cells,
+        }));
+    }
+    serde_json::json!({
+        "estimator": format!("{:?}", estimator),
+        "rows": rows,
+    })
 }
@@ -1,25 +1,78 @@
 use super::ArgOrStdinIter;
+use super::ResultFilter;
 use super::ResultInfo;
-use anyhow::{Context, Result};
-use clap::Args;
-use std::{fs::File, path::PathBuf};
+use super::ResultStore;
+use crate::Estimator;
+use anyhow::Result;
+use clap::ValueEnum;
+use log::warn;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone, Debug, Default)]
 pub struct Table {
     pub installed: Vec<InstallNode>,
     pub benchmarks: Vec<BenchmarkNode>,
     pub results: Vec<ResultInfo>,
+    /// Directory of each result.
+    pub result_dirs: Vec<PathBuf>,
     pub reference: Option<Vec<ResultInfo>>,
+    /// Results from different machines are placed in separate installs.
+    pub separate_machines: bool,
+    pub orientation: TableOrientation,
 }
 
 impl Table {
-    pub fn new(arg_path: &Option<PathBuf>, ref_name: &Option<String>) -> Result<Self> {
+    /// Reads the results into a table, from the result store if given.
+    ///
+    /// The reference matches the install name, the (abbreviated) commit hash, the date, or the
+    /// build variant, and is included regardless of the install filter. Each build variant of an
+    /// install is a separate install in the table.
+    pub fn new(
+        arg_path: &Option<PathBuf>,
+        store: Option<&ResultStore>,
+        reference_name: &Option<String>,
+        filter: &ResultFilter,
+        separate_machines: bool,
+    ) -> Result<Self> {
+        let results = match store {
+            Some(store) => store.read_results()?,
+            None => ArgOrStdinIter::new(arg_path)
+                .map(|path| Ok((path.clone(), ResultInfo::read(&path)?)))
+                .collect::<Result<Vec<_>>>()?,
+        };
+        Ok(Self::from_results(
+            results,
+            reference_name,
+            filter,
+            separate_machines,
+        ))
+    }
+
+    /// Places the results, with the path to their result file, in a table.
+    fn from_results(
+        results: Vec<(PathBuf, ResultInfo)>,
+        reference_name: &Option<String>,
+        filter: &ResultFilter,
+        separate_machines: bool,
+    ) -> Self {
         let mut reference: Vec<ResultInfo> = Vec::new();
-        let mut out = Self::default();
-        for path in ArgOrStdinIter::new(arg_path) {
-            let result = crate::read_json::<ResultInfo>(&path)?;
+        let mut out = Self {
+            separate_machines,
+            ..Default::default()
+        };
+        for (path, result) in results {
+            let matches_reference = reference_name
+                .as_ref()
+                .is_some_and(|r| is_reference(&result, r));
+            // The reference install is kept, even if excluded by the install filter.
+            if !(filter.is_match(&result)
+                || (matches_reference && filter.is_benchmark_match(&result)))
+            {
+                continue;
+            }
+            let machine = out.machine(&result);
 
-            if ref_name.as_ref() == Some(&result.opensim_name) {
+            if matches_reference {
                 reference.push(result.clone());
             }
 
@@ -40,23 +93,57 @@ impl Table {
             if out
                 .installed
                 .iter()
-                .find(|x| x.name == result.opensim_name)
+                .find(|x| {
+                    x.name == result.opensim_name
+                        && x.variant == result.variant
+                        && x.machine == machine
+                })
                 .is_none()
             {
+                let mut cell_name = match result.variant.as_ref() {
+                    Some(variant) => {
+                        format!("{} {} ({})", result.opensim_name, variant, result.date)
+                    }
+                    None => format!("{} ({})", result.opensim_name, result.date),
+                };
+                if let Some(machine) = machine.as_ref() {
+                    cell_name.push_str(&format!(" @{machine}"));
+                }
                 out.installed.push(InstallNode {
                     name: result.opensim_name.clone(),
                     date: result.date.clone(),
-                    cell_name: format!("{} ({})", result.opensim_name, result.date),
+                    variant: result.variant.clone(),
+                    cell_name,
+                    machine,
                 });
             }
 
             out.results.push(result);
+            out.result_dirs
+                .push(path.parent().map(PathBuf::from).unwrap_or_default());
         }
         reference.sort_by(|a, b| b.date.cmp(&a.date));
-        if reference.len() > 0 {
+        if !reference.is_empty() {
             out.reference = Some(reference);
         }
-        Ok(out)
+        out
+    }
+
+    /// Returns the directory of a result in this table.
+    pub fn result_dir(&self, result: &ResultInfo) -> Option<&Path> {
+        self.results
+            .iter()
+            .position(|r| std::ptr::eq(r, result))
+            .map(|i| self.result_dirs[i].as_path())
+    }
+
+    /// Returns the machine the result belongs to, if separating machines.
+    fn machine(&self, result: &ResultInfo) -> Option<String> {
+        result
+            .fingerprint
+            .as_ref()
+            .filter(|_| self.separate_machines)
+            .map(|f| f.hostname.clone())
     }
 }
 
@@ -68,7 +155,7 @@ impl<'a> IntoIterator for &'a Table {
     fn into_iter(self) -> Self::IntoIter {
         RowIterator {
             table: self,
-            direction: TableOrientation::BenchmarksOnRow,
+            direction: self.orientation,
             index: None,
         }
     }
@@ -84,12 +171,16 @@ pub struct BenchmarkNode {
 pub struct InstallNode {
     name: String,
     date: String,
+    variant: Option<String>,
     cell_name: String,
+    /// Hostname, if separating machines.
+    machine: Option<String>,
 }
 
-#[derive(Copy, Clone, Debug)]
-enum TableOrientation {
+#[derive(Copy, Clone, Debug, Default)]
+pub enum TableOrientation {
     InstallsOnRow,
+    #[default]
     BenchmarksOnRow,
 }
 
@@ -135,7 +226,7 @@ impl<'a> IntoIterator for &'a RowIterator<'a> {
 
 impl<'a> ColIterator<'a> {
     pub fn row_name(&self) -> &'a str {
-        &self.row_name
+        self.row_name
     }
 
     pub fn col_name(&self) -> &'a str {
@@ -207,70 +298,268 @@ impl<'a> Iterator for ColIterator<'a> {
         };
 
         Some(TableCell {
-            result: self.table.results.iter().find(|res| is_eq_cell(res, installed_node, benchmark_node)
-                    ),
-            reference: self
-                .table
-                .reference
-                .as_ref()
-                .and_then(|x| x.iter().find(|res| res.name == benchmark_node.name && !is_eq_cell(res, installed_node, benchmark_node))),
+            result: self.table.results.iter().find(|res| {
+                is_eq_cell(res, installed_node, benchmark_node)
+                    && self.table.machine(res) == installed_node.machine
+            }),
+            reference: self.table.reference.as_ref().and_then(|x| {
+                x.iter().find(|res| {
+                    res.name == benchmark_node.name
+                        && !is_eq_cell(res, installed_node, benchmark_node)
+                        && self.table.machine(res) == installed_node.machine
+                })
+            }),
             row_name: self.row_name(),
             col_name: self.col_name(),
         })
     }
 }
 
+fn is_reference(result: &ResultInfo, reference: &str) -> bool {
+    result.opensim_name == reference
+        || result.date == reference
+        || result.variant.as_deref() == Some(reference)
+        || (!reference.is_empty() && result.commit.starts_with(reference))
+}
+
 fn is_eq_cell(
     res: &ResultInfo,
     installed_node: &InstallNode,
     benchmark_node: &BenchmarkNode,
 ) -> bool {
-    ((res.opensim_name == installed_node.name) && (res.date == installed_node.date))
+    ((res.opensim_name == installed_node.name)
+        && (res.date == installed_node.date)
+        && (res.variant == installed_node.variant))
         && (res.name == benchmark_node.name)
 }
 
 impl<'a> TableCell<'a> {
-    pub fn percentage(&self) -> Option<f64> {
-        let reference = self.reference?;
-        let result = self.result?;
-        Some(
-            (result.durations.get_mean()? - reference.durations.get_mean()?)
-                / result.durations.get_mean()?
-                * 100.,
-        )
+    pub fn percentage(&self, estimator: Estimator) -> Option<f64> {
+        let reference = self.reference?.durations.get_estimate(estimator)?;
+        let result = self.result?.durations.get_estimate(estimator)?;
+        Some((result - reference) / reference * 100.)
     }
 
+    /// Largest relative error of the opensim log channels, see `osimperf-cli diff`.
+    ///
+    /// Returns None, leaving the cell empty, if either of the logs can not be read.
     pub fn log_diff(&self) -> Option<f64> {
-        let file_a = std::fs::File::open(self.result?.opensim_log.as_ref()?)
-            .expect("failed to open result file");
-        let data_a = crate::parse_logs::Data::read_opensim_file(&file_a)
-            .expect("failed to parse result file");
-
-        let file_b = std::fs::File::open(self.reference?.opensim_log.as_ref()?)
-            .expect("failed to open result file");
-        let data_b = crate::parse_logs::Data::read_opensim_file(&file_b)
-            .expect("failed to parse result file");
-
-        let diff = crate::parse_logs::Diff::new(&data_a, &data_b)
-            .with_context(|| format!("file-a = {:?}", file_a))
-            .with_context(|| format!("file-b = {:?}", file_b))
-            .expect("failed to compute diff");
-        let sum: f64 = diff.channels.iter().filter_map(|x| x.diff).sum();
-
-        Some(sum)
+        let file_a = self.result?.opensim_log.as_ref()?;
+        let file_b = self.reference?.opensim_log.as_ref()?;
+        let read = |path: &Path| {
+            crate::parse_logs::Data::read_file(path)
+                .map_err(|err| warn!("Failed to read opensim log: {err:#}"))
+                .ok()
+        };
+        let data_a = read(file_a)?;
+        let data_b = read(file_b)?;
+
+        // Relative errors, such that channels of different units can be compared.
+        crate::parse_logs::Diff::new(&data_a, &data_b).max_relative()
     }
 
-    pub fn write_cell_str(&self, s: &mut String) -> Option<()> {
-        s.push_str(&format!(
-            " {:.3} ({:.3})",
-            self.result?.durations.get_mean().unwrap_or(f64::NAN),
-            self.result?.durations.get_stddev().unwrap_or(f64::NAN),
-        ));
+    /// Returns the value of the metric, if available.
+    pub fn metric(&self, metric: CellMetric, estimator: Estimator) -> Option<f64> {
+        let result = self.result?;
+        match metric {
+            CellMetric::Duration => result.durations.get_estimate(estimator),
+            CellMetric::Spread => result.durations.get_spread(estimator),
+            CellMetric::Percentage => self.percentage(estimator),
+            CellMetric::LogError => self.log_diff(),
+            CellMetric::Grind => result.grind.map(|d| d.as_secs_f64()),
+            CellMetric::Memory => result.max_rss().map(|kb| kb as f64 / 1024.),
+            CellMetric::CpuTime => result.mean_cpu_time(),
+            CellMetric::Instructions => result.mean_instructions(),
+        }
+    }
 
-        s.push_str(&format!(" {:.1}%", self.percentage()?));
+    /// Returns TIMEOUT, FAILED and INVALID, if applicable.
+    pub fn flags(&self) -> Vec<&'static str> {
+        let mut flags = Vec::new();
+        if let Some(result) = self.result {
+            if result.timed_out() {
+                flags.push("TIMEOUT");
+            }
+            if result.failed() {
+                flags.push("FAILED");
+            }
+            if !result.is_valid() {
+                flags.push("INVALID");
+            }
+        }
+        flags
+    }
 
-        s.push_str(&format!(" E{:.1}", self.log_diff()?));
+    /// Returns the flags followed by the formatted metrics, skipping unavailable metrics.
+    pub fn cell_str(&self, metrics: &[CellMetric], estimator: Estimator) -> String {
+        let mut words = self
+            .flags()
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect::<Vec<String>>();
+        for &metric in metrics {
+            if let Some(value) = self.metric(metric, estimator) {
+                words.push(metric.format(value));
+            }
+        }
+        words.join(" ")
+    }
+}
 
-        Some(())
+/// A value shown in a table cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CellMetric {
+    /// Duration summarized by the estimator, in seconds.
+    #[value(alias = "mean")]
+    Duration,
+    /// Spread of the duration belonging to the estimator, in seconds.
+    #[value(alias = "stddev")]
+    Spread,
+    /// Change of the duration relative to the reference.
+    Percentage,
+    /// Largest relative error of the opensim log channels compared to the reference.
+    LogError,
+    /// Duration of the valgrind run, in seconds.
+    Grind,
+    /// Peak memory, in megabytes.
+    Memory,
+    /// Mean cpu time, in seconds.
+    CpuTime,
+    /// Mean instruction count.
+    Instructions,
+}
+
+impl CellMetric {
+    pub fn name(&self) -> String {
+        self.to_possible_value().unwrap().get_name().to_owned()
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Self::Duration => format!("{:.3}", value),
+            Self::Spread => format!("({:.3})", value),
+            Self::Percentage => format!("{:.1}%", value),
+            Self::LogError => format!("E{:.1e}", value),
+            Self::Grind => format!("{:.3}s grind", value),
+            Self::Memory => format!("{:.0}MB", value),
+            Self::CpuTime => format!("{:.3}s cpu", value),
+            Self::Instructions => format!("{:.3}G instr", value * 1e-9),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use std::time::Duration;
+
+    fn result(secs: &[f64]) -> ResultInfo {
+        let mut result = ResultInfo::new(
+            String::from("test"),
+            String::from("opensim"),
+            String::from("abc"),
+            String::from("2023-01-01"),
+        );
+        for &s in secs {
+            result.durations.add_sample(Duration::from_secs_f64(s));
+        }
+        result
+    }
+
+    #[test]
+    fn percentage_is_relative_to_reference() {
+        let reference = result(&[2.]);
+        let slower = result(&[3.]);
+        let cell = TableCell {
+            result: Some(&slower),
+            reference: Some(&reference),
+            row_name: "test",
+            col_name: "opensim",
+        };
+        assert_eq!(cell.percentage(Estimator::Mean), Some(50.));
+
+        let cell = TableCell {
+            result: Some(&reference),
+            reference: Some(&slower),
+            row_name: "test",
+            col_name: "opensim",
+        };
+        let percentage = cell.percentage(Estimator::Mean).unwrap();
+        assert!((percentage + 100. / 3.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn percentage_requires_reference() {
+        let result = result(&[1.]);
+        let cell = TableCell {
+            result: Some(&result),
+            reference: None,
+            row_name: "test",
+            col_name: "opensim",
+        };
+        assert_eq!(cell.percentage(Estimator::Mean), None);
+    }
+
+    fn install(name: &str, benchmark: &str) -> (PathBuf, ResultInfo) {
+        let result = ResultInfo::new(
+            String::from(benchmark),
+            String::from(name),
+            String::from("abc"),
+            String::from("2023-01-01"),
+        );
+        (PathBuf::from(name).join(benchmark), result)
+    }
+
+    #[test]
+    fn install_filter_keeps_reference() {
+        let results = vec![
+            install("Latest", "Arm26"),
+            install("Latest", "Gait"),
+            install("opensim-4.4", "Arm26"),
+            install("opensim-4.4", "Gait"),
+            install("opensim-4.3", "Arm26"),
+        ];
+        let filter = ResultFilter {
+            benchmark: Some(Regex::new("Arm").unwrap()),
+            install: Some(Regex::new("4.4").unwrap()),
+        };
+        let table = Table::from_results(results, &Some(String::from("Latest")), &filter, false);
+
+        let installs = table
+            .installed
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(installs, ["Latest", "opensim-4.4"]);
+        assert_eq!(table.benchmarks.len(), 1);
+        assert_eq!(table.reference.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn install_filter_without_reference() {
+        let results = vec![install("Latest", "Arm26"), install("opensim-4.4", "Arm26")];
+        let filter = ResultFilter {
+            benchmark: None,
+            install: Some(Regex::new("4.4").unwrap()),
+        };
+        let table = Table::from_results(results, &None, &filter, false);
+
+        assert_eq!(table.installed.len(), 1);
+        assert!(table.reference.is_none());
+    }
+
+    #[test]
+    fn unreadable_log_leaves_cell_empty() {
+        let mut result = result(&[1.]);
+        result.opensim_log = Some(PathBuf::from("/nonexistent/osimperf-log.sto"));
+        let cell = TableCell {
+            result: Some(&result),
+            reference: Some(&result),
+            row_name: "test",
+            col_name: "opensim",
+        };
+        assert_eq!(cell.log_diff(), None);
+        assert_eq!(cell.metric(CellMetric::LogError, Estimator::Mean), None);
     }
 }
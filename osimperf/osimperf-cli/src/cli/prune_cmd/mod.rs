@@ -0,0 +1,178 @@
+mod retention;
+
+use super::plot_cmd::plain_lines;
+use super::{absolute_path, InstallInfo, ResultInfo, ResultStore};
+use crate::{find_file_by_name, folder_size, remove_folder, RESULT_INFO_FILE_NAME};
+use anyhow::{ensure, Context, Result};
+use clap::Args;
+use log::info;
+use osimperf_results::migrate::LEGACY_MONITOR_FILE_NAME;
+use retention::{Candidate, InstallKey, RetentionPolicy};
+use std::{collections::HashSet, path::Path, path::PathBuf};
+
+/// Removes installs that are not kept by any of the retention policies.
+///
+/// Without a keep policy all installs are kept, unless over the disk budget.
+#[derive(Debug, Args)]
+pub struct PruneCommand {
+    /// Path to directory in which to search for installs.
+    #[arg(long, short)]
+    install: PathBuf,
+
+    /// Keep the N most recent installs of each install name and build variant.
+    #[arg(long)]
+    keep_last: Option<usize>,
+
+    /// Keep the newest install of each of the last N days.
+    #[arg(long)]
+    keep_daily: Option<usize>,
+
+    /// Keep the newest install of each of the last N months.
+    #[arg(long)]
+    keep_monthly: Option<usize>,
+
+    /// Keep installs that have results, found in the install directory, `--results` or `--db`.
+    #[arg(long)]
+    keep_referenced: bool,
+
+    /// Path to directory in which to search for result files.
+    #[arg(long, short, requires = "keep_referenced")]
+    results: Option<PathBuf>,
+
+    /// Path to the result store (or set OSIMPERF_DB).
+    #[arg(long, requires = "keep_referenced")]
+    db: Option<PathBuf>,
+
+    /// Disk budget in megabytes: removes the oldest installs until the rest fits.
+    #[arg(long)]
+    max_size: Option<usize>,
+
+    /// Only print what would be removed.
+    #[arg(long, short = 'n')]
+    dry_run: bool,
+}
+
+impl PruneCommand {
+    pub fn run(&self) -> Result<()> {
+        let install_dir = absolute_path(&self.install)?;
+
+        let mut policy = RetentionPolicy {
+            last: self.keep_last,
+            daily: self.keep_daily,
+            monthly: self.keep_monthly,
+            referenced: None,
+            max_size: self.max_size,
+        };
+        if self.keep_referenced {
+            policy.referenced = Some(self.referenced_installs(&install_dir)?);
+        }
+        ensure!(
+            policy.has_keep_policy() || policy.max_size.is_some(),
+            "no retention policy given: use --keep-last, --keep-daily, --keep-monthly, --keep-referenced or --max-size"
+        );
+
+        let mut candidates = Vec::new();
+        for (path, info) in InstallInfo::find_all(&install_dir)? {
+            let root = InstallInfo::root_of(&path).to_owned();
+            let size = match info.size {
+                Some(size) => size,
+                None => folder_size(&root)?,
+            };
+            let modified = std::fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .with_context(|| format!("failed to read modification time of {:?}", path))?;
+            candidates.push(Candidate::new(root, info, size, modified));
+        }
+        policy.apply(&mut candidates);
+
+        let header = [
+            "action", "reason", "size", "date", "name", "variant", "path",
+        ]
+        .map(String::from)
+        .to_vec();
+        let rows = candidates
+            .iter()
+            .map(|c| {
+                let (action, reason) = match c.remove {
+                    Some(reason) => ("remove", reason.to_owned()),
+                    None if c.keep.is_empty() => ("keep", String::new()),
+                    None => (
+                        "keep",
+                        c.keep
+                            .iter()
+                            .map(|k| k.as_str())
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ),
+                };
+                vec![
+                    action.to_owned(),
+                    reason,
+                    c.size.to_string(),
+                    c.info.date.clone(),
+                    c.info.name.clone(),
+                    c.info.variant.clone().unwrap_or_default(),
+                    c.root.display().to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        for line in plain_lines(&header, &rows) {
+            println!("{line}");
+        }
+
+        let (removed, kept): (Vec<_>, Vec<_>) = candidates.iter().partition(|c| c.remove.is_some());
+        if !self.dry_run {
+            for c in removed.iter() {
+                info!("Removing install {:?}", c.root);
+                remove_folder(&c.root)?;
+            }
+        }
+        info!(
+            "{} {} installs, freeing {} MB, and kept {} installs ({} MB)",
+            if self.dry_run {
+                "Would remove"
+            } else {
+                "Removed"
+            },
+            removed.len(),
+            removed.iter().map(|c| c.size).sum::<usize>(),
+            kept.len(),
+            kept.iter().map(|c| c.size).sum::<usize>(),
+        );
+        Ok(())
+    }
+
+    /// The installs of the results in the install directory, the results directory and the store.
+    fn referenced_installs(&self, install_dir: &Path) -> Result<HashSet<InstallKey>> {
+        let mut dirs = vec![install_dir.to_owned()];
+        if let Some(dir) = self.results.as_ref() {
+            dirs.push(absolute_path(dir)?);
+        }
+
+        let mut results = Vec::new();
+        for dir in dirs.iter() {
+            let mut paths = find_file_by_name(dir, RESULT_INFO_FILE_NAME);
+            paths.extend(find_file_by_name(dir, LEGACY_MONITOR_FILE_NAME));
+            // Reads without upgrading old results, which would rewrite them. Fails on any
+            // unreadable result, as its install would otherwise lose protection.
+            for path in paths {
+                let json = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read result {:?}", path))
+                    .context("refusing to prune")?;
+                results.push(
+                    ResultInfo::from_json(&json, &path)
+                        .with_context(|| format!("failed to parse result {:?}", path))
+                        .context("refusing to prune")?,
+                );
+            }
+        }
+        if let Some(store) = ResultStore::open_arg_or_env_var(&self.db)? {
+            results.extend(store.read_results()?.into_iter().map(|(_, result)| result));
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| (result.opensim_name, result.commit, result.variant))
+            .collect())
+    }
+}
@@ -0,0 +1,415 @@
+use super::super::InstallInfo;
+use crate::git::parse_date;
+use chrono::Datelike;
+use std::{collections::HashSet, path::PathBuf, time::SystemTime};
+
+/// Why an install is kept, see [RetentionPolicy].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeepReason {
+    Last,
+    Daily,
+    Monthly,
+    Referenced,
+}
+
+impl KeepReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Last => "last",
+            Self::Daily => "daily",
+            Self::Monthly => "monthly",
+            Self::Referenced => "referenced",
+        }
+    }
+}
+
+/// Identifies the install of a result: the install name, commit and build variant.
+pub type InstallKey = (String, String, Option<String>);
+
+/// An install considered for removal.
+#[derive(Debug)]
+pub struct Candidate {
+    pub root: PathBuf,
+    pub info: InstallInfo,
+    /// Disk size in megabytes.
+    pub size: usize,
+    /// Modification time of the manifest, for ordering installs of the same date.
+    pub modified: SystemTime,
+    /// Policies that keep this install.
+    pub keep: Vec<KeepReason>,
+    /// Reason for removing this install, if removed.
+    pub remove: Option<&'static str>,
+}
+
+impl Candidate {
+    pub fn new(root: PathBuf, info: InstallInfo, size: usize, modified: SystemTime) -> Self {
+        Self {
+            root,
+            info,
+            size,
+            modified,
+            keep: Vec::new(),
+            remove: None,
+        }
+    }
+
+    fn key(&self) -> InstallKey {
+        (
+            self.info.name.clone(),
+            self.info.commit.clone(),
+            self.info.variant.clone(),
+        )
+    }
+
+    fn keep(&mut self, reason: KeepReason) {
+        if !self.keep.contains(&reason) {
+            self.keep.push(reason);
+        }
+    }
+}
+
+/// Selects the installs to remove.
+///
+/// The `last`, `daily` and `monthly` policies apply to each series of installs with the same
+/// name and build variant, by commit date. The daily (monthly) policy keeps the newest install
+/// of each of the last N days (months) that have an install, and skips installs with an invalid
+/// date. Installs that are not kept by any policy are removed. Without any policy all installs
+/// are kept.
+///
+/// If the kept installs exceed the disk budget, the oldest are removed until within budget,
+/// except for referenced installs.
+#[derive(Debug, Default)]
+pub struct RetentionPolicy {
+    pub last: Option<usize>,
+    pub daily: Option<usize>,
+    pub monthly: Option<usize>,
+    /// Keep the installs of these results.
+    pub referenced: Option<HashSet<InstallKey>>,
+    /// Disk budget in megabytes.
+    pub max_size: Option<usize>,
+}
+
+impl RetentionPolicy {
+    pub fn has_keep_policy(&self) -> bool {
+        self.last.is_some()
+            || self.daily.is_some()
+            || self.monthly.is_some()
+            || self.referenced.is_some()
+    }
+
+    /// Marks the installs to remove, sorting the installs newest first.
+    pub fn apply(&self, candidates: &mut [Candidate]) {
+        candidates.sort_by(|a, b| {
+            b.info
+                .date
+                .cmp(&a.info.date)
+                .then(b.modified.cmp(&a.modified))
+        });
+
+        let mut series = Vec::<(&str, Option<&str>)>::new();
+        for c in candidates.iter() {
+            let key = (c.info.name.as_str(), c.info.variant.as_deref());
+            if !series.contains(&key) {
+                series.push(key);
+            }
+        }
+        let series = series
+            .into_iter()
+            .map(|(name, variant)| {
+                candidates
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| c.info.name == name && c.info.variant.as_deref() == variant)
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        for indices in series.iter() {
+            for &i in indices.iter().take(self.last.unwrap_or(0)) {
+                candidates[i].keep(KeepReason::Last);
+            }
+            let periods = [
+                (self.daily, KeepReason::Daily),
+                (self.monthly, KeepReason::Monthly),
+            ];
+            for (count, reason) in periods {
+                let count = count.unwrap_or(0);
+                let mut seen = Vec::new();
+                for &i in indices.iter() {
+                    if seen.len() == count {
+                        break;
+                    }
+                    let Ok(date) = parse_date(&candidates[i].info.date) else {
+                        continue;
+                    };
+                    // The first day of the day or month of the install.
+                    let period = match reason {
+                        KeepReason::Monthly => date.with_day(1).unwrap(),
+                        _ => date,
+                    };
+                    if !seen.contains(&period) {
+                        seen.push(period);
+                        candidates[i].keep(reason);
+                    }
+                }
+            }
+        }
+
+        for c in candidates.iter_mut() {
+            if self
+                .referenced
+                .as_ref()
+                .is_some_and(|keys| keys.contains(&c.key()))
+            {
+                c.keep(KeepReason::Referenced);
+            }
+            if self.has_keep_policy() && c.keep.is_empty() {
+                c.remove = Some("unmatched");
+            }
+        }
+
+        let Some(max_size) = self.max_size else {
+            return;
+        };
+        let mut total = candidates
+            .iter()
+            .filter(|c| c.remove.is_none())
+            .map(|c| c.size)
+            .sum::<usize>();
+        for c in candidates.iter_mut().rev() {
+            if total <= max_size {
+                break;
+            }
+            if c.remove.is_some() || c.keep.contains(&KeepReason::Referenced) {
+                continue;
+            }
+            c.remove = Some("over budget");
+            total -= c.size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn candidate(name: &str, variant: Option<&str>, commit: &str, date: &str) -> Candidate {
+        let info = InstallInfo {
+            name: name.to_owned(),
+            commit: commit.to_owned(),
+            date: date.to_owned(),
+            duration: 0,
+            compiler: None,
+            build_type: None,
+            cmake_flags: Vec::new(),
+            source_url: None,
+            branch: None,
+            size: None,
+            cache: None,
+            variant: variant.map(String::from),
+        };
+        Candidate::new(PathBuf::from(commit), info, 100, SystemTime::UNIX_EPOCH)
+    }
+
+    /// Returns the commits of the removed installs, newest first.
+    fn removed(candidates: &[Candidate]) -> Vec<&str> {
+        candidates
+            .iter()
+            .filter(|c| c.remove.is_some())
+            .map(|c| c.info.commit.as_str())
+            .collect()
+    }
+
+    fn keep_reasons<'a>(candidates: &'a [Candidate], commit: &str) -> &'a [KeepReason] {
+        &candidates
+            .iter()
+            .find(|c| c.info.commit == commit)
+            .unwrap()
+            .keep
+    }
+
+    #[test]
+    fn without_policy_keeps_all() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2023-05-01"),
+            candidate("opensim", None, "b", "2023-05-02"),
+        ];
+        RetentionPolicy::default().apply(&mut candidates);
+        assert!(removed(&candidates).is_empty());
+        // Sorted newest first.
+        assert_eq!(candidates[0].info.commit, "b");
+    }
+
+    #[test]
+    fn keep_last_of_each_series() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2023-05-01"),
+            candidate("opensim", None, "b", "2023-05-02"),
+            candidate("opensim", Some("debug"), "c", "2023-05-01"),
+            candidate("biolab", None, "d", "2023-04-01"),
+        ];
+        let policy = RetentionPolicy {
+            last: Some(1),
+            ..Default::default()
+        };
+        policy.apply(&mut candidates);
+        assert_eq!(removed(&candidates), vec!["a"]);
+        assert_eq!(candidates[0].remove, None);
+        assert_eq!(keep_reasons(&candidates, "c"), &[KeepReason::Last]);
+    }
+
+    #[test]
+    fn keep_daily_keeps_newest_of_each_day() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2023-05-01"),
+            candidate("opensim", None, "b", "2023-05-02"),
+            candidate("opensim", None, "c", "2023-05-03"),
+            candidate("opensim", None, "d", "2023-05-03"),
+        ];
+        // The install of "d" was modified last.
+        candidates[3].modified += Duration::from_secs(1);
+        let policy = RetentionPolicy {
+            daily: Some(2),
+            ..Default::default()
+        };
+        policy.apply(&mut candidates);
+        assert_eq!(removed(&candidates), vec!["c", "a"]);
+        assert_eq!(keep_reasons(&candidates, "d"), &[KeepReason::Daily]);
+        assert_eq!(keep_reasons(&candidates, "b"), &[KeepReason::Daily]);
+    }
+
+    #[test]
+    fn keep_monthly_keeps_newest_of_each_month() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2022-04-15"),
+            candidate("opensim", None, "b", "2023-04-30"),
+            candidate("opensim", None, "c", "2023-05-01"),
+            candidate("opensim", None, "d", "2023-05-20"),
+        ];
+        let policy = RetentionPolicy {
+            monthly: Some(3),
+            ..Default::default()
+        };
+        policy.apply(&mut candidates);
+        // The same month of another year is a different month.
+        assert_eq!(removed(&candidates), vec!["c"]);
+    }
+
+    #[test]
+    fn invalid_dates_are_not_kept_by_period() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2023-05-01"),
+            candidate("opensim", None, "b", "unknown"),
+        ];
+        let policy = RetentionPolicy {
+            daily: Some(2),
+            monthly: Some(2),
+            ..Default::default()
+        };
+        policy.apply(&mut candidates);
+        assert_eq!(removed(&candidates), vec!["b"]);
+    }
+
+    #[test]
+    fn policies_combine() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2023-04-01"),
+            candidate("opensim", None, "b", "2023-05-01"),
+            candidate("opensim", None, "c", "2023-05-02"),
+            candidate("opensim", None, "d", "2023-05-03"),
+        ];
+        let referenced = [("opensim".to_owned(), "a".to_owned(), None)];
+        let policy = RetentionPolicy {
+            last: Some(1),
+            daily: Some(2),
+            monthly: Some(1),
+            referenced: Some(referenced.into_iter().collect()),
+            max_size: None,
+        };
+        policy.apply(&mut candidates);
+        assert_eq!(removed(&candidates), vec!["b"]);
+        assert_eq!(
+            keep_reasons(&candidates, "d"),
+            &[KeepReason::Last, KeepReason::Daily, KeepReason::Monthly]
+        );
+        assert_eq!(keep_reasons(&candidates, "c"), &[KeepReason::Daily]);
+        assert_eq!(keep_reasons(&candidates, "a"), &[KeepReason::Referenced]);
+    }
+
+    #[test]
+    fn referenced_requires_same_variant() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2023-05-01"),
+            candidate("opensim", Some("debug"), "a", "2023-05-01"),
+        ];
+        let referenced = [(
+            "opensim".to_owned(),
+            "a".to_owned(),
+            Some("debug".to_owned()),
+        )];
+        let policy = RetentionPolicy {
+            referenced: Some(referenced.into_iter().collect()),
+            ..Default::default()
+        };
+        policy.apply(&mut candidates);
+        let removed = candidates
+            .iter()
+            .filter(|c| c.remove.is_some())
+            .map(|c| c.info.variant.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(removed, vec![None]);
+    }
+
+    #[test]
+    fn budget_removes_oldest_except_referenced() {
+        let candidates = || {
+            vec![
+                candidate("opensim", None, "a", "2023-05-01"),
+                candidate("opensim", None, "b", "2023-05-02"),
+                candidate("opensim", None, "c", "2023-05-03"),
+                candidate("opensim", None, "d", "2023-05-04"),
+            ]
+        };
+        let referenced = || Some([("opensim".to_owned(), "a".to_owned(), None)].into());
+
+        // Only "a" is kept by a policy, which is within budget.
+        let mut unmatched = candidates();
+        let policy = RetentionPolicy {
+            referenced: referenced(),
+            max_size: Some(250),
+            ..Default::default()
+        };
+        policy.apply(&mut unmatched);
+        assert_eq!(removed(&unmatched), vec!["d", "c", "b"]);
+        assert_eq!(unmatched[0].remove, Some("unmatched"));
+
+        // The oldest kept installs are removed, skipping the referenced "a".
+        let mut over_budget = candidates();
+        let policy = RetentionPolicy {
+            last: Some(3),
+            referenced: referenced(),
+            max_size: Some(250),
+            ..Default::default()
+        };
+        policy.apply(&mut over_budget);
+        assert_eq!(removed(&over_budget), vec!["c", "b"]);
+        assert_eq!(over_budget[1].remove, Some("over budget"));
+    }
+
+    #[test]
+    fn budget_without_keep_policy() {
+        let mut candidates = vec![
+            candidate("opensim", None, "a", "2023-05-01"),
+            candidate("opensim", None, "b", "2023-05-02"),
+            candidate("biolab", None, "c", "2023-05-03"),
+        ];
+        let policy = RetentionPolicy {
+            max_size: Some(200),
+            ..Default::default()
+        };
+        policy.apply(&mut candidates);
+        assert_eq!(removed(&candidates), vec!["a"]);
+    }
+}
@@ -1,7 +1,14 @@
 use super::absolute_path;
 use super::InstallInfo;
-
-use crate::{read_json, write_json, Command, CommandTrait, Durations, RESULT_INFO_FILE_NAME};
+use super::ResultStore;
+
+use crate::parse_logs::{Data, Tolerance, Tolerances};
+use crate::{
+    quote, read_cpu_frequency, read_fingerprint, read_json, verify_isolation,
+    Command, CommandOutput, CommandTrait, EnvVar, Isolation, OutlierMethod, OutlierPolicy,
+    PerfCounterSet, RESULT_DIR_ENV_VAR, RESULT_INFO_FILE_NAME, STDERR_LOG_ENV_VAR,
+    STDOUT_LOG_ENV_VAR,
+};
 use anyhow::ensure;
 use anyhow::{Context, Result};
 use clap::Args;
@@ -11,15 +18,19 @@ use log::warn;
 use log::{debug, info};
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::{path::PathBuf, str::FromStr};
+use osimperf_results::{config_hash, Outcome, ResultInfo, ValidationFailure};
+use std::{
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
 
 /// OSimPerf record command for running benchmark tests.
 ///
 /// Takes path to benchmark configuration file.
 /// Runs specified commands from that directory and creates `osimperf-result-ID/osimperf-result-info.json`
-/// Uses PATH to find `osimperf-install-info`, which must match `opensim-cmd --version`
+/// Uses PATH to find the install manifest, which must match `opensim-cmd --version`
 #[derive(Debug, Args)]
 pub struct RecordCommand {
     /// Number of test iterations.
@@ -45,36 +56,46 @@ pub struct RecordCommand {
     /// Run visualization command (if present).
     #[arg(long, short)]
     visualize: bool,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ResultInfo {
-    /// Test case name.
-    pub name: String,
-    /// Cell name.
-    pub cell_name: Option<String>,
-    /// Opensim install name.
-    pub opensim_name: String,
-    /// Opensim-core commit hash.
-    pub commit: String,
-    /// Opensim-core commit date.
-    pub date: String,
-    /// Already ran the pre_benchmark_cmds.
-    pub setup: bool,
-    /// Benchmark durations.
-    pub durations: Durations,
-    /// Benchmark grind result.
-    pub grind: Option<std::time::Duration>,
-    /// Test config hash.
-    pub config_hash: u64,
-    /// Output opensim log file.
-    pub opensim_log: Option<PathBuf>
-}
 
-impl ResultInfo {
-    pub fn filename() -> &'static str {
-        RESULT_INFO_FILE_NAME
-    }
+    /// Reject outliers before computing the duration statistics.
+    #[arg(long, value_enum)]
+    outliers: Option<OutlierMethod>,
+
+    /// Outlier rejection threshold (defaults to 1.5 for iqr, and 3 for mad).
+    #[arg(long, requires = "outliers")]
+    outlier_threshold: Option<f64>,
+
+    /// Percentiles (0-100) of the durations to compute, e.g. `--percentiles 5,95`.
+    #[arg(long, value_delimiter = ',')]
+    percentiles: Vec<f64>,
+
+    /// Count instructions, cycles, cache and branch misses using hardware perf counters.
+    #[arg(long)]
+    perf_counters: bool,
+
+    /// Run the post-benchmark commands after every iteration, instead of only after the last.
+    #[arg(long)]
+    post_every_iter: bool,
+
+    /// Pin the benchmark to these cpus, e.g. `--cpus 2,3`.
+    #[arg(long, value_delimiter = ',')]
+    cpus: Vec<usize>,
+
+    /// Niceness of the benchmark (negative values require privileges).
+    #[arg(
+        long,
+        allow_negative_numbers = true,
+        value_parser = clap::value_parser!(i32).range(-20..=19)
+    )]
+    nice: Option<i32>,
+
+    /// Run the benchmark with this real-time (SCHED_FIFO) priority (requires privileges).
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..=99))]
+    realtime_priority: Option<i32>,
+
+    /// Also write each iteration to the result store at this path (or set OSIMPERF_DB).
+    #[arg(long)]
+    db: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -82,20 +103,82 @@ struct BenchTestCtxt {
     pub result_dir: PathBuf,
     pub pre_benchmark_cmds: Vec<Command>,
     pub benchmark_cmd: Command,
+    pub post_benchmark_cmds: Vec<Command>,
     pub grind_cmd: Command,
     pub visualize_cmd: Option<Command>,
     pub output: ResultInfo,
     pub repeats: usize,
+    /// Reference output that the opensim-log is checked against.
+    pub reference_log: Option<PathBuf>,
+    pub tolerances: Vec<Tolerance>,
+}
+
+impl BenchTestCtxt {
+    /// Returns true if more iterations are needed, and the benchmark did not time out or fail.
+    fn should_run(&self) -> bool {
+        !self.output.timed_out()
+            && !self.output.failed()
+            && self.output.durations.len() < self.repeats
+    }
+
+    /// Writes the result file, and the result store if any.
+    fn write_result(&self, store: &mut Option<ResultStore>) -> Result<()> {
+        self.output.write(&self.result_path())?;
+        self.write_iteration(store)
+    }
+
+    /// Writes the result store only, the result file is written after the last iteration.
+    fn write_iteration(&self, store: &mut Option<ResultStore>) -> Result<()> {
+        if let Some(store) = store.as_mut() {
+            store.write_result(&self.result_path(), &self.output)?;
+        }
+        Ok(())
+    }
+
+    fn result_path(&self) -> PathBuf {
+        self.result_dir.join(RESULT_INFO_FILE_NAME)
+    }
+
+    /// Returns true if the opensim-log failed the accuracy check.
+    fn diverged(&self) -> bool {
+        self.output
+            .validation_failures
+            .iter()
+            .any(|f| f.check.starts_with(ACCURACY_CHECK))
+    }
 }
 
 impl RecordCommand {
+    /// Record command for running a single benchmark config.
+    pub fn new(config: PathBuf, iter: Option<usize>) -> Self {
+        Self {
+            iter,
+            config: Some(config),
+            grind: false,
+            force: false,
+            print: false,
+            visualize: false,
+            outliers: None,
+            outlier_threshold: None,
+            percentiles: Vec::new(),
+            perf_counters: false,
+            post_every_iter: false,
+            cpus: Vec::new(),
+            nice: None,
+            realtime_priority: None,
+            db: None,
+        }
+    }
+
     pub fn run(&self) -> Result<()> {
         info!("Start OSimPerf record command");
 
+        let (install_root, install_info) = InstallInfo::find_on_path()?;
+        debug!("{:?}", install_info);
+
         // Prefix linker path.
         {
-            let mut prefix_path = Command::parse("osimperf-install-info root").run_trim()?;
-            prefix_path.push_str("/lib");
+            let prefix_path = install_root.join("lib").to_str().unwrap().to_owned();
             super::prefix_path(&["LD_LIBRARY_PATH"], &prefix_path)?;
 
             debug!(
@@ -105,8 +188,14 @@ impl RecordCommand {
             );
         }
 
-        let install_info = InstallInfo::try_read("osimperf-install-info")?;
-        debug!("{:?}", install_info);
+        let mut store = ResultStore::open_arg_or_env_var(&self.db)?;
+
+        let isolation = Isolation {
+            cpus: self.cpus.clone(),
+            nice: self.nice,
+            realtime_priority: self.realtime_priority,
+        };
+        verify_isolation(&isolation)?;
 
         let mut tests = Vec::new();
 
@@ -136,68 +225,119 @@ impl RecordCommand {
             let root_dir = config_path.parent().unwrap();
 
             // Create subdirectory for placing results from this record.
-            let result_dir =
-                PathBuf::from(Command::parse("osimperf-install-info root").run_trim()?)
-                    .join("results")
-                    .join(&config.name);
+            let result_dir = install_root.join("results").join(&config.name);
 
             // Path to result-info file, placed in results subdirectory.
             let result_info_path = result_dir.join(RESULT_INFO_FILE_NAME);
 
             // Detect changes in test configuration.
-            let mut hasher = DefaultHasher::new();
-            config.hash(&mut hasher);
-            let config_hash = hasher.finish();
+            let config_hash = config_hash(&config);
 
             // Read any previous result, if it exists.
-            let result_info = read_json::<ResultInfo>(&result_info_path)
+            let mut result_info = ResultInfo::read(&result_info_path)
                 .ok()
                 .filter(|r| r.commit == install_info.commit)
                 .filter(|r| r.config_hash == config_hash)
                 .unwrap_or(ResultInfo {
-                    name: config.name.clone(),
-                    opensim_name: install_info.name.clone(),
-                    commit: install_info.commit.clone(),
-                    date: install_info.date.clone(),
-                    durations: Default::default(),
-                    grind: None,
                     config_hash,
-                    setup: false,
                     cell_name: config.cell_name.clone(),
+                    variant: install_info.variant.clone(),
                     opensim_log: config.opensim_log.map(|file| root_dir.join(file)),
+                    ..ResultInfo::new(
+                        config.name.clone(),
+                        install_info.name.clone(),
+                        install_info.commit.clone(),
+                        install_info.date.clone(),
+                    )
                 });
 
-            // Setup pre-benchmark, benchmark, grind, and visualize commands for this benchmark.
+            // (Re)compute the duration statistics using the requested policy.
+            result_info.durations.set_outlier_policy(
+                self.outliers
+                    .map(|method| OutlierPolicy::new(method, self.outlier_threshold)),
+            );
+            result_info.durations.set_percentiles(&self.percentiles);
 
-            let pre_benchmark_cmds = parse_commands(&config.pre_benchmark_cmds)
-                .drain(..)
-                .map(|c| c.set_run_root(&root_dir))
-                .collect::<Vec<Command>>();
+            // Setup pre-benchmark, benchmark, grind, and visualize commands for this benchmark.
 
-            let benchmark_cmd = Command::parse(&config.benchmark_cmd).set_run_root(&root_dir);
+            let shell = config.shell.unwrap_or(false);
+            let timeout = config.timeout.map(Duration::from_secs);
+            let parse_with_context = |cmd: &str| {
+                let mut cmd = parse_command(cmd, shell)
+                    .with_context(|| format!("failed to parse command of {:?}", config_path))?
+                    .set_run_root(root_dir);
+                cmd.set_timeout(timeout);
+                Ok(cmd)
+            };
+
+            let pre_benchmark_cmds = config
+                .pre_benchmark_cmds
+                .iter()
+                .flatten()
+                .map(|c| parse_with_context(c))
+                .collect::<Result<Vec<Command>>>()?;
+
+            let benchmark_cmd = parse_with_context(&config.benchmark_cmd)?
+                .set_isolation(Some(isolation.clone()).filter(|i| !i.is_default()));
+
+            let post_benchmark_cmds = config
+                .post_benchmark_cmds
+                .iter()
+                .flatten()
+                .map(|c| parse_with_context(c))
+                .collect::<Result<Vec<Command>>>()?;
 
             let grind_cmd_base = "valgrind --tool=callgrind --dump-instr=yes --collect-jumps=yes --cache-sim=yes --branch-sim=yes";
-            let grind_cmd = Command::parse(&super::substitute_install_info(format!(
-                "{grind_cmd_base} --callgrind-out-file={}/callgrind.out.%n_%H {}",
-                result_dir.to_str().unwrap(),
-                config.benchmark_cmd
-            )))
-            .set_run_root(&root_dir);
+            let mut grind_cmd = Command::parse(&super::substitute_install_info(
+                format!(
+                    "{grind_cmd_base} --callgrind-out-file={}/callgrind.out.%n_%H",
+                    quote(result_dir.to_str().unwrap()),
+                ),
+                &install_info,
+            ));
+            if shell {
+                grind_cmd.add_arg("--trace-children=yes");
+            }
+            grind_cmd.append(&benchmark_cmd);
+            let grind_cmd = grind_cmd.set_run_root(root_dir);
 
             let visualize_cmd = config
                 .visualize_cmd
                 .as_ref()
-                .map(|s| Command::parse(s).set_run_root(&root_dir));
+                .map(|s| parse_with_context(s))
+                .transpose()?;
+
+            // Reference output for checking the accuracy of the opensim-log.
+            let reference_log = config
+                .reference_log
+                .as_ref()
+                .map(|file| root_dir.join(file));
+            if let Some(reference) = reference_log.as_ref() {
+                ensure!(
+                    result_info.opensim_log.is_some(),
+                    "reference_log requires an opensim_log in {:?}",
+                    config_path
+                );
+                ensure!(
+                    reference.exists(),
+                    "could not find reference_log: {:?}",
+                    reference
+                );
+            }
 
             // Collext benchmark info.
             tests.push(BenchTestCtxt {
                 pre_benchmark_cmds,
                 benchmark_cmd,
+                post_benchmark_cmds,
                 grind_cmd,
                 visualize_cmd,
                 output: result_info,
                 result_dir,
                 repeats: self.iter.unwrap_or(config.repeats.unwrap_or(3)),
+                reference_log,
+                // Without tolerances the output must match the reference exactly.
+                tolerances: config.tolerances.unwrap_or(vec![Tolerance::default()]),
             });
 
             // Break if --test argument was used, otherwise continue reading from stdin.
@@ -213,7 +353,7 @@ impl RecordCommand {
         // Update ResultInfo file such that setup is done once.
         for test in tests.iter_mut() {
             test.output.setup = true;
-            write_json(&test.result_dir.join(RESULT_INFO_FILE_NAME), &test.output)?;
+            test.write_result(&mut store)?;
         }
 
         // If --print argument was set: Print relevant command to stdout.
@@ -241,14 +381,14 @@ impl RecordCommand {
 
         // If --visualize argument was set: Run visualization.
         if self.visualize {
-            if tests.len() == 0 {
+            if tests.is_empty() {
                 info!("Nothing to show.");
                 return Ok(());
             }
 
             let mut msg = String::from("Prepare to visualize benchmarks:");
             tests.iter().for_each(|t| {
-                msg.push_str("\n");
+                msg.push('\n');
                 msg.push_str(&t.output.name);
             });
             info!("{msg}");
@@ -270,17 +410,17 @@ impl RecordCommand {
                 tests.retain(|t| t.output.grind.is_none());
             }
 
-            if tests.len() == 0 {
+            if tests.is_empty() {
                 info!("Nothing to grind");
                 return Ok(());
             }
 
             let mut msg = String::from("Prepare to grind benchmarks:");
             tests.iter().for_each(|t| {
-                msg.push_str("\n");
+                msg.push('\n');
                 msg.push_str(&t.output.name);
                 if log_enabled!(log::Level::Debug) {
-                    msg.push_str("\n");
+                    msg.push('\n');
                     msg.push_str(&t.grind_cmd.print_command());
                 }
             });
@@ -291,7 +431,7 @@ impl RecordCommand {
                 let dt = *test.output.grind.insert(output.duration);
 
                 // Store results.
-                write_json(&test.result_dir.join(RESULT_INFO_FILE_NAME), &test.output)?;
+                test.write_result(&mut store)?;
                 info!(
                     "Completed grinding {} in {}",
                     test.output.name,
@@ -303,20 +443,32 @@ impl RecordCommand {
         }
 
         if true {
-            // Filter tests that are complete.
+            // Filter tests that are complete, or timed out or failed previously.
             if !self.force {
-                tests.retain(|t| t.output.durations.len() < t.repeats);
+                for test in tests.iter().filter(|t| t.output.timed_out()) {
+                    info!(
+                        "Skipping {}: timed out previously (use --force to retry)",
+                        test.output.name
+                    );
+                }
+                for test in tests.iter().filter(|t| t.output.failed()) {
+                    info!(
+                        "Skipping {}: failed previously (use --force to retry)",
+                        test.output.name
+                    );
+                }
+                tests.retain(|t| t.should_run());
             }
 
-            if tests.len() == 0 {
+            if tests.is_empty() {
                 info!("Nothing to test");
                 return Ok(());
             }
 
             // Print list of tests that will be ran.
-            let mut msg = format!("Prepare to run benchmarks:");
+            let mut msg = String::from("Prepare to run benchmarks:");
             tests.iter().for_each(|t| {
-                msg.push_str("\n");
+                msg.push('\n');
                 msg.push_str(&t.output.name);
                 msg.push_str(&format!(" ({}X)", t.repeats));
             });
@@ -324,38 +476,134 @@ impl RecordCommand {
 
             // Reset any previous measurements.
             for test in tests.iter_mut() {
-                test.output.durations = Default::default();
+                test.output.durations.clear();
+                test.output.resource_usage.clear();
+                test.output.perf_counters.clear();
+                test.output.validation_failures.clear();
+                test.output.outcome = Outcome::Completed;
+                test.output.failed_count = 0;
             }
 
+            // Record the isolation settings, and warn about noisy cpu frequency scaling.
+            let cpu_frequency = read_cpu_frequency(&isolation.cpus)?;
+            cpu_frequency.warn_if_noisy();
+            let fingerprint = read_fingerprint(install_info.build_info())?;
+            debug!("{:?}", fingerprint);
+            for test in tests.iter_mut() {
+                test.output.isolation = isolation.clone();
+                test.output.cpu_frequency = cpu_frequency.clone();
+                test.output.fingerprint = Some(fingerprint.clone());
+            }
+
+            // Check if perf counters are available, otherwise only record durations.
+            let perf_counters = self.perf_counters
+                && PerfCounterSet::probe()
+                    .map_err(|err| warn!("Perf counters unavailable, recording without: {err:#}"))
+                    .is_ok();
+
             // Run tests repeatedly.
             let mut rng = rand::thread_rng();
-            while tests.iter().filter(|t| t.output.durations.len() < t.repeats).count() > 0 {
+            while tests.iter().filter(|t| t.should_run()).count() > 0 {
                 // Randomize test order.
                 tests.shuffle(&mut rng);
-                for test in tests.iter_mut().filter(|t| t.output.durations.len() < t.repeats) {
-                    let output = if log_enabled!(log::Level::Trace) {
+                for test in tests.iter_mut().filter(|t| t.should_run()) {
+                    let output = if perf_counters {
+                        test.benchmark_cmd.run_and_count()?
+                    } else if log_enabled!(log::Level::Trace) {
                         test.benchmark_cmd.run_and_stream(&mut std::io::stdout())?
                     } else {
                         test.benchmark_cmd.run_and_time()?
                     };
+                    if output.timed_out {
+                        // Keep the logs of the hanging benchmark, and stop running it.
+                        warn!("Benchmark {} timed out", test.output.name);
+                        write_benchmark_logs(test, &output)?;
+                        test.output.outcome = Outcome::TimedOut;
+                        test.write_iteration(&mut store)?;
+                        continue;
+                    }
+                    if !output.success() {
+                        // Keep the logs of the failing benchmark, and stop running it.
+                        warn!(
+                            "Benchmark {} failed: {}",
+                            test.output.name, output.output.status
+                        );
+                        write_benchmark_logs(test, &output)?;
+                        test.output.outcome = Outcome::Failed;
+                        test.output.failed_count += 1;
+                        test.write_iteration(&mut store)?;
+                        continue;
+                    }
                     test.output.durations.add_sample(output.duration);
-                    debug!("Completed {} in {} seconds.", test.output.name, output.duration.as_secs_f64());
+                    test.output.resource_usage.push(output.usage);
+                    if perf_counters {
+                        // One sample per iteration, to keep the counters in step with the durations.
+                        trace!("{} perf counters: {:?}", test.output.name, output.counters);
+                        test.output.perf_counters.push(output.counters);
+                    }
+
+                    if self.post_every_iter || test.output.durations.len() == test.repeats {
+                        run_post_benchmark_commands(test, &output)?;
+                        run_accuracy_check(test)?;
+                    }
+                    debug!(
+                        "Completed {} in {} seconds (peak rss {} kB).",
+                        test.output.name,
+                        output.duration.as_secs_f64(),
+                        output.usage.max_rss
+                    );
+                    test.write_iteration(&mut store)?;
                 }
             }
 
             // Store results.
+            let mut diverged = Vec::new();
             for test in tests.drain(..) {
                 info!(
-                    "Benchmark result {}: {} ({})",
+                    "Benchmark result {}: {} ({}), median {} ({}), {} outliers",
                     test.output.name,
                     test.output.durations.get_mean().unwrap_or(f64::NAN),
-                    test.output.durations.get_stddev().unwrap_or(f64::NAN)
+                    test.output.durations.get_stddev().unwrap_or(f64::NAN),
+                    test.output.durations.get_median().unwrap_or(f64::NAN),
+                    test.output.durations.get_mad().unwrap_or(f64::NAN),
+                    test.output.durations.get_outliers().len(),
                 );
-                if let Some(log) = test.output.opensim_log.as_ref() {
+                if test.output.timed_out() {
+                    warn!(
+                        "Benchmark result {} timed out after {} iterations",
+                        test.output.name,
+                        test.output.durations.len()
+                    );
+                }
+                if test.output.failed() {
+                    warn!(
+                        "Benchmark result {} failed after {} iterations",
+                        test.output.name,
+                        test.output.durations.len()
+                    );
+                }
+                if !test.output.is_valid() {
+                    warn!(
+                        "Benchmark result {} is invalid: {} validations failed",
+                        test.output.name,
+                        test.output.validation_failures.len()
+                    );
+                }
+                // A benchmark that did not complete might not have written the log.
+                let completed = test.output.outcome == Outcome::Completed;
+                if let Some(log) = test.output.opensim_log.as_ref().filter(|_| completed) {
                     ensure!(log.exists(), format!("could not find expected opensim-log: {:?}", log));
                 }
-                write_json(&test.result_dir.join(RESULT_INFO_FILE_NAME), &test.output)?;
+                test.write_result(&mut store)?;
+                if test.diverged() {
+                    diverged.push(test.output.name);
+                }
             }
+            ensure!(
+                diverged.is_empty(),
+                "simulation output diverged from the reference: {}",
+                diverged.join(", ")
+            );
 
             info!("Benchmark complete");
 
@@ -368,11 +616,12 @@ impl RecordCommand {
     }
 }
 
-fn parse_commands(cmds: &Option<Vec<String>>) -> Vec<Command> {
-    if let Some(c) = cmds {
-        c.iter().map(|cmd| Command::parse(cmd)).collect()
+/// Parses a command from the config, either to run directly or using a shell.
+fn parse_command(cmd: &str, shell: bool) -> Result<Command> {
+    if shell {
+        Ok(Command::shell(cmd))
     } else {
-        Vec::new()
+        Command::try_parse(cmd)
     }
 }
 
@@ -403,7 +652,109 @@ fn run_pre_benchmark_commands(path: &PathBuf, cmds: &[Command]) -> Result<()> {
     Ok(())
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Hash)]
+/// Writes the benchmark logs, and runs the post-benchmark commands.
+///
+/// The commands can access the logs and result directory through environmental variables. Failing
+/// commands are recorded as validation failures in the result.
+fn run_post_benchmark_commands(test: &mut BenchTestCtxt, output: &CommandOutput) -> Result<()> {
+    let iteration = test.output.durations.len();
+    let (stdout_log, stderr_log) = write_benchmark_logs(test, output)?;
+
+    let env_vars = [
+        EnvVar::new(RESULT_DIR_ENV_VAR, &test.result_dir),
+        EnvVar::new(STDOUT_LOG_ENV_VAR, &stdout_log),
+        EnvVar::new(STDERR_LOG_ENV_VAR, &stderr_log),
+    ];
+
+    for cmd in test.post_benchmark_cmds.iter() {
+        let check = cmd.print_command();
+        let cmd = cmd.clone().set_envs(&env_vars);
+        debug!("Run post-benchmark cmd: {}", cmd.print_command());
+        let message = match cmd.run_and_time() {
+            Ok(out) if out.success() => continue,
+            Ok(out) => format!(
+                "returned {}:\n{}",
+                out.output.status,
+                out.stderr_str_clone().trim()
+            ),
+            Err(err) => format!("{err:#}"),
+        };
+        warn!(
+            "Post-benchmark command of {} failed: {check}\n{message}",
+            test.output.name,
+        );
+        test.output.validation_failures.push(ValidationFailure {
+            check,
+            iteration,
+            message,
+        });
+    }
+    Ok(())
+}
+
+/// Checks the opensim-log against the reference output, if any.
+///
+/// Channels that are not within tolerance are recorded as validation failures in the result.
+fn run_accuracy_check(test: &mut BenchTestCtxt) -> Result<()> {
+    let Some(reference_log) = test.reference_log.as_ref() else {
+        return Ok(());
+    };
+    let iteration = test.output.durations.len();
+    // Only the channels that are checked are read.
+    let tolerances = Tolerances::new(&test.tolerances)?;
+    let read =
+        |path: &PathBuf| Data::read_file_channels(path, |label| tolerances.get(label).is_some());
+    let reference = read(reference_log)?;
+
+    let opensim_log = test
+        .output
+        .opensim_log
+        .as_ref()
+        .context("accuracy check requires an opensim-log")?;
+    let divergences = match read(opensim_log) {
+        Ok(output) => tolerances
+            .check(output, reference)
+            .into_iter()
+            .map(|d| (format!("{ACCURACY_CHECK}: {}", d.label), d.message))
+            .collect(),
+        Err(err) => vec![(ACCURACY_CHECK.to_owned(), format!("{err:#}"))],
+    };
+
+    if !divergences.is_empty() {
+        warn!(
+            "Output of {} diverged from reference {:?} in {} channels",
+            test.output.name,
+            reference_log,
+            divergences.len()
+        );
+    }
+    for (check, message) in divergences {
+        debug!("{check}: {message}");
+        test.output.validation_failures.push(ValidationFailure {
+            check,
+            iteration,
+            message,
+        });
+    }
+    Ok(())
+}
+
+/// Writes the stdout and stderr of the benchmark to the result directory, returning the paths.
+fn write_benchmark_logs(
+    test: &BenchTestCtxt,
+    output: &CommandOutput,
+) -> Result<(PathBuf, PathBuf)> {
+    let stdout_log = test.result_dir.join("osimperf-stdout.log");
+    let stderr_log = test.result_dir.join("osimperf-stderr.log");
+    output.write_stdout(&stdout_log)?;
+    output.write_stderr(&stderr_log)?;
+    Ok((stdout_log, stderr_log))
+}
+
+/// Prefix of the validation failures of the accuracy check.
+static ACCURACY_CHECK: &str = "accuracy";
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ReadBenchTestSetup {
     pub name: String,
     pub cell_name: Option<String>,
@@ -418,6 +769,14 @@ pub struct ReadBenchTestSetup {
     /// Number of repeats for this test.
     pub repeats: Option<usize>,
     pub opensim_log: Option<PathBuf>,
+    /// Reference output that the opensim-log is checked against after the benchmark.
+    pub reference_log: Option<PathBuf>,
+    /// Tolerances of the opensim-log channels, the first tolerance matching a channel is used.
+    pub tolerances: Option<Vec<Tolerance>>,
+    /// Run the commands using `/bin/sh -c`, to allow for redirects, globs, etc.
+    pub shell: Option<bool>,
+    /// Timeout in seconds of each command, after which it is killed.
+    pub timeout: Option<u64>,
 }
 
 impl Default for ReadBenchTestSetup {
@@ -441,6 +800,101 @@ impl Default for ReadBenchTestSetup {
             cell_name: None,
             repeats: None,
             opensim_log: None,
+            reference_log: None,
+            tolerances: None,
+            shell: None,
+            timeout: None,
+        }
+    }
+}
+
+// Hashed for detecting changes of the benchmark config. Options added later are only hashed when
+// set, such that existing configs keep their hash.
+impl Hash for ReadBenchTestSetup {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.cell_name.hash(state);
+        self.pre_benchmark_cmds.hash(state);
+        self.benchmark_cmd.hash(state);
+        self.post_benchmark_cmds.hash(state);
+        self.visualize_cmd.hash(state);
+        self.repeats.hash(state);
+        self.opensim_log.hash(state);
+        if let Some(reference_log) = self.reference_log.as_ref() {
+            ("reference_log", reference_log).hash(state);
+        }
+        if let Some(tolerances) = self.tolerances.as_ref() {
+            ("tolerances", tolerances).hash(state);
+        }
+        if self.shell == Some(true) {
+            "shell".hash(state);
+        }
+        if let Some(timeout) = self.timeout {
+            ("timeout", timeout).hash(state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The config before the reference log, tolerances, shell and timeout were added.
+    #[derive(Deserialize, Hash)]
+    struct LegacySetup {
+        name: String,
+        cell_name: Option<String>,
+        pre_benchmark_cmds: Option<Vec<String>>,
+        benchmark_cmd: String,
+        post_benchmark_cmds: Option<Vec<String>>,
+        visualize_cmd: Option<String>,
+        repeats: Option<usize>,
+        opensim_log: Option<PathBuf>,
+    }
+
+    static CONFIG: &str = r#"{
+        "name": "Rajagopal",
+        "cell_name": null,
+        "pre_benchmark_cmds": ["cp $SETUP/model.osim ."],
+        "benchmark_cmd": "opensim-cmd run-tool setup.xml",
+        "post_benchmark_cmds": null,
+        "visualize_cmd": null,
+        "repeats": 3,
+        "opensim_log": "output/states.sto"
+    }"#;
+
+    fn with_key(key: &str, value: &str) -> String {
+        CONFIG.replacen('{', &format!("{{\"{key}\": {value},"), 1)
+    }
+
+    #[test]
+    fn config_without_new_keys_keeps_hash() {
+        let config: ReadBenchTestSetup = serde_json::from_str(CONFIG).unwrap();
+        let legacy: LegacySetup = serde_json::from_str(CONFIG).unwrap();
+        assert_eq!(config_hash(&config), config_hash(&legacy));
+
+        // Unset options do not change the hash either.
+        for (key, value) in [
+            ("shell", "false"),
+            ("timeout", "null"),
+            ("tolerances", "null"),
+        ] {
+            let config: ReadBenchTestSetup = serde_json::from_str(&with_key(key, value)).unwrap();
+            assert_eq!(config_hash(&config), config_hash(&legacy), "{key}");
+        }
+    }
+
+    #[test]
+    fn new_keys_change_hash() {
+        let legacy: LegacySetup = serde_json::from_str(CONFIG).unwrap();
+        for (key, value) in [
+            ("shell", "true"),
+            ("timeout", "60"),
+            ("reference_log", "\"reference.sto\""),
+            ("tolerances", "[{\"absolute\": 0.1}]"),
+        ] {
+            let config: ReadBenchTestSetup = serde_json::from_str(&with_key(key, value)).unwrap();
+            assert_ne!(config_hash(&config), config_hash(&legacy), "{key}");
         }
     }
 }
@@ -0,0 +1,274 @@
+use super::plot_cmd::{
+    check_fingerprints, check_isolation, render_svg, ResultFilter, Table, TableCell,
+};
+use super::{ResultInfo, ResultStore};
+use crate::Estimator;
+use anyhow::{ensure, Context, Result};
+use clap::Args;
+use log::{debug, info};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Changes relative to the reference above this percentage are highlighted.
+static HIGHLIGHT_PERCENTAGE: f64 = 5.;
+
+/// Result files that are copied to the report, and linked from the table.
+static LINKED_FILES: [(&str, &str); 2] = [
+    ("osimperf-stdout.log", "stdout"),
+    ("osimperf-stderr.log", "stderr"),
+];
+
+static CALLGRIND_PREFIX: &str = "callgrind.out.";
+
+static STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.4em 0.6em; text-align: right; vertical-align: top; }
+th { background: #f4f4f4; }
+td.slower { background: #fbe3e3; }
+td.faster { background: #e3f6e3; }
+.flag { color: #b00; font-weight: bold; }
+.links { font-size: 0.8em; }
+.links a { margin-left: 0.3em; }
+";
+
+#[derive(Debug, Args)]
+pub struct ReportCommand {
+    /// Path to result file (or reads from stdin).
+    #[arg(long)]
+    results: Option<PathBuf>,
+
+    /// Read the results from the result store at this path, instead of the result files.
+    #[arg(long, conflicts_with = "results")]
+    db: Option<PathBuf>,
+
+    /// Output directory of the html report.
+    #[arg(long)]
+    html: PathBuf,
+
+    /// Install to compare against, by name, commit, date or build variant.
+    ///
+    /// The reference is shown in the table, even if excluded by `--install`.
+    #[arg(long, short, default_value = "Latest")]
+    reference: String,
+
+    /// Statistic of the durations to show.
+    #[arg(long, short, value_enum, default_value_t = Estimator::Mean)]
+    estimator: Estimator,
+
+    /// Only include benchmarks with a matching name (regex).
+    #[arg(long, short)]
+    benchmark: Option<Regex>,
+
+    /// Only include installs with a matching name (regex).
+    #[arg(long, short)]
+    install: Option<Regex>,
+
+    /// Show results recorded on different machines as separate installs.
+    #[arg(long)]
+    separate_machines: bool,
+}
+
+impl ReportCommand {
+    pub fn run(&self) -> Result<()> {
+        info!("Start OSimPerf report command");
+        let store = self.db.as_deref().map(ResultStore::open).transpose()?;
+        let filter = ResultFilter {
+            benchmark: self.benchmark.clone(),
+            install: self.install.clone(),
+        };
+        let table = Table::new(
+            &self.results,
+            store.as_ref(),
+            &Some(self.reference.clone()),
+            &filter,
+            self.separate_machines,
+        )?;
+        ensure!(!table.results.is_empty(), "no results to report");
+        check_fingerprints(&table.results, self.separate_machines)?;
+        check_isolation(&table.results);
+
+        fs::create_dir_all(&self.html)
+            .with_context(|| format!("failed to create report directory {:?}", self.html))?;
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>OSimPerf report</title>\n");
+        html.push_str(&format!("<style>{STYLE}</style>\n</head>\n<body>\n"));
+        html.push_str("<h1>OSimPerf report</h1>\n");
+        html.push_str(&format!(
+            "<p>Generated on {}. Showing the {:?} duration in seconds, with the change relative to \
+            install {}.</p>\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M"),
+            self.estimator,
+            escape(&self.reference),
+        ));
+
+        html.push_str("<h2>Results</h2>\n");
+        self.write_table(&table, &mut html)?;
+
+        html.push_str("<h2>Timeline</h2>\n");
+        html.push_str(&render_svg(
+            &table.results,
+            self.estimator,
+            self.separate_machines,
+        )?);
+        html.push_str("\n</body>\n</html>\n");
+
+        let index = self.html.join("index.html");
+        fs::write(&index, html).with_context(|| format!("failed to write report {:?}", index))?;
+        info!("Wrote report to {:?}", index);
+        Ok(())
+    }
+
+    fn write_table(&self, table: &Table, html: &mut String) -> Result<()> {
+        html.push_str("<table>\n<tr><th></th>");
+        if let Some(row) = table.into_iter().next() {
+            for cell in row {
+                html.push_str(&format!("<th>{}</th>", escape(cell.col_name)));
+            }
+        }
+        html.push_str("</tr>\n");
+
+        for row in table {
+            html.push_str(&format!("<tr><th>{}</th>", escape(row.row_name())));
+            for cell in row {
+                self.write_cell(table, &cell, html)?;
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        Ok(())
+    }
+
+    fn write_cell(&self, table: &Table, cell: &TableCell, html: &mut String) -> Result<()> {
+        let Some(result) = cell.result else {
+            html.push_str("<td></td>");
+            return Ok(());
+        };
+        let percentage = cell.percentage(self.estimator);
+        let class = match percentage {
+            Some(p) if p > HIGHLIGHT_PERCENTAGE => " class=\"slower\"",
+            Some(p) if p < -HIGHLIGHT_PERCENTAGE => " class=\"faster\"",
+            _ => "",
+        };
+        html.push_str(&format!("<td{class}>"));
+
+        if result.timed_out() {
+            html.push_str("<span class=\"flag\">TIMEOUT</span> ");
+        }
+        if result.failed() {
+            html.push_str("<span class=\"flag\">FAILED</span> ");
+        }
+        if !result.is_valid() {
+            html.push_str("<span class=\"flag\">INVALID</span> ");
+        }
+        let durations = &result.durations;
+        html.push_str(&format!(
+            "{:.3}",
+            durations.get_estimate(self.estimator).unwrap_or(f64::NAN)
+        ));
+        if let Some(spread) = durations.get_spread(self.estimator) {
+            html.push_str(&format!(" &plusmn; {:.3}", spread));
+        }
+        if let Some(percentage) = percentage {
+            html.push_str(&format!("<br>{:+.1}%", percentage));
+        }
+        if let Some(log_diff) = cell.log_diff() {
+            html.push_str(&format!("<br>error {:.3e}", log_diff));
+        }
+
+        let links = match table.result_dir(result) {
+            Some(dir) => self.copy_linked_files(result, dir)?,
+            None => Vec::new(),
+        };
+        if !links.is_empty() {
+            html.push_str("<br><span class=\"links\">");
+            for (name, href) in links {
+                html.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(&href),
+                    escape(&name)
+                ));
+            }
+            html.push_str("</span>");
+        }
+        html.push_str("</td>");
+        Ok(())
+    }
+
+    /// Copies the logs and callgrind outputs of a result to the report.
+    ///
+    /// Returns the link names and the paths relative to the report.
+    fn copy_linked_files(&self, result: &ResultInfo, dir: &Path) -> Result<Vec<(String, String)>> {
+        let mut files: Vec<(String, PathBuf)> = Vec::new();
+        for (file_name, name) in LINKED_FILES {
+            files.push((name.to_owned(), dir.join(file_name)));
+        }
+        if let Some(log) = result.opensim_log.as_ref() {
+            files.push((String::from("opensim log"), log.clone()));
+        }
+        if let Ok(entries) = fs::read_dir(dir) {
+            let mut callgrind = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(CALLGRIND_PREFIX))
+                })
+                .collect::<Vec<PathBuf>>();
+            callgrind.sort();
+            for path in callgrind {
+                files.push((String::from("callgrind"), path));
+            }
+        }
+
+        // Each result gets its own directory, named after the benchmark, install and machine.
+        let mut install = format!("{}_{}", result.opensim_name, result.date);
+        if let Some(variant) = result.variant.as_ref() {
+            install.push_str(&format!("_{variant}"));
+        }
+        if let Some(fingerprint) = result
+            .fingerprint
+            .as_ref()
+            .filter(|_| self.separate_machines)
+        {
+            install.push_str(&format!("_{}", fingerprint.hostname));
+        }
+        let relative_dir = PathBuf::from("files")
+            .join(sanitize(&result.name))
+            .join(sanitize(&install));
+        let mut links = Vec::new();
+        for (name, path) in files.into_iter().filter(|(_, path)| path.is_file()) {
+            let relative = relative_dir.join(path.file_name().unwrap());
+            let target = self.html.join(&relative);
+            debug!("Copy {:?} to {:?}", path, target);
+            fs::create_dir_all(target.parent().unwrap())?;
+            fs::copy(&path, &target)
+                .with_context(|| format!("failed to copy {:?} to {:?}", path, target))?;
+            links.push((name, relative.to_str().unwrap().to_owned()));
+        }
+        Ok(links)
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Replaces characters that are not safe in a file name.
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
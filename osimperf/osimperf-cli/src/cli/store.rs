@@ -0,0 +1,112 @@
+use super::ResultInfo;
+use crate::DB_ENV_VAR;
+use anyhow::{Context, Result};
+use osimperf_results::STORE_SCHEMA;
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+
+/// SQLite database of benchmark results.
+#[derive(Debug)]
+pub struct ResultStore {
+    conn: Connection,
+}
+
+impl ResultStore {
+    /// Opens the database, creating it if it does not exist.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open result store at {:?}", path))?;
+        conn.execute_batch(STORE_SCHEMA)
+            .with_context(|| format!("failed to create tables of result store at {:?}", path))?;
+        Ok(Self { conn })
+    }
+
+    /// Opens the database at the argument, or at the path set by the env var.
+    pub fn open_arg_or_env_var(arg: &Option<PathBuf>) -> Result<Option<Self>> {
+        arg.clone()
+            .or_else(|| std::env::var_os(DB_ENV_VAR).map(PathBuf::from))
+            .map(|path| Self::open(&path))
+            .transpose()
+    }
+
+    /// Inserts the result, or replaces the result with the same path, including its iterations.
+    pub fn write_result(&mut self, path: &Path, result: &ResultInfo) -> Result<()> {
+        let path_str = path.to_str().context("result path is not valid unicode")?;
+        let info = serde_json::to_string(result)?;
+        let tx = self.conn.transaction()?;
+        let id: i64 = tx
+            .query_row(
+                "INSERT INTO results
+                    (path, name, opensim_name, commit_hash, date, hostname, outcome, failed_count, info)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT (path) DO UPDATE SET
+                    name = excluded.name,
+                    opensim_name = excluded.opensim_name,
+                    commit_hash = excluded.commit_hash,
+                    date = excluded.date,
+                    hostname = excluded.hostname,
+                    outcome = excluded.outcome,
+                    failed_count = excluded.failed_count,
+                    info = excluded.info
+                RETURNING id",
+                params![
+                    path_str,
+                    result.name,
+                    result.opensim_name,
+                    result.commit,
+                    result.date,
+                    result.fingerprint.as_ref().map(|f| &f.hostname),
+                    format!("{:?}", result.outcome),
+                    result.failed_count as i64,
+                    info,
+                ],
+                |row| row.get(0),
+            )
+            .with_context(|| format!("failed to store result {:?}", path))?;
+
+        tx.execute("DELETE FROM iterations WHERE result_id = ?1", [id])?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO iterations (result_id, iteration, duration, max_rss, cpu_time, instructions)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            // One resource usage and perf counter sample is recorded per duration.
+            for (i, duration) in result.durations.get().iter().enumerate() {
+                let usage = result.resource_usage.get(i);
+                let instructions = result
+                    .perf_counters
+                    .get(i)
+                    .and_then(|c| c.as_ref()?.instructions);
+                insert.execute(params![
+                    id,
+                    i as i64,
+                    duration.as_secs_f64(),
+                    usage.map(|u| u.max_rss as i64),
+                    usage.map(|u| u.cpu_time().as_secs_f64()),
+                    instructions.map(|x| x as i64),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Reads all results together with the path of their result file, ordered by path.
+    pub fn read_results(&self) -> Result<Vec<(PathBuf, ResultInfo)>> {
+        let mut select = self
+            .conn
+            .prepare("SELECT path, info FROM results ORDER BY path")?;
+        let rows = select.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            let (path, info) = row?;
+            let path = PathBuf::from(path);
+            let result = ResultInfo::from_json(&info, &path)
+                .with_context(|| format!("failed to parse stored result {:?}", path))?;
+            out.push((path, result));
+        }
+        Ok(out)
+    }
+}
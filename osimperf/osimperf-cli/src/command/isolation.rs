@@ -0,0 +1,115 @@
+use anyhow::{ensure, Result};
+use nix::errno::Errno;
+use nix::libc;
+use osimperf_results::{CpuFrequency, Isolation};
+use std::path::Path;
+
+static SYS_CPU_DIR: &str = "/sys/devices/system/cpu";
+
+/// Checks that the cpus of the isolation settings are available to this process.
+pub fn verify_isolation(isolation: &Isolation) -> Result<()> {
+    let allowed = allowed_cpus()?;
+    for cpu in isolation.cpus.iter() {
+        ensure!(
+            allowed.contains(cpu),
+            "cpu {cpu} is not available: allowed cpus = {:?}",
+            allowed
+        );
+    }
+    Ok(())
+}
+
+/// Returns a hook for [std::os::unix::process::CommandExt::pre_exec] that applies the settings.
+pub(crate) fn pre_exec_hook(
+    isolation: &Isolation,
+) -> impl FnMut() -> std::io::Result<()> + Send + Sync {
+    // Prepare the cpu set before forking, the hook should only make async-signal-safe calls.
+    let cpu_set = (!isolation.cpus.is_empty()).then(|| {
+        // SAFETY: An all zero cpu set is valid.
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for &cpu in isolation.cpus.iter() {
+            // SAFETY: CPU_SET checks the cpu index against the size of the set.
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+        set
+    });
+    let nice = isolation.nice;
+    let realtime_priority = isolation.realtime_priority;
+
+    move || {
+        let check = |ret: libc::c_int| match ret {
+            -1 => Err(std::io::Error::last_os_error()),
+            _ => Ok(()),
+        };
+        // SAFETY: All pointers refer to values that live for the duration of the calls.
+        unsafe {
+            if let Some(set) = cpu_set.as_ref() {
+                check(libc::sched_setaffinity(
+                    0,
+                    std::mem::size_of::<libc::cpu_set_t>(),
+                    set,
+                ))?;
+            }
+            if let Some(nice) = nice {
+                check(libc::setpriority(libc::PRIO_PROCESS, 0, nice))?;
+            }
+            if let Some(priority) = realtime_priority {
+                let param = libc::sched_param {
+                    sched_priority: priority,
+                };
+                check(libc::sched_setscheduler(0, libc::SCHED_FIFO, &param))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the cpus this process is allowed to run on.
+fn allowed_cpus() -> Result<Vec<usize>> {
+    // SAFETY: An all zero cpu set is valid.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    // SAFETY: The set is valid for the duration of the call.
+    let ret =
+        unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+    Errno::result(ret)?;
+    Ok((0..libc::CPU_SETSIZE as usize)
+        // SAFETY: The index is within the size of the set.
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect())
+}
+
+/// Reads the cpu frequency scaling settings of the given cpus, or of all cpus if empty.
+pub fn read_cpu_frequency(cpus: &[usize]) -> Result<CpuFrequency> {
+    let cpus = if cpus.is_empty() {
+        allowed_cpus()?
+    } else {
+        cpus.to_vec()
+    };
+    let sys = Path::new(SYS_CPU_DIR);
+
+    let mut governors = Vec::new();
+    for cpu in cpus {
+        let path = sys
+            .join(format!("cpu{cpu}"))
+            .join("cpufreq")
+            .join("scaling_governor");
+        if let Some(governor) = read_trimmed(&path) {
+            if !governors.contains(&governor) {
+                governors.push(governor);
+            }
+        }
+    }
+
+    // Intel reports disabled turbo, other drivers report enabled boost.
+    let turbo = read_trimmed(&sys.join("intel_pstate").join("no_turbo"))
+        .map(|no_turbo| no_turbo == "0")
+        .or_else(|| read_trimmed(&sys.join("cpufreq").join("boost")).map(|boost| boost == "1"));
+
+    Ok(CpuFrequency { governors, turbo })
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_owned())
+}
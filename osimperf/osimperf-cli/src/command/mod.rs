@@ -1,11 +1,22 @@
+mod isolation;
+mod perf_counters;
 mod piped_command;
+mod resource_usage;
 mod single_command;
 
+pub use isolation::{read_cpu_frequency, verify_isolation};
+pub use perf_counters::{perf_event_paranoid, PerfCounterSet};
 pub use piped_command::{PipedCommands, PipedCommandsExecutor};
+pub use resource_usage::wait_with_usage;
+pub use shell_words::{escape_vars, quote};
 pub use single_command::{Command, CommandExecutor};
+pub use osimperf_common::Watchdog;
 
-use anyhow::{anyhow, Context, Result};
-use std::io::BufReader;
+pub use osimperf_results::{CpuFrequency, Isolation, PerfCounters, ResourceUsage};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use osimperf_common::shell_words;
+use std::io::{BufReader, Read};
 use serde::{Deserialize, Serialize};
 use std::thread;
 use std::{
@@ -13,7 +24,6 @@ use std::{
     fs::OpenOptions,
     io::{BufRead, Write},
     path::Path,
-    path::PathBuf,
     time::Duration,
 };
 
@@ -24,6 +34,10 @@ pub struct CommandOutput {
     pub cmd_str: String,
     pub duration: Duration,
     pub output: std::process::Output,
+    pub usage: ResourceUsage,
+    pub counters: Option<PerfCounters>,
+    /// The command was killed after exceeding its timeout.
+    pub timed_out: bool,
 }
 
 impl CommandOutput {
@@ -49,7 +63,7 @@ impl CommandOutput {
                 "failed to open file for writing stdout logs at path = {:?}",
                 path
             ))?;
-        file.write_all(&format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
+        file.write_all(format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
         file.write_all(&self.output.stdout)?;
         Ok(())
     }
@@ -64,7 +78,7 @@ impl CommandOutput {
                 "failed to open file for writing stderr logs at path = {:?}",
                 path
             ))?;
-        file.write_all(&format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
+        file.write_all(format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
         file.write_all(&self.output.stderr)?;
         Ok(())
     }
@@ -77,6 +91,7 @@ impl CommandOutput {
     }
 
     pub fn into_duration(self) -> Result<Duration> {
+        ensure!(!self.timed_out, "command timed out:\n{}", self.cmd_str);
         if self.success() {
             Ok(self.duration)
         } else {
@@ -106,16 +121,25 @@ pub trait CommandTrait {
         let output = cmd.execute();
         let end = duration_since_boot()?;
         let duration = end - start;
+        let (output, usage, timed_out) = output
+            .with_context(|| format!("failed to execute command: {}", self.print_command()))?;
         Ok(CommandOutput {
             cmd_str: self.print_command(),
             duration,
-            output: output
-                .with_context(|| format!("failed to execute command: {}", self.print_command()))?,
+            output,
+            usage,
+            counters: None,
+            timed_out,
         })
     }
 
     fn run_stdout(&self) -> Result<Vec<u8>> {
         let output = self.run_and_time()?;
+        ensure!(
+            !output.timed_out,
+            "command timed out: {}",
+            self.print_command()
+        );
         Some(())
             .filter(|_| output.success())
             .with_context(|| format!("stdout: {:#?}", output.stdout_str_clone()))
@@ -136,10 +160,12 @@ pub trait CommandTrait {
     fn run_and_stream(&self, stream: &mut impl Write) -> Result<CommandOutput> {
         // Construct command.
         let cmd = self.create_executor();
+        let timeout = cmd.timeout();
 
         // Exectute command and start timer.
         let start = duration_since_boot()?;
         let mut child = cmd.start_execute()?;
+        let watchdog = timeout.map(|timeout| Watchdog::start(child.id(), timeout));
 
         // Access the stdout and stderr of the child process.
         let stderr = child.stderr.take().expect("Failed to capture stderr");
@@ -182,13 +208,16 @@ pub trait CommandTrait {
             .join()
             .expect("Failed to join stderr thread")?;
 
-        let mut output = child
-            .wait_with_output()
+        let (status, usage) = wait_with_usage(&child)
             .context("error waiting for command output")
             .with_context(|| format!("failed to execute command: {}", self.print_command()))?;
+        let timed_out = watchdog.map(Watchdog::stop).unwrap_or(false);
 
-        output.stdout.extend(stdout_buffer);
-        output.stderr.extend(stderr_result);
+        let output = std::process::Output {
+            status,
+            stdout: stdout_buffer,
+            stderr: stderr_result,
+        };
 
         let end = duration_since_boot()?;
         let duration = end - start;
@@ -196,32 +225,73 @@ pub trait CommandTrait {
             cmd_str: self.print_command(),
             duration,
             output,
+            usage,
+            counters: None,
+            timed_out,
         })
     }
 }
 
-pub trait CommandExecutorTrait {
-    fn execute(self) -> Result<std::process::Output>;
-
+pub trait CommandExecutorTrait: Sized {
     fn start_execute(self) -> Result<std::process::Child>;
-}
 
-pub(crate) fn substitute_if_present(string: &mut String, key: &str, value: &str) -> Option<()> {
-    while let Some(start) = string.find(key) {
-        let end = start + key.len();
-        let start = string.find(key)?;
-        string.replace_range(start..end, value);
+    /// Time after which the command is killed.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Runs the command to completion, collecting the output, resource usage, and whether it was
+    /// killed after timing out.
+    fn execute(self) -> Result<(std::process::Output, ResourceUsage, bool)> {
+        let timeout = self.timeout();
+        collect_output(self.start_execute()?, timeout)
     }
-    Some(())
 }
 
+/// Waits for the child to exit, collecting the output and resource usage.
+///
+/// If a timeout is given, the process group of the child is killed once it expires.
+pub(crate) fn collect_output(
+    mut child: std::process::Child,
+    timeout: Option<Duration>,
+) -> Result<(std::process::Output, ResourceUsage, bool)> {
+    let watchdog = timeout.map(|timeout| Watchdog::start(child.id(), timeout));
+    let mut stderr = child.stderr.take().context("Failed to capture stderr")?;
+    let mut stdout = child.stdout.take().context("Failed to capture stdout")?;
+
+    // Read stderr in thread, to prevent blocking the child on a full pipe.
+    let stderr_handle = thread::spawn(move || -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        stderr.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    });
+
+    let mut stdout_buffer = Vec::new();
+    stdout.read_to_end(&mut stdout_buffer)?;
+    let stderr_buffer = stderr_handle
+        .join()
+        .expect("Failed to join stderr thread")?;
+
+    let (status, usage) = wait_with_usage(&child)?;
+    let timed_out = watchdog.map(Watchdog::stop).unwrap_or(false);
+    Ok((
+        std::process::Output {
+            status,
+            stdout: stdout_buffer,
+            stderr: stderr_buffer,
+        },
+        usage,
+        timed_out,
+    ))
+}
+
+/// Expands `$KEY` and `${KEY}` of the env vars, see [shell_words::expand_vars].
 pub(crate) fn substitute_all(string: &str, envs: &[crate::EnvVar]) -> String {
-    let mut out = String::from(string);
-    for env in envs.iter() {
-        let dollar_key = format!("${}", env.key);
-        _ = substitute_if_present(&mut out, &dollar_key, &env.value);
-    }
-    out
+    shell_words::expand_vars(string, |key| {
+        envs.iter()
+            .find(|env| env.key == key)
+            .map(|env| env.value.as_str())
+    })
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Hash)]
@@ -231,7 +301,7 @@ pub struct EnvVar {
 }
 
 impl EnvVar {
-    pub fn new(key: &str, value: &PathBuf) -> Self {
+    pub fn new(key: &str, value: &Path) -> Self {
         Self {
             key: key.to_owned(),
             value: value.to_str().unwrap().to_owned(),
@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use nix::errno::Errno;
+use nix::libc;
+use std::fs::File;
+use std::io::Read;
+use osimperf_results::PerfCounters;
+use std::os::fd::{FromRawFd, RawFd};
+
+const PERF_EVENT_PARANOID: &str = "/proc/sys/kernel/perf_event_paranoid";
+
+const PERF_TYPE_HARDWARE: u32 = 0;
+
+const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+
+const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+
+const PERF_FLAG_DISABLED: u64 = 1 << 0;
+const PERF_FLAG_INHERIT: u64 = 1 << 1;
+const PERF_FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+const PERF_FLAG_EXCLUDE_HV: u64 = 1 << 6;
+const PERF_FLAG_ENABLE_ON_EXEC: u64 = 1 << 12;
+
+/// Layout of `struct perf_event_attr` (PERF_ATTR_SIZE_VER5).
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+    config2: u64,
+    branch_sample_type: u64,
+    sample_regs_user: u64,
+    sample_stack_user: u32,
+    clockid: i32,
+    sample_regs_intr: u64,
+    aux_watermark: u32,
+    sample_max_stack: u16,
+    reserved: u16,
+}
+
+/// Open hardware counters, attached to a single process.
+#[derive(Debug)]
+pub struct PerfCounterSet {
+    instructions: Option<File>,
+    cycles: Option<File>,
+    cache_misses: Option<File>,
+    branch_misses: Option<File>,
+}
+
+/// Returns the value of `perf_event_paranoid`, if it can be read.
+pub fn perf_event_paranoid() -> Option<i32> {
+    std::fs::read_to_string(PERF_EVENT_PARANOID)
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
+
+fn perf_event_open(config: u64, pid: libc::pid_t) -> Result<File, Errno> {
+    let attr = PerfEventAttr {
+        kind: PERF_TYPE_HARDWARE,
+        size: std::mem::size_of::<PerfEventAttr>() as u32,
+        config,
+        read_format: PERF_FORMAT_TOTAL_TIME_ENABLED | PERF_FORMAT_TOTAL_TIME_RUNNING,
+        // Counting starts when the process calls exec, and includes its children. Kernel events
+        // are excluded, which is allowed for perf_event_paranoid <= 2.
+        flags: PERF_FLAG_DISABLED
+            | PERF_FLAG_INHERIT
+            | PERF_FLAG_EXCLUDE_KERNEL
+            | PERF_FLAG_EXCLUDE_HV
+            | PERF_FLAG_ENABLE_ON_EXEC,
+        ..Default::default()
+    };
+    // SAFETY: attr is a valid perf_event_attr, and outlives the call.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_perf_event_open,
+            &attr as *const PerfEventAttr,
+            pid,
+            -1 as libc::c_int,
+            -1 as libc::c_int,
+            0 as libc::c_ulong,
+        )
+    };
+    if fd == -1 {
+        return Err(Errno::last());
+    }
+    // SAFETY: the syscall returned a new file descriptor that we now own.
+    Ok(unsafe { File::from_raw_fd(fd as RawFd) })
+}
+
+/// Reads the counter value, scaled for the time it was actually running when multiplexed.
+fn read_counter(file: &mut File) -> Result<Option<u64>> {
+    let mut buf = [0u8; 24];
+    file.read_exact(&mut buf)
+        .context("failed to read perf counter")?;
+    let word = |i: usize| u64::from_ne_bytes(buf[i * 8..(i + 1) * 8].try_into().unwrap());
+    let (value, enabled, running) = (word(0), word(1), word(2));
+    if running == 0 {
+        return Ok(None);
+    }
+    Ok(Some((value as f64 * enabled as f64 / running as f64).round() as u64))
+}
+
+impl PerfCounterSet {
+    /// Opens the counters for process `pid`, which start counting once it calls exec.
+    ///
+    /// Fails if none of the counters could be opened, for example due to `perf_event_paranoid`.
+    pub fn open(pid: u32) -> Result<Self> {
+        let pid = pid as libc::pid_t;
+        let mut errors = Vec::new();
+        let mut open = |config| {
+            perf_event_open(config, pid)
+                .map_err(|err| errors.push(err))
+                .ok()
+        };
+        let out = Self {
+            instructions: open(PERF_COUNT_HW_INSTRUCTIONS),
+            cycles: open(PERF_COUNT_HW_CPU_CYCLES),
+            cache_misses: open(PERF_COUNT_HW_CACHE_MISSES),
+            branch_misses: open(PERF_COUNT_HW_BRANCH_MISSES),
+        };
+        if let Some(err) = errors.first().filter(|_| errors.len() == 4) {
+            let mut msg = format!("failed to open perf counters: {err}");
+            if matches!(err, Errno::EACCES | Errno::EPERM) {
+                if let Some(level) = perf_event_paranoid() {
+                    msg.push_str(&format!(
+                        " (perf_event_paranoid = {level}, at most 2 is required)"
+                    ));
+                }
+            }
+            anyhow::bail!(msg);
+        }
+        Ok(out)
+    }
+
+    /// Checks if perf counters can be opened for child processes.
+    pub fn probe() -> Result<()> {
+        // Counting the current process requires the same permissions as counting a child.
+        Self::open(0).map(|_| ())
+    }
+
+    /// Reads the counters, should be called after the process exited.
+    pub fn read(&mut self) -> Result<PerfCounters> {
+        let read = |file: &mut Option<File>| -> Result<Option<u64>> {
+            Ok(match file.as_mut() {
+                Some(f) => read_counter(f)?,
+                None => None,
+            })
+        };
+        Ok(PerfCounters {
+            instructions: read(&mut self.instructions)?,
+            cycles: read(&mut self.cycles)?,
+            cache_misses: read(&mut self.cache_misses)?,
+            branch_misses: read(&mut self.branch_misses)?,
+        })
+    }
+}
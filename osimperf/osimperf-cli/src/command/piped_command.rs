@@ -68,10 +68,6 @@ impl CommandExecutorTrait for PipedCommandsExecutor {
 
         Err(anyhow!("How did we end up here?"))
     }
-
-    fn execute(self) -> Result<std::process::Output> {
-        Ok(self.start_execute()?.wait_with_output()?)
-    }
 }
 
 impl PipedCommands {
@@ -83,22 +79,23 @@ impl PipedCommands {
         self.cmds.push(cmd);
     }
 
+    /// Splits the string on unquoted `|`, and parses each command using [Command::parse].
     pub fn parse(string: &str) -> Self {
-        let mut pipe = Self { cmds: Vec::new() };
-        let split = string.split('|');
-        for s in split {
-            if !s.is_empty() {
-                pipe.cmds.push(Command::parse(s));
-            }
+        let (commands, _) = shell_words::split_words(string, true);
+        Self {
+            cmds: commands
+                .into_iter()
+                .filter(|words| !words.is_empty())
+                .map(Command::from_words)
+                .collect(),
         }
-        pipe
     }
 }
 
 impl CommandTrait for PipedCommands {
     type Executor = PipedCommandsExecutor;
     fn create_executor(&self) -> Self::Executor {
-        if self.cmds.len() == 0 {
+        if self.cmds.is_empty() {
             panic!();
         }
 
@@ -110,7 +107,7 @@ impl CommandTrait for PipedCommands {
     }
 
     fn print_command_with_delim(&self, arg_delim: &str) -> String {
-        if self.cmds.len() == 0 {
+        if self.cmds.is_empty() {
             return String::from("empty command");
         }
         let mut iter = self.cmds.iter();
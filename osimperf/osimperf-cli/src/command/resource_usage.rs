@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+use nix::errno::Errno;
+use nix::libc;
+use osimperf_results::ResourceUsage;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, ExitStatus};
+use std::time::Duration;
+
+fn to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
+
+fn from_rusage(usage: &libc::rusage) -> ResourceUsage {
+    ResourceUsage {
+        max_rss: usage.ru_maxrss as u64,
+        user_time: to_duration(usage.ru_utime),
+        system_time: to_duration(usage.ru_stime),
+        minor_page_faults: usage.ru_minflt as u64,
+        major_page_faults: usage.ru_majflt as u64,
+        voluntary_context_switches: usage.ru_nvcsw as u64,
+        involuntary_context_switches: usage.ru_nivcsw as u64,
+    }
+}
+
+/// Waits for the child to exit, and collects its resource usage.
+///
+/// The child is reaped by this call: do not wait on it again afterwards.
+pub fn wait_with_usage(child: &Child) -> Result<(ExitStatus, ResourceUsage)> {
+    let pid = child.id() as libc::pid_t;
+    let mut status: libc::c_int = 0;
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    loop {
+        // SAFETY: status and usage point to valid memory for the duration of the call.
+        let ret = unsafe { libc::wait4(pid, &mut status, 0, usage.as_mut_ptr()) };
+        if ret != -1 {
+            break;
+        }
+        match Errno::last() {
+            Errno::EINTR => continue,
+            err => Err(err).with_context(|| format!("failed to wait for child process {pid}"))?,
+        }
+    }
+    // SAFETY: wait4 succeeded, and filled in the usage.
+    let usage = unsafe { usage.assume_init() };
+    Ok((ExitStatus::from_raw(status), from_rusage(&usage)))
+}
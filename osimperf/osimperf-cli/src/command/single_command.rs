@@ -1,8 +1,20 @@
-use super::{substitute_all, CommandExecutorTrait, CommandTrait};
-use super::EnvVar;
-use anyhow::Result;
+use super::shell_words::{escape_vars, quote, split_words};
+use super::{collect_output, substitute_all, CommandExecutorTrait, CommandTrait};
+use super::isolation::pre_exec_hook;
+use super::{CommandOutput, EnvVar, Isolation, PerfCounterSet, PerfCounters, ResourceUsage};
+use crate::duration_since_boot;
+use anyhow::{ensure, Context, Result};
+use log::warn;
+use nix::errno::Errno;
+use nix::fcntl::OFlag;
+use nix::unistd::{close, getpid, pipe2, read, write};
 use serde::{Deserialize, Serialize};
 use std::hash::Hash;
+use std::io::Read;
+use std::os::fd::FromRawFd;
+use std::os::unix::process::CommandExt;
+use std::thread;
+use std::time::Duration;
 use std::{path::Path, process::Stdio};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Hash)]
@@ -11,11 +23,17 @@ pub struct Command {
     args: Vec<String>,
     envs: Option<Vec<EnvVar>>,
     root: Option<String>,
+    #[serde(default)]
+    timeout: Option<Duration>,
+    #[serde(default)]
+    isolation: Option<Isolation>,
 }
 
 #[derive(Debug)]
 pub struct CommandExecutor {
     cmd: std::process::Command,
+    timeout: Option<Duration>,
+    isolation: Option<Isolation>,
 }
 
 impl CommandExecutor {
@@ -24,17 +42,87 @@ impl CommandExecutor {
     }
 }
 
-impl CommandExecutorTrait for CommandExecutor {
-    fn execute(mut self) -> Result<std::process::Output> {
-        Ok(self.get_mut().output()?)
+impl CommandExecutor {
+    /// Runs the command while counting hardware events using perf counters.
+    ///
+    /// The child is held before exec until the counters are attached. If the counters cannot be
+    /// opened the command still runs, without counting.
+    pub fn execute_with_counters(
+        mut self,
+    ) -> Result<(
+        std::process::Output,
+        ResourceUsage,
+        Option<PerfCounters>,
+        bool,
+    )> {
+        let (pid_read, pid_write) = pipe2(OFlag::O_CLOEXEC).context("failed to create pipe")?;
+        let (gate_read, gate_write) = pipe2(OFlag::O_CLOEXEC).context("failed to create pipe")?;
+        // SAFETY: Only async-signal-safe calls are made between fork and exec.
+        unsafe {
+            self.cmd.pre_exec(move || {
+                let _ = close(pid_read);
+                let _ = close(gate_write);
+                // Send our pid, and block until the parent closes its end of the gate.
+                let _ = write(pid_write, &getpid().as_raw().to_ne_bytes());
+                let mut buf = [0u8; 1];
+                while let Err(Errno::EINTR) = read(gate_read, &mut buf) {}
+                Ok(())
+            });
+        }
+
+        // Spawning blocks until the child calls exec, so the counters are attached from a thread.
+        // SAFETY: We own these ends of the pipes, the child closes its own copies.
+        let mut pid_pipe = unsafe { std::fs::File::from_raw_fd(pid_read) };
+        let gate = unsafe { std::fs::File::from_raw_fd(gate_write) };
+        let attach = thread::spawn(move || -> Result<PerfCounterSet> {
+            // Releases the child when dropped.
+            let _gate = gate;
+            let mut buf = [0u8; 4];
+            pid_pipe
+                .read_exact(&mut buf)
+                .context("failed to read pid of child")?;
+            PerfCounterSet::open(i32::from_ne_bytes(buf) as u32)
+        });
+
+        let timeout = self.timeout;
+        let child = self.start_execute();
+        let _ = close(pid_write);
+        let _ = close(gate_read);
+        let child = child?;
+        let counters = attach
+            .join()
+            .expect("Failed to join perf counter thread")
+            .map_err(|err| warn!("Continuing without perf counters: {err:#}"))
+            .ok();
+
+        let (output, usage, timed_out) = collect_output(child, timeout)?;
+        let counters = counters.map(|mut c| c.read()).transpose()?;
+        Ok((output, usage, counters, timed_out))
     }
+}
 
+impl CommandExecutorTrait for CommandExecutor {
     fn start_execute(mut self) -> Result<std::process::Child> {
+        self.cmd.stdin(Stdio::null());
         self.cmd.stdout(Stdio::piped());
         self.cmd.stderr(Stdio::piped());
+        // Start a new process group, such that the child and its descendants can be killed on timeout.
+        if self.timeout.is_some() {
+            self.cmd.process_group(0);
+        }
+        if let Some(isolation) = self.isolation.as_ref() {
+            // SAFETY: The hook only makes async-signal-safe calls.
+            unsafe {
+                self.cmd.pre_exec(pre_exec_hook(isolation));
+            }
+        }
 
         Ok(self.cmd.spawn()?)
     }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 }
 
 impl Command {
@@ -44,6 +132,8 @@ impl Command {
             args: Vec::new(),
             envs: None,
             root: None,
+            timeout: None,
+            isolation: None,
         }
     }
 
@@ -80,22 +170,92 @@ impl Command {
         self
     }
 
+    pub(super) fn from_words(mut words: Vec<String>) -> Self {
+        let mut cmd = Self::new(if words.is_empty() {
+            String::new()
+        } else {
+            words.remove(0)
+        });
+        cmd.args = words;
+        cmd
+    }
+
+    /// Splits the string into a command and arguments, following shell quoting rules.
+    ///
+    /// Quotes and backslash escapes are handled, and `$KEY` or `${KEY}` are expanded using the
+    /// env vars of this command. Other shell syntax is passed on literally.
     pub fn parse(string: &str) -> Self {
-        let mut split = string.split(' ');
-        let mut cmd = Self::new(split.next().unwrap());
-        for s in split {
-            if !s.is_empty() {
-                cmd.add_arg(s);
-            }
-        }
+        let (mut commands, _) = split_words(string, false);
+        Self::from_words(commands.remove(0))
+    }
+
+    /// Same as [Command::parse], but fails on syntax that requires a shell (e.g. redirects and
+    /// globs), or unterminated quotes.
+    pub fn try_parse(string: &str) -> Result<Self> {
+        let (mut commands, unsupported) = split_words(string, false);
+        ensure!(
+            unsupported.is_empty(),
+            "unsupported syntax in command {:?}: {} (requires a shell)",
+            string,
+            unsupported.join(", ")
+        );
+        let words = commands.remove(0);
+        ensure!(!words.is_empty(), "command is empty");
+        Ok(Self::from_words(words))
+    }
+
+    /// Runs the script using `/bin/sh -c`.
+    ///
+    /// The env vars are not substituted in the script, but are available to the shell.
+    pub fn shell(script: &str) -> Self {
+        let mut cmd = Self::new("/bin/sh");
+        cmd.add_arg("-c");
+        cmd.add_arg(escape_vars(script));
         cmd
     }
 
+    /// Appends the program and arguments of the other command as arguments to this command.
+    pub fn append(&mut self, other: &Command) {
+        self.args.push(other.cmd.clone());
+        self.args.extend(other.args.iter().cloned());
+    }
+
     #[must_use]
     pub fn set_run_root(mut self, root: &Path) -> Self {
         self.root = Some(String::from(root.to_str().unwrap()));
         self
     }
+
+    /// Kills the command, and any processes it started, if it runs longer than the timeout.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Pins the command to cpus, and sets its scheduling priority.
+    #[must_use]
+    pub fn set_isolation(mut self, isolation: Option<Isolation>) -> Self {
+        self.isolation = isolation;
+        self
+    }
+
+    /// Same as [CommandTrait::run_and_time], but also counts hardware events.
+    pub fn run_and_count(&self) -> Result<CommandOutput> {
+        let cmd = self.create_executor();
+        let start = duration_since_boot()?;
+        let output = cmd.execute_with_counters();
+        let end = duration_since_boot()?;
+        let duration = end - start;
+        let (output, usage, counters, timed_out) = output
+            .with_context(|| format!("failed to execute command: {}", self.print_command()))?;
+        Ok(CommandOutput {
+            cmd_str: self.print_command(),
+            duration,
+            output,
+            usage,
+            counters,
+            timed_out,
+        })
+    }
 }
 
 impl CommandTrait for Command {
@@ -126,31 +286,33 @@ impl CommandTrait for Command {
                 cmd.env(&env.key, &env.value);
             }
         }
-        CommandExecutor { cmd }
+        CommandExecutor {
+            cmd,
+            timeout: self.timeout,
+            isolation: self.isolation.clone(),
+        }
     }
 
     fn print_command_with_delim(&self, arg_delim: &str) -> String {
         let mut msg = String::new();
         if let Some(envs) = self.envs.as_ref() {
             for env in envs.iter() {
-                msg.push_str(&format!("{}={} ", env.key, env.value));
+                msg.push_str(&format!("{}={} ", env.key, quote(&env.value)));
             }
         }
         if let Some(root) = self.root.as_ref() {
-            msg.push_str(&format!("env -C {}{}", root, arg_delim));
+            let root = then_substitute_all(root, &self.envs);
+            msg.push_str(&format!("env -C {}{}", quote(&root), arg_delim));
         }
-        msg.push_str(&self.cmd);
+        msg.push_str(&quote(&then_substitute_all(&self.cmd, &self.envs)));
         for arg in self.args.iter() {
             msg.push_str(arg_delim);
-            msg.push_str(&arg);
+            msg.push_str(&quote(&then_substitute_all(arg, &self.envs)));
         }
-        then_substitute_all(&msg, &self.envs)
+        msg
     }
 }
 
 pub fn then_substitute_all(string: &str, key_value: &Option<Vec<EnvVar>>) -> String {
-    if let Some(env) = key_value {
-        return substitute_all(string, env);
-    }
-    String::from(string)
+    substitute_all(string, key_value.as_deref().unwrap_or_default())
 }
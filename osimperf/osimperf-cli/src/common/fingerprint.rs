@@ -0,0 +1,120 @@
+use super::find_file_by_name;
+use anyhow::{Context, Result};
+use log::debug;
+use osimperf_results::Fingerprint;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Reads the fingerprint of this machine, combined with the build info of the install.
+pub fn read_fingerprint(build: CMakeBuildInfo) -> Result<Fingerprint> {
+    let cpuinfo = read_proc("/proc/cpuinfo")?;
+    let meminfo = read_proc("/proc/meminfo")?;
+    let fingerprint = Fingerprint {
+        hostname: read_proc("/proc/sys/kernel/hostname")?.trim().to_owned(),
+        cpu_model: proc_values(&cpuinfo, "model name")
+            .next()
+            .unwrap_or("unknown")
+            .to_owned(),
+        cpu_cores: proc_values(&cpuinfo, "processor").count(),
+        memory: proc_values(&meminfo, "MemTotal")
+            .next()
+            .and_then(|kb| kb.trim_end_matches("kB").trim().parse().ok())
+            .unwrap_or_default(),
+        kernel: read_proc("/proc/sys/kernel/osrelease")?.trim().to_owned(),
+        compiler: build.compiler,
+        build_type: build.build_type,
+    };
+    Ok(fingerprint)
+}
+
+fn read_proc(path: &str) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))
+}
+
+/// Returns the values of all `key : value` lines with the given key.
+fn proc_values<'a>(content: &'a str, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    content.lines().filter_map(move |line| {
+        let (k, v) = line.split_once(':')?;
+        (k.trim() == key).then_some(v.trim())
+    })
+}
+
+/// Compiler, build type and options of an install, read from the cmake cache of its build.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct CMakeBuildInfo {
+    pub compiler: Option<String>,
+    pub build_type: Option<String>,
+    /// The build options, as `-DKEY=VALUE`.
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+impl CMakeBuildInfo {
+    /// Searches the build directory for the cmake cache of opensim-core.
+    pub fn read(build_dir: &Path) -> Self {
+        let mut out = Self::default();
+        for cache in find_file_by_name(build_dir, "CMakeCache.txt") {
+            let Ok(content) = std::fs::read_to_string(&cache) else {
+                continue;
+            };
+            if cache_value(&content, "CMAKE_PROJECT_NAME") != Some("OpenSim") {
+                continue;
+            }
+            debug!("Reading build info from {:?}", cache);
+            out.build_type = cache_value(&content, "CMAKE_BUILD_TYPE").map(String::from);
+            out.compiler = read_compiler_version(cache.parent().unwrap())
+                .or_else(|| cache_value(&content, "CMAKE_CXX_COMPILER").map(String::from));
+            out.flags = cache_flags(&content);
+            break;
+        }
+        out
+    }
+}
+
+/// Returns the value of a `KEY:TYPE=VALUE` line in the cmake cache.
+fn cache_value<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        Some(v).filter(|v| !v.is_empty() && k.split(':').next() == Some(key))
+    })
+}
+
+/// Cache entries that affect the build.
+static CMAKE_FLAG_PREFIXES: [&str; 4] =
+    ["BUILD_", "OPENSIM_", "CMAKE_BUILD_TYPE", "CMAKE_CXX_FLAGS"];
+
+/// Returns the non-internal cache entries with one of the [CMAKE_FLAG_PREFIXES], as `-DKEY=VALUE`.
+fn cache_flags(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            let (key, kind) = k.split_once(':')?;
+            let internal = kind == "INTERNAL" || kind == "STATIC";
+            let selected = CMAKE_FLAG_PREFIXES.iter().any(|p| key.starts_with(p));
+            (selected && !internal && !v.is_empty()).then(|| format!("-D{key}={v}"))
+        })
+        .collect()
+}
+
+/// Reads compiler id and version from the `CMakeFiles/*/CMakeCXXCompiler.cmake`.
+fn read_compiler_version(build_dir: &Path) -> Option<String> {
+    let path = std::fs::read_dir(build_dir.join("CMakeFiles"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path().join("CMakeCXXCompiler.cmake"))
+        .find(|path| path.exists())?;
+    let content = std::fs::read_to_string(path).ok()?;
+    let get = |key: &str| {
+        content.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix(&format!("set({key} \""))?
+                .strip_suffix("\")")
+        })
+    };
+    Some(format!(
+        "{} {}",
+        get("CMAKE_CXX_COMPILER_ID")?,
+        get("CMAKE_CXX_COMPILER_VERSION")?
+    ))
+}
@@ -11,12 +11,12 @@ pub fn read_current_branch(repo: &Path) -> Result<String> {
     cmd.add_arg("rev-parse");
     cmd.add_arg("--abbrev-ref");
     cmd.add_arg("HEAD");
-    Ok(cmd.run_trim()?)
+    cmd.run_trim()
 }
 
 pub fn commit_merged_to(repo: &Path, hash: &str) -> Result<String> {
     PipedCommands::parse(&format!(
-        r#"git -C {} branch --contains {} --no-color|sed -E s/\*//"#,
+        r#"git -C {} branch --contains {} --no-color|sed -E 's/\*//'"#,
         repo.to_str().unwrap(),
         hash
     ))
@@ -34,7 +34,73 @@ pub fn read_current_commit(repo: &Path) -> Result<String> {
     cmd.add_arg(repo.to_str().unwrap());
     cmd.add_arg("rev-parse");
     cmd.add_arg("HEAD");
-    Ok(cmd.run_trim()?)
+    cmd.run_trim()
+}
+
+/// Returns the full hash of a commit, given any revision (short hash, branch, tag).
+pub fn resolve_commit(repo: &Path, rev: &str) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.add_arg("-C");
+    cmd.add_arg(repo.to_str().unwrap());
+    cmd.add_arg("rev-parse");
+    cmd.add_arg("--verify");
+    cmd.add_arg(format!("{rev}^{{commit}}"));
+    cmd.run_trim()
+        .with_context(|| format!("failed to resolve commit {rev}"))
+}
+
+/// Returns the full hashes of the commits in a range, e.g. `A..B` are the commits reachable from
+/// B, but not from A.
+pub fn get_commit_range(repo: &Path, range: &str) -> Result<Vec<String>> {
+    ensure!(
+        range.contains(".."),
+        "expected commit range A..B, got {range}"
+    );
+    let mut cmd = Command::new("git");
+    cmd.add_arg("-C");
+    cmd.add_arg(repo.to_str().unwrap());
+    cmd.add_arg("rev-list");
+    cmd.add_arg(range);
+    let output = cmd
+        .run_trim()
+        .with_context(|| format!("failed to resolve commit range {range}"))?;
+    Ok(output.lines().map(String::from).collect())
+}
+
+/// Returns true if the first commit is an ancestor of (or equal to) the second commit.
+pub fn is_ancestor(repo: &Path, ancestor: &str, hash: &str) -> Result<bool> {
+    let mut cmd = Command::new("git");
+    cmd.add_arg("-C");
+    cmd.add_arg(repo.to_str().unwrap());
+    cmd.add_arg("merge-base");
+    cmd.add_arg("--is-ancestor");
+    cmd.add_arg(ancestor);
+    cmd.add_arg(hash);
+    Ok(cmd.run_and_time()?.success())
+}
+
+/// Returns the commits on the first-parent ancestry path from `good` to `bad`, excluding `good`,
+/// newest first.
+pub fn get_ancestry_path(repo: &Path, good: &str, bad: &str) -> Result<Vec<Commit>> {
+    let mut cmd = Command::new("git");
+    cmd.add_arg("-C");
+    cmd.add_arg(repo.to_str().unwrap());
+    cmd.add_arg("rev-list");
+    cmd.add_arg("--ancestry-path");
+    cmd.add_arg("--first-parent");
+    cmd.add_arg("--no-commit-header");
+    cmd.add_arg("--format=%H,%cs");
+    cmd.add_arg(format!("{good}..{bad}"));
+    let output = cmd.run()?;
+
+    let mut commits = Vec::new();
+    for line in output.lines() {
+        let mut split = line.split(',');
+        let hash = String::from(split.next().context("failed to read hash")?);
+        let date = parse_date(split.next().context("failed to read date")?)?;
+        commits.push(Commit::new(hash, date));
+    }
+    Ok(commits)
 }
 
 pub fn checkout_commit(repo: &Path, hash: &str) -> Result<()> {
@@ -42,7 +108,7 @@ pub fn checkout_commit(repo: &Path, hash: &str) -> Result<()> {
     cmd.add_arg("-C");
     cmd.add_arg(repo.to_str().unwrap());
     cmd.add_arg("checkout");
-    cmd.add_arg(&hash);
+    cmd.add_arg(hash);
     cmd.run()?;
     Ok(())
 }
@@ -61,8 +127,8 @@ pub fn switch_branch(repo: &Path, branch: &str) -> Result<()> {
 pub fn get_date(repo: &Path, hash: &str) -> Result<Date> {
     let path: &str = repo.to_str().unwrap();
     let cmd = Command::parse(&format!("git -C {path} show -s {hash} --pretty=format:%cs"));
-    Ok(parse_date(&cmd.run_trim()?)
-        .with_context(|| format!("failed to parse output of command {}", cmd.print_command()))?)
+    parse_date(&cmd.run_trim()?)
+        .with_context(|| format!("failed to parse output of command {}", cmd.print_command()))
 }
 
 /// Completes hash with date.
@@ -98,10 +164,10 @@ pub fn get_commits_since(
         "git -C {path} log {branch} --pretty=format:%H,%cs"
     ));
     if let Some(date) = after_date {
-        cmd.add_arg(format!("--after={}", date.format("%Y-%m-%d").to_string()));
+        cmd.add_arg(format!("--after={}", date.format("%Y-%m-%d")));
     }
     if let Some(date) = before_date {
-        cmd.add_arg(format!("--before={}", date.format("%Y-%m-%d").to_string()));
+        cmd.add_arg(format!("--before={}", date.format("%Y-%m-%d")));
     }
     let output = cmd.run()?;
 
@@ -1,10 +1,12 @@
 mod commit;
+#[allow(clippy::module_inception)]
 mod git;
 mod repo;
 
 pub use commit::Commit;
 pub use repo::{Repository, RepositoryState};
 pub use git::*;
+pub use osimperf_common::git::read_tree_hash;
 
 use anyhow::{Context, Result};
 
@@ -65,12 +65,12 @@ impl Repository {
     }
 
     pub fn last_commit_at_date(&self, date: &Date) -> anyhow::Result<Option<Commit>> {
-        let after = date.clone() - Days::new(1);
-        let commits = self.commits_between(Some(&after), Some(&date))?;
-        if commits.len() == 0 {
+        let after = *date - Days::new(1);
+        let commits = self.commits_between(Some(&after), Some(date))?;
+        if commits.is_empty() {
             return Ok(None);
         }
-        Ok(Some(commits.iter().next().unwrap().clone()))
+        Ok(Some(commits.first().unwrap().clone()))
     }
 
     pub fn collect_monthly_commits(
@@ -79,7 +79,7 @@ impl Repository {
         until: Option<&Date>,
     ) -> anyhow::Result<Vec<Commit>> {
         let mut commits = Vec::<Commit>::new();
-        for c in Self::commits_between(&self, since, until)?.drain(..) {
+        for c in Self::commits_between(self, since, until)?.drain(..) {
             if let Some(last) = commits.last() {
                 let d0 = format_date(&c.date()).split_at(7).0.to_string();
                 let d1 = format_date(&last.date()).split_at(7).0.to_string();
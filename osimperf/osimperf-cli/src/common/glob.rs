@@ -0,0 +1,16 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Converts a glob with `*` and `?` wildcards to a regex matching the whole name.
+pub fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).with_context(|| format!("invalid glob: {glob}"))
+}
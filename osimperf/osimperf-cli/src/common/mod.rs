@@ -1,13 +1,18 @@
 mod read_write_json;
 mod find;
 mod folder_size;
-mod durations;
-mod duration_since_boot;
+mod fingerprint;
+mod glob;
 
 pub use read_write_json::{read_json, write_json, write_default_json};
 pub use folder_size::folder_size;
-pub use durations::Durations;
+pub use osimperf_results::{
+    Durations, Estimator, Fingerprint, OutlierMethod, OutlierPolicy, Percentile,
+};
 pub use find::*;
-pub use duration_since_boot::duration_since_boot;
+pub use osimperf_common::duration_since_boot;
+pub use fingerprint::{read_fingerprint, CMakeBuildInfo};
+pub use glob::glob_to_regex;
+pub use osimperf_common::{remove_folder, REMOVING_FOLDER_PREFIX};
 
 pub mod git;
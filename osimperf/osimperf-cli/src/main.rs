@@ -1,4 +1,3 @@
-#![feature(absolute_path)]
 
 mod cli;
 mod command;
@@ -8,7 +7,7 @@ pub mod parse_logs;
 pub use command::*;
 pub use common::*;
 
-use cli::{InstallCommand, ListCommand, RecordCommand, PlotCommand, LogCommand, ReadBenchTestSetup};
+use cli::{BisectCommand, CompareCommand, DiffCommand, ImportCommand, InstallCommand, InstallInfoCommand, ListCommand, RecordCommand, PlotCommand, LogCommand, ReadBenchTestSetup, ReportCommand, PruneCommand};
 
 use std::path::PathBuf;
 
@@ -27,9 +26,17 @@ pub const MODELS_ENV_VAR: &str = "OSIMPERF_MODELS";
 pub const SETUP_ENV_VAR: &str = "OSIMPERF_SETUP";
 pub const CONTEXT_ENV_VAR: &str = "OSIMPERF_CONTEXT";
 
-pub static INSTALL_INFO_FILE_NAME: &'static str = "osimperf-install-info.json";
-pub static RESULT_INFO_FILE_NAME: &'static str = "osimperf-result-info.json";
-pub static TEST_CONFIG_FILE_NAME: &'static str = "osimperf-test.config";
+// Path to the optional result store.
+pub const DB_ENV_VAR: &str = "OSIMPERF_DB";
+
+// Available to the post-benchmark commands.
+pub const RESULT_DIR_ENV_VAR: &str = "OSIMPERF_RESULT_DIR";
+pub const STDOUT_LOG_ENV_VAR: &str = "OSIMPERF_STDOUT_LOG";
+pub const STDERR_LOG_ENV_VAR: &str = "OSIMPERF_STDERR_LOG";
+
+pub static INSTALL_INFO_FILE_NAME: &str = "osimperf-install-info.json";
+pub use osimperf_results::RESULT_INFO_FILE_NAME;
+pub static TEST_CONFIG_FILE_NAME: &str = "osimperf-test.config";
 
 /// A fictional versioning CLI
 #[derive(Debug, Parser)] // requires `derive` feature
@@ -48,12 +55,26 @@ enum Commands {
     Ls(ListCommand),
     /// Install dir.
     Install(InstallCommand),
+    /// Print the manifest of an install.
+    InstallInfo(InstallInfoCommand),
     /// Record test result.
     ///
     /// Description: Reads path to test config from stdin.
     Record(RecordCommand),
     /// Plot
     Plot(PlotCommand),
+    /// Write a static html report of the results.
+    Report(ReportCommand),
+    /// Compare two sets of results for significant changes.
+    Compare(CompareCommand),
+    /// Find the commit that introduced a slowdown.
+    Bisect(BisectCommand),
+    /// Import existing result files into the result store.
+    Import(ImportCommand),
+    /// Compare the channels of two simulation outputs.
+    Diff(DiffCommand),
+    /// Remove installs using retention policies and a disk budget.
+    Prune(PruneCommand),
     /// Write default test config file.
     #[command(arg_required_else_help = true)]
     WriteDefaultTestConfig { path: PathBuf },
@@ -72,8 +93,15 @@ fn do_main() -> Result<()> {
         Commands::Log(args) => args.run()?,
         Commands::Ls(mut args) => args.run()?,
         Commands::Install(args) => args.run()?,
+        Commands::InstallInfo(args) => args.run()?,
         Commands::Record(args) => args.run()?,
         Commands::Plot(args) => args.run()?,
+        Commands::Report(args) => args.run()?,
+        Commands::Compare(args) => args.run()?,
+        Commands::Bisect(args) => args.run()?,
+        Commands::Import(args) => args.run()?,
+        Commands::Diff(args) => args.run()?,
+        Commands::Prune(args) => args.run()?,
         Commands::WriteDefaultTestConfig { path } => {
             write_default_json::<ReadBenchTestSetup>(&path)?
         }
@@ -0,0 +1,191 @@
+use super::Data;
+use crate::glob_to_regex;
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// Allowed difference between a channel of the simulation output and the reference output.
+///
+/// A sample is accurate if `|value - reference| <= absolute + relative * |reference|`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Tolerance {
+    /// Glob of the channel labels this tolerance applies to (default is all channels).
+    pub channels: Option<String>,
+    /// Allowed absolute difference.
+    #[serde(default)]
+    pub absolute: f64,
+    /// Allowed difference relative to the reference value.
+    #[serde(default)]
+    pub relative: f64,
+    /// The channels are angles, converted to radians if a file has `inDegrees=yes`.
+    #[serde(default)]
+    pub angles: bool,
+}
+
+// Hashed for detecting changes of the benchmark config.
+impl Hash for Tolerance {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.channels.hash(state);
+        self.absolute.to_bits().hash(state);
+        self.relative.to_bits().hash(state);
+        self.angles.hash(state);
+    }
+}
+
+impl Tolerance {
+    fn allowed(&self, reference: f64) -> f64 {
+        self.absolute + self.relative * reference.abs()
+    }
+}
+
+/// A channel of the simulation output that is not within tolerance of the reference output.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub label: String,
+    pub message: String,
+}
+
+/// Tolerances of the channels, see [Tolerance].
+pub struct Tolerances<'a> {
+    tolerances: &'a [Tolerance],
+    globs: Vec<Option<Regex>>,
+}
+
+impl<'a> Tolerances<'a> {
+    pub fn new(tolerances: &'a [Tolerance]) -> Result<Self> {
+        let globs = tolerances
+            .iter()
+            .map(|t| t.channels.as_deref().map(glob_to_regex).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { tolerances, globs })
+    }
+
+    /// Returns the first tolerance that matches the channel label.
+    pub fn get(&self, label: &str) -> Option<&'a Tolerance> {
+        self.tolerances
+            .iter()
+            .zip(self.globs.iter())
+            .find(|(_, glob)| glob.as_ref().is_none_or(|g| g.is_match(label)))
+            .map(|(t, _)| t)
+    }
+
+    /// Checks the channels of the output against the reference output.
+    ///
+    /// Each channel of the reference is checked using the first tolerance that matches its label.
+    /// Channels without a matching tolerance are not checked. The output is interpolated at the
+    /// time of the reference samples, and must cover all of them. Angles are compared in radians.
+    pub fn check(&self, mut output: Data, mut reference: Data) -> Vec<Divergence> {
+        let is_angle = |label: &str| self.get(label).is_some_and(|t| t.angles);
+        output.convert_to_radians(is_angle);
+        reference.convert_to_radians(is_angle);
+
+        let mut divergences = Vec::new();
+        for expected in reference.channels() {
+            let label = expected.label();
+            let Some(tolerance) = self.get(label) else {
+                continue;
+            };
+            let mut diverge = |message: String| {
+                divergences.push(Divergence {
+                    label: label.clone(),
+                    message,
+                })
+            };
+
+            let Some(i) = output.find_label(label) else {
+                diverge("channel missing from output".to_owned());
+                continue;
+            };
+            let channel = &output.channels()[i];
+
+            // Find the sample that exceeds the tolerance the most, and the samples outside the
+            // time range of the output.
+            let mut compared = 0;
+            let mut uncovered = Vec::new();
+            let mut worst: Option<(f64, f64, f64, f64)> = None;
+            for sample in expected.data() {
+                let Some(value) = channel.find_interpolate(sample.time) else {
+                    uncovered.push(sample.time);
+                    continue;
+                };
+                compared += 1;
+                let excess = (value - sample.value).abs() - tolerance.allowed(sample.value);
+                // Fail on NaN.
+                let excess = if excess.is_nan() {
+                    f64::INFINITY
+                } else {
+                    excess
+                };
+                if excess > 0. && worst.is_none_or(|w| excess > w.0) {
+                    worst = Some((excess, sample.time, value, sample.value));
+                }
+            }
+
+            if compared == 0 {
+                diverge("no samples overlap in time with the reference".to_owned());
+            } else if let (Some(first), Some(last)) = (uncovered.first(), uncovered.last()) {
+                // E.g. the simulation stopped early.
+                diverge(format!(
+                    "{} reference samples between time {first} and {last} are outside the output",
+                    uncovered.len()
+                ));
+            } else if let Some((_, time, value, expected)) = worst {
+                diverge(format!(
+                    "{value} differs from reference {expected} at time {time} (allowed difference {})",
+                    tolerance.allowed(expected)
+                ));
+            }
+        }
+        divergences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_logs::FileFormat;
+
+    static REFERENCE: &str = "time\tknee\n0\t1\n0.1\t2\n0.2\t3\n0.3\t4\n";
+
+    fn check(output: &str) -> Vec<Divergence> {
+        let tolerances = [Tolerance {
+            absolute: 0.01,
+            ..Default::default()
+        }];
+        let parse = |text| Data::parse(text, FileFormat::Storage, |_| true).unwrap();
+        Tolerances::new(&tolerances)
+            .unwrap()
+            .check(parse(output), parse(REFERENCE))
+    }
+
+    #[test]
+    fn accepts_matching_output() {
+        assert!(check("time\tknee\n0\t1\n0.3\t4\n").is_empty());
+    }
+
+    #[test]
+    fn reports_differences() {
+        let divergences = check("time\tknee\n0\t1\n0.3\t5\n");
+        assert_eq!(divergences.len(), 1);
+        assert!(divergences[0].message.contains("at time 0.3"));
+    }
+
+    #[test]
+    fn reports_truncated_output() {
+        // The output stops halfway, but matches the reference until then.
+        let divergences = check("time\tknee\n0\t1\n0.1\t2\n");
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].label, "knee");
+        assert_eq!(
+            divergences[0].message,
+            "2 reference samples between time 0.2 and 0.3 are outside the output"
+        );
+    }
+
+    #[test]
+    fn reports_missing_channel() {
+        let divergences = check("time\thip\n0\t1\n0.3\t4\n");
+        assert_eq!(divergences[0].message, "channel missing from output");
+    }
+}
@@ -15,18 +15,37 @@ impl Channel {
         }
     }
 
+    /// Creates an empty channel, with space for the given number of samples.
+    pub fn with_capacity(label: impl ToString, capacity: usize) -> Self {
+        Self {
+            data: Vec::with_capacity(capacity),
+            label: label.to_string(),
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.data.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn label(&self) -> &String {
         &self.label
     }
 
+    /// Appends the sample, keeping the samples sorted by time.
+    ///
+    /// Appending in order of time takes constant time.
     pub fn append(&mut self, sample: TimeAndValue) -> Result<()> {
-        self.data.push(sample);
-        self.data
-            .sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        ensure!(!sample.time.is_nan(), "time is NaN");
+        if self.data.last().is_some_and(|last| last.time > sample.time) {
+            let i = self.data.partition_point(|x| x.time <= sample.time);
+            self.data.insert(i, sample);
+        } else {
+            self.data.push(sample);
+        }
         Ok(())
     }
 
@@ -34,24 +53,28 @@ impl Channel {
         self.data.as_ref()
     }
 
-    pub fn find_interpolate(&self, time: f64) -> Option<f64> {
-        for i_after in 0..self.data.len() {
-            if self.data[i_after].time <= time {
-                continue;
-            }
-            let i_before = i_after - 1;
-            let t0 = self.data[i_before].time;
-            let t1 = self.data[i_after].time;
-            let y0 = self.data[i_before].value;
-            let y1 = self.data[i_after].value;
-
-            let dt = t1 - t0;
-            let dy = y1 - y0;
+    /// Applies the function to all values, e.g. for converting units.
+    pub fn map_values(&mut self, f: impl Fn(f64) -> f64) {
+        for sample in self.data.iter_mut() {
+            sample.value = f(sample.value);
+        }
+    }
 
-            let y = y0 + dy / dt * (time - t0);
-            return Some(y);
+    /// Returns the value at the given time, linearly interpolating between samples.
+    ///
+    /// Returns None if the time is outside the sampled range.
+    pub fn find_interpolate(&self, time: f64) -> Option<f64> {
+        let i_after = self.data.partition_point(|x| x.time < time);
+        let after = self.data.get(i_after)?;
+        if after.time == time {
+            return Some(after.value);
         }
-        None
+        // Time is before the first sample.
+        let before = self.data.get(i_after.checked_sub(1)?)?;
+
+        let dt = after.time - before.time;
+        let dy = after.value - before.value;
+        Some(before.value + dy / dt * (time - before.time))
     }
 
     pub fn find_absolute_difference(&self, others: &[Self]) -> Option<f64> {
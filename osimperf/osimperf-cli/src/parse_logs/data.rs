@@ -1,19 +1,22 @@
 use crate::parse_logs::TimeAndValue;
 
 use super::channel::Channel;
+use super::Header;
 use anyhow::{ensure, Context, Result};
-use std::io::{BufRead, BufReader, Read};
+use log::warn;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 
 #[derive(Debug, Default)]
 pub struct Data {
     channels: Vec<Channel>,
+    header: Header,
 }
 
 impl Data {
     pub fn new() -> Self {
-        Self {
-            channels: Vec::new(),
-        }
+        Self::default()
     }
 
     pub fn print_info(&self) {
@@ -45,14 +48,18 @@ impl Data {
         None
     }
 
+    pub fn channel(&self, label: &str) -> Option<&Channel> {
+        self.channels.iter().find(|c| c.label() == label)
+    }
+
     fn get_labeled_or(&mut self, label: &str) -> usize {
-        let i = if let Some(i) = self.find_label(label) {
+        
+        if let Some(i) = self.find_label(label) {
             i
         } else {
             self.channels.push(Channel::new(String::from(label)));
             self.channels.len() - 1
-        };
-        i
+        }
     }
 
     pub fn add_sample(
@@ -76,87 +83,452 @@ impl Data {
         self.channels.iter().map(|c| c.label().clone()).collect()
     }
 
-    pub fn read_opensim_file(buffer: impl Read) -> Result<Self> {
-        let mut line = String::new();
+    /// Reads all channels of a storage, motion, marker or csv file, see [FileFormat].
+    pub fn read_file(path: &Path) -> Result<Self> {
+        Self::read_file_channels(path, |_| true)
+    }
+
+    /// Reads the channels for which `select` returns true, skipping the other columns.
+    pub fn read_file_channels(path: &Path, select: impl Fn(&str) -> bool) -> Result<Self> {
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .with_context(|| format!("failed to read {:?}", path))?;
+        Self::parse(&text, FileFormat::from_path(path), select)
+            .with_context(|| format!("failed to parse {:?}", path))
+    }
 
-        let mut reader = BufReader::new(buffer);
+    pub fn parse(text: &str, format: FileFormat, select: impl Fn(&str) -> bool) -> Result<Self> {
+        let mut lines = text.lines().enumerate().peekable();
+        let (header, labels, delimiter, time_column) = match format {
+            FileFormat::Storage => {
+                // The header is optional, and ends at `endheader` or at the row of labels.
+                let mut preamble = Vec::new();
+                let mut end_of_header = false;
+                while let Some(&(_, line)) = lines.peek() {
+                    if line.trim() == "endheader" || is_data_row(line) {
+                        end_of_header = line.trim() == "endheader";
+                        break;
+                    }
+                    preamble.push(line);
+                    lines.next();
+                }
+                let labels = if end_of_header {
+                    lines.next();
+                    lines
+                        .by_ref()
+                        .map(|(_, line)| line)
+                        .find(|line| !line.trim().is_empty())
+                } else {
+                    while preamble.last().is_some_and(|line| line.trim().is_empty()) {
+                        preamble.pop();
+                    }
+                    preamble.pop()
+                }
+                .context("no labels found")?;
 
-        // Find end of header.
-        while !line.contains("endheader") {
-            line.clear();
-            reader.read_line(&mut line)?;
+                let delimiter = Delimiter::detect(labels);
+                let mut labels = delimiter.split(labels);
+                labels.next().context("no time column found")?;
+                let labels = labels.map(String::from).collect::<Vec<_>>();
+                (Header::parse(preamble.into_iter()), labels, delimiter, 0)
+            }
+            FileFormat::Trc => {
+                let mut next_line = |what: &str| {
+                    lines
+                        .next()
+                        .map(|(_, line)| line)
+                        .with_context(|| format!("missing {what}"))
+                };
+                let file_type = next_line("file type")?;
+                let keys = next_line("header keys")?;
+                let values = next_line("header values")?;
+                let markers = next_line("marker names")?;
+                let components = next_line("marker components")?;
+
+                let header = Header {
+                    name: file_type.split_whitespace().last().map(String::from),
+                    metadata: keys
+                        .split_whitespace()
+                        .zip(values.split_whitespace())
+                        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                        .collect(),
+                };
+                // Each marker has three columns: X, Y and Z.
+                let markers = markers
+                    .split('\t')
+                    .skip(2)
+                    .map(str::trim)
+                    .filter(|marker| !marker.is_empty())
+                    .collect::<Vec<_>>();
+                let labels = components
+                    .split_whitespace()
+                    .enumerate()
+                    .map(|(i, component)| {
+                        let marker = markers
+                            .get(i / 3)
+                            .with_context(|| format!("no marker name for column {component}"))?;
+                        let axis = component.trim_end_matches(|c: char| c.is_ascii_digit());
+                        Ok(format!("{marker}/{axis}"))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (header, labels, Delimiter::Tab, 1)
+            }
+        };
+
+        if let Some(n_columns) = header.n_columns() {
+            if n_columns != labels.len() + 1 {
+                warn!(
+                    "header has nColumns={n_columns}, but found {} columns",
+                    labels.len() + 1
+                );
+            }
         }
 
-        // Read labels.
-        let mut labels = Vec::new();
-        line.clear();
-        reader.read_line(&mut line).context("no labels present")?;
-        let mut words = line.split("\t");
-        ensure!(
-            words.next().context("no labels found")?.trim() == "time",
-            "expected first label to be time"
-        );
-        for label in words {
-            labels.push(String::from(label.trim()));
+        // Only allocate the selected channels. The header row count is not trusted beyond the
+        // length of the text, as each row takes at least one character per column.
+        let max_rows = text.len() / (labels.len() + 1);
+        let capacity = header.n_rows().unwrap_or_default().min(max_rows);
+        let mut data = Data {
+            channels: Vec::new(),
+            header,
+        };
+        let columns = labels
+            .iter()
+            .map(|label| {
+                select(label).then(|| {
+                    data.channels.push(Channel::with_capacity(label, capacity));
+                    data.channels.len() - 1
+                })
+            })
+            .collect::<Vec<Option<usize>>>();
+
+        let mut n_rows = 0;
+        for (i, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+            read_row(
+                delimiter.split(line),
+                time_column,
+                &columns,
+                &mut data.channels,
+                format == FileFormat::Trc,
+            )
+            .with_context(|| format!("failed to read line {}", i + 1))?;
+            n_rows += 1;
         }
 
-        // Read data.
-        let mut data = Data::new();
-        loop {
-            line.clear();
-            if reader.read_line(&mut line)? == 0 {
-                break;
+        if let Some(expected) = data.header.n_rows() {
+            if expected != n_rows {
+                warn!("header has {expected} rows, but found {n_rows} rows");
             }
+        }
+        Ok(data)
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
 
-            let mut words = line.trim().split("\t");
-
-            let time = words
-                .next()
-                .context("failed to read time")?
-                .trim()
-                .parse::<f64>()
-                .context("failed to parse time")?;
-            for label in labels.iter_mut() {
-                let svalue = words.next().context("failed to read value")?.trim();
-                let value = svalue.parse::<f64>().context("failed to parse value")?;
-                data.add_sample(label.trim(), TimeAndValue { time, value })?;
+    /// Converts the angles from degrees to radians, if the header has `inDegrees=yes`.
+    ///
+    /// The header does not specify which channels are angles, these are selected by `is_angle`.
+    pub fn convert_to_radians(&mut self, is_angle: impl Fn(&str) -> bool) {
+        if self.header.in_degrees() != Some(true) {
+            return;
+        }
+        for channel in self.channels.iter_mut() {
+            if is_angle(channel.label()) {
+                channel.map_values(f64::to_radians);
             }
         }
+        self.header
+            .metadata
+            .insert(String::from("inDegrees"), String::from("no"));
+    }
+}
 
-        Ok(data)
+/// Format of a file with time series, detected from the file extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    /// OpenSim storage (`.sto`), motion (`.mot`) or `.csv` file: An optional header, followed by
+    /// a row of labels and rows of values. The first column is the time.
+    Storage,
+    /// Marker trajectories (`.trc`), with a channel per marker and axis, e.g. `LASI/X`.
+    Trc,
+}
+
+impl FileFormat {
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("trc") => Self::Trc,
+            _ => Self::Storage,
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct ChannelDiff {
-    pub label: String,
-    pub diff: Option<f64>,
+/// Separator of the values in a row.
+#[derive(Clone, Copy, Debug)]
+enum Delimiter {
+    Tab,
+    Comma,
+    Whitespace,
 }
 
-#[derive(Debug)]
-pub struct Diff {
-    pub channels: Vec<ChannelDiff>,
+impl Delimiter {
+    /// Detects the delimiter from the row of labels.
+    fn detect(labels: &str) -> Self {
+        if labels.contains('\t') {
+            Self::Tab
+        } else if labels.contains(',') {
+            Self::Comma
+        } else {
+            Self::Whitespace
+        }
+    }
+
+    fn split<'a>(self, line: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match self {
+            Self::Tab => Box::new(line.split('\t').map(str::trim)),
+            Self::Comma => Box::new(line.split(',').map(str::trim)),
+            Self::Whitespace => Box::new(line.split_whitespace()),
+        }
+    }
 }
 
-impl Diff {
-    pub fn new(a: &Data, b: &Data) -> Result<Self> {
-        let mut channels = Vec::new();
-        for channel in a.channels.iter() {
-            let label = channel.label().to_owned();
-            let diff = if let Some(diff_a) = channel.find_absolute_difference(&b.channels) {
-                let i = b
-                    .find_label(channel.label())
-                    .with_context(|| format!("found label in b but not in a"))?;
-                if let Some(diff_b) = b.channels[i].find_absolute_difference(&a.channels) {
-                    Some((diff_a + diff_b) / 2.)
-                } else {
-                    Some(diff_a)
-                }
-            } else {
-                None
-            };
-            channels.push(ChannelDiff { label, diff });
+/// Returns true if the line starts with a number, i.e. is not part of the header or labels.
+fn is_data_row(line: &str) -> bool {
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .find(|word| !word.is_empty())
+        .is_some_and(|word| word.parse::<f64>().is_ok())
+}
+
+/// Reads a row of values into the selected channels.
+///
+/// Only the values of the selected columns are parsed. Empty values are missing samples, and rows
+/// of markers may leave out the trailing missing samples.
+fn read_row<'a>(
+    mut values: impl Iterator<Item = &'a str>,
+    time_column: usize,
+    columns: &[Option<usize>],
+    channels: &mut [Channel],
+    allow_missing: bool,
+) -> Result<()> {
+    let time = values
+        .nth(time_column)
+        .map(parse_value)
+        .transpose()?
+        .flatten()
+        .context("missing time")?;
+    for (column, channel) in columns.iter().enumerate() {
+        let Some(value) = values.next() else {
+            ensure!(
+                allow_missing,
+                "expected {} values, found {column}",
+                columns.len()
+            );
+            break;
+        };
+        if let Some(channel) = channel {
+            if let Some(value) = parse_value(value)? {
+                channels[*channel].append(TimeAndValue { time, value })?;
+            }
+        }
+    }
+    ensure!(
+        values.all(str::is_empty),
+        "expected {} values, found more",
+        columns.len()
+    );
+    Ok(())
+}
+
+/// Parses a value, returning None if the value is empty.
+fn parse_value(value: &str) -> Result<Option<f64>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    value
+        .parse::<f64>()
+        .map(Some)
+        .with_context(|| format!("failed to parse value {:?}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static STORAGE: &str = "Coordinates
+version=1
+nRows=2
+nColumns=3
+inDegrees=yes
+endheader
+time\tknee\thip
+0\t90\t180
+0.1\t45\t0
+";
+
+    static TRC: &str = "PathFileType\t4\t(X/Y/Z)\tmarkers.trc
+DataRate\tCameraRate\tNumFrames\tNumMarkers\tUnits
+100\t100\t2\t2\tmm
+Frame#\tTime\tLASI\t\t\tRASI
+\t\tX1\tY1\tZ1\tX2\tY2\tZ2
+1\t0\t1\t2\t3\t4\t5\t6
+2\t0.01\t1.5\t2.5\t3.5
+";
+
+    fn values(data: &Data, label: &str) -> Vec<f64> {
+        let channel = data.channel(label).unwrap();
+        channel.data().iter().map(|x| x.value).collect()
+    }
+
+    fn labels(data: &Data) -> Vec<&str> {
+        data.channels().iter().map(|c| c.label().as_str()).collect()
+    }
+
+    #[test]
+    fn detects_delimiter() {
+        assert!(matches!(Delimiter::detect("time\ta,b c"), Delimiter::Tab));
+        assert!(matches!(Delimiter::detect("time,a b"), Delimiter::Comma));
+        assert!(matches!(
+            Delimiter::detect("time  a b"),
+            Delimiter::Whitespace
+        ));
+
+        for text in [
+            "time,a,b\n0,1,2\n",
+            "time a  b\n0 1  2\n",
+            "time\ta b\tc\n0\t1\t2\n",
+        ] {
+            let data = Data::parse(text, FileFormat::Storage, |_| true).unwrap();
+            assert_eq!(data.channels().len(), 2, "{text:?}");
+            assert_eq!(values(&data, data.channels()[1].label()), [2.]);
         }
-        Ok(Self { channels })
+        // Labels may contain spaces if the columns are separated by tabs.
+        let data = Data::parse("time\ta b\tc\n0\t1\t2\n", FileFormat::Storage, |_| true).unwrap();
+        assert_eq!(labels(&data), ["a b", "c"]);
+    }
+
+    #[test]
+    fn parses_storage_header() {
+        let data = Data::parse(STORAGE, FileFormat::Storage, |_| true).unwrap();
+        let header = data.header();
+        assert_eq!(header.name.as_deref(), Some("Coordinates"));
+        assert_eq!(header.version(), Some(1));
+        assert_eq!(header.n_rows(), Some(2));
+        assert_eq!(header.n_columns(), Some(3));
+        assert_eq!(header.in_degrees(), Some(true));
+        assert_eq!(labels(&data), ["knee", "hip"]);
+        assert_eq!(values(&data, "knee"), [90., 45.]);
+        assert_eq!(data.channel("hip").unwrap().data()[1].time, 0.1);
+    }
+
+    #[test]
+    fn ignores_huge_header_row_count() {
+        let text = STORAGE.replace("nRows=2", "nRows=1000000000000000000");
+        let data = Data::parse(&text, FileFormat::Storage, |_| true).unwrap();
+        assert_eq!(values(&data, "knee"), [90., 45.]);
+    }
+
+    #[test]
+    fn parses_storage_without_header() {
+        let data = Data::parse("\ntime,a\n0,1\n\n1,2\n", FileFormat::Storage, |_| true).unwrap();
+        assert!(data.header().name.is_none());
+        assert_eq!(values(&data, "a"), [1., 2.]);
+
+        // Empty values are missing samples.
+        let data = Data::parse("time,a,b\n0,,1\n1,2,3\n", FileFormat::Storage, |_| true).unwrap();
+        assert_eq!(values(&data, "a"), [2.]);
+        assert_eq!(values(&data, "b"), [1., 3.]);
+    }
+
+    #[test]
+    fn parses_trc_header() {
+        let data = Data::parse(TRC, FileFormat::Trc, |_| true).unwrap();
+        let header = data.header();
+        assert_eq!(header.name.as_deref(), Some("markers.trc"));
+        assert_eq!(header.get("Units"), Some("mm"));
+        assert_eq!(header.n_rows(), Some(2));
+        assert_eq!(
+            labels(&data),
+            ["LASI/X", "LASI/Y", "LASI/Z", "RASI/X", "RASI/Y", "RASI/Z"]
+        );
+        assert_eq!(values(&data, "LASI/Y"), [2., 2.5]);
+        assert_eq!(data.channel("LASI/Y").unwrap().data()[1].time, 0.01);
+        // Trailing missing markers are allowed.
+        assert_eq!(values(&data, "RASI/Z"), [6.]);
+    }
+
+    #[test]
+    fn detects_format_from_extension() {
+        assert_eq!(
+            FileFormat::from_path(Path::new("a/markers.TRC")),
+            FileFormat::Trc
+        );
+        assert_eq!(
+            FileFormat::from_path(Path::new("a/states.sto")),
+            FileFormat::Storage
+        );
+        assert_eq!(
+            FileFormat::from_path(Path::new("a/table.csv")),
+            FileFormat::Storage
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_rows() {
+        let parse = |text| Data::parse(text, FileFormat::Storage, |_| true);
+        assert!(parse("time,a\n0,1,2\n").is_err());
+        assert!(parse("time,a,b\n0,1\n").is_err());
+        assert!(parse("time,a\n0,x\n").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn selects_columns() {
+        let data = Data::parse(STORAGE, FileFormat::Storage, |label| label == "hip").unwrap();
+        assert_eq!(labels(&data), ["hip"]);
+        assert_eq!(values(&data, "hip"), [180., 0.]);
+
+        // Values of columns that are not selected are not parsed.
+        let data = Data::parse("time,a,b\n0,x,1\n", FileFormat::Storage, |label| {
+            label == "b"
+        });
+        assert_eq!(values(&data.unwrap(), "b"), [1.]);
+
+        let data = Data::parse(TRC, FileFormat::Trc, |label| label.starts_with("RASI")).unwrap();
+        assert_eq!(labels(&data), ["RASI/X", "RASI/Y", "RASI/Z"]);
+        assert_eq!(values(&data, "RASI/X"), [4.]);
+    }
+
+    #[test]
+    fn converts_degrees_to_radians() {
+        let mut data = Data::parse(STORAGE, FileFormat::Storage, |_| true).unwrap();
+        data.convert_to_radians(|label| label == "knee");
+        assert_eq!(
+            values(&data, "knee"),
+            [90f64.to_radians(), 45f64.to_radians()]
+        );
+        assert_eq!(values(&data, "hip"), [180., 0.]);
+        assert_eq!(data.header().in_degrees(), Some(false));
+
+        // Converting again has no effect.
+        data.convert_to_radians(|_| true);
+        assert_eq!(values(&data, "hip"), [180., 0.]);
+    }
+
+    #[test]
+    fn keeps_radians() {
+        let text = STORAGE.replace("inDegrees=yes", "inDegrees=no");
+        let mut data = Data::parse(&text, FileFormat::Storage, |_| true).unwrap();
+        data.convert_to_radians(|_| true);
+        assert_eq!(values(&data, "knee"), [90., 45.]);
+
+        let mut data = Data::parse("time,knee\n0,90\n", FileFormat::Storage, |_| true).unwrap();
+        data.convert_to_radians(|_| true);
+        assert_eq!(values(&data, "knee"), [90.]);
     }
 }
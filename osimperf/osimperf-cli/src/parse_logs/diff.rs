@@ -0,0 +1,307 @@
+use super::{Channel, Data};
+use serde::Serialize;
+
+/// Difference between a channel of two files.
+///
+/// The errors are computed at the samples of `a` that are in the overlapping time range,
+/// interpolating `b`. The errors are None if the channel is missing from either file, or if the
+/// time ranges do not overlap.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelDiff {
+    pub label: String,
+    pub in_a: bool,
+    pub in_b: bool,
+    /// Root mean square of the error.
+    pub rms: Option<f64>,
+    /// Largest absolute error.
+    pub max: Option<f64>,
+    /// Time of the largest absolute error.
+    pub time_of_max: Option<f64>,
+    /// Largest absolute error, relative to the largest absolute value of the channel in `b`.
+    pub relative: Option<f64>,
+    /// Fraction of the combined time range of both channels in which both have samples.
+    pub overlap: Option<f64>,
+}
+
+impl ChannelDiff {
+    fn new(label: &str, a: Option<&Channel>, b: Option<&Channel>) -> Self {
+        let mut out = Self {
+            label: label.to_owned(),
+            in_a: a.is_some(),
+            in_b: b.is_some(),
+            rms: None,
+            max: None,
+            time_of_max: None,
+            relative: None,
+            overlap: None,
+        };
+        let (Some(a), Some(b)) = (a, b) else {
+            return out;
+        };
+        let (Some((a_start, a_end)), Some((b_start, b_end))) = (time_range(a), time_range(b))
+        else {
+            return out;
+        };
+
+        let start = a_start.max(b_start);
+        let end = a_end.min(b_end);
+        let span = a_end.max(b_end) - a_start.min(b_start);
+        out.overlap = Some(if end < start {
+            0.
+        } else if span > 0. {
+            (end - start) / span
+        } else {
+            1.
+        });
+
+        let mut count = 0;
+        let mut sum_squared = 0.;
+        let mut max: Option<(f64, f64)> = None;
+        for sample in a.data() {
+            if sample.time < start || sample.time > end {
+                continue;
+            }
+            let Some(value) = b.find_interpolate(sample.time) else {
+                continue;
+            };
+            let error = (sample.value - value).abs();
+            count += 1;
+            sum_squared += error * error;
+            // Keep the first NaN as the largest error.
+            if max.is_none_or(|(m, _)| !m.is_nan() && (error.is_nan() || error > m)) {
+                max = Some((error, sample.time));
+            }
+        }
+        let Some((max, time_of_max)) = max else {
+            return out;
+        };
+
+        let scale = b.data().iter().map(|x| x.value.abs()).fold(0., f64::max);
+        out.rms = Some((sum_squared / count as f64).sqrt());
+        out.max = Some(max);
+        out.time_of_max = Some(time_of_max);
+        out.relative = Some(if max == 0. { 0. } else { max / scale });
+        out
+    }
+
+    /// Returns the reason that the errors are missing, if any.
+    pub fn note(&self) -> Option<&'static str> {
+        match (self.in_a, self.in_b) {
+            (false, _) => Some("missing in a"),
+            (_, false) => Some("missing in b"),
+            _ if self.max.is_none() => Some("no overlap"),
+            _ => None,
+        }
+    }
+}
+
+fn time_range(channel: &Channel) -> Option<(f64, f64)> {
+    Some((channel.data().first()?.time, channel.data().last()?.time))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diff {
+    pub channels: Vec<ChannelDiff>,
+}
+
+impl Diff {
+    /// Compares the channels of both files by label, including channels present in only one.
+    pub fn new(a: &Data, b: &Data) -> Self {
+        let mut channels = a
+            .channels()
+            .iter()
+            .map(|channel| {
+                ChannelDiff::new(channel.label(), Some(channel), b.channel(channel.label()))
+            })
+            .collect::<Vec<_>>();
+        channels.extend(
+            b.channels()
+                .iter()
+                .filter(|channel| a.channel(channel.label()).is_none())
+                .map(|channel| ChannelDiff::new(channel.label(), None, Some(channel))),
+        );
+        Self { channels }
+    }
+
+    /// Returns the largest relative error of all channels.
+    pub fn max_relative(&self) -> Option<f64> {
+        self.channels
+            .iter()
+            .filter_map(|c| c.relative)
+            .reduce(f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_logs::TimeAndValue;
+
+    fn channel(samples: &[(f64, f64)]) -> Channel {
+        let mut channel = Channel::new("q");
+        for &(time, value) in samples {
+            channel.append(TimeAndValue { time, value }).unwrap();
+        }
+        channel
+    }
+
+    fn channel_diff(a: &[(f64, f64)], b: &[(f64, f64)]) -> ChannelDiff {
+        ChannelDiff::new("q", Some(&channel(a)), Some(&channel(b)))
+    }
+
+    fn assert_close(a: Option<f64>, b: f64) {
+        let a = a.unwrap();
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    #[test]
+    fn identical_channels() {
+        let samples = [(0., 1.), (1., -2.), (2., 3.)];
+        let diff = channel_diff(&samples, &samples);
+        assert_eq!(diff.rms, Some(0.));
+        assert_eq!(diff.max, Some(0.));
+        assert_eq!(diff.relative, Some(0.));
+        assert_eq!(diff.overlap, Some(1.));
+        assert_eq!(diff.note(), None);
+    }
+
+    #[test]
+    fn rms_and_max() {
+        let diff = channel_diff(
+            &[(0., 3.), (1., 4.), (2., 0.)],
+            &[(0., 0.), (1., 0.), (2., 0.5)],
+        );
+        assert_close(diff.rms, ((9. + 16. + 0.25) / 3f64).sqrt());
+        assert_eq!(diff.max, Some(4.));
+        assert_eq!(diff.time_of_max, Some(1.));
+    }
+
+    #[test]
+    fn first_of_equal_errors_is_max() {
+        let diff = channel_diff(
+            &[(0., 1.), (1., 2.), (2., 3.)],
+            &[(0., 2.), (1., 3.), (2., 4.)],
+        );
+        assert_eq!(diff.rms, Some(1.));
+        assert_eq!(diff.max, Some(1.));
+        assert_eq!(diff.time_of_max, Some(0.));
+    }
+
+    #[test]
+    fn interpolates_b() {
+        let diff = channel_diff(&[(1., 2.)], &[(0., 0.), (2., 2.)]);
+        assert_eq!(diff.max, Some(1.));
+        assert_eq!(diff.time_of_max, Some(1.));
+    }
+
+    #[test]
+    fn relative_to_largest_value_of_b() {
+        let diff = channel_diff(&[(0., 1.), (1., 2.)], &[(0., 2.), (1., -4.)]);
+        assert_eq!(diff.max, Some(6.));
+        assert_eq!(diff.relative, Some(1.5));
+
+        // Relative to all samples of b, not only the overlapping ones.
+        let diff = channel_diff(&[(0., 1.)], &[(0., 0.), (1., 10.)]);
+        assert_eq!(diff.relative, Some(0.1));
+    }
+
+    #[test]
+    fn relative_to_zeros_is_infinite() {
+        let diff = channel_diff(&[(0., 1.), (1., 0.)], &[(0., 0.), (1., 0.)]);
+        assert_eq!(diff.max, Some(1.));
+        assert_eq!(diff.relative, Some(f64::INFINITY));
+
+        // Unless there is no error.
+        let diff = channel_diff(&[(0., 0.)], &[(0., 0.)]);
+        assert_eq!(diff.relative, Some(0.));
+    }
+
+    #[test]
+    fn nan_is_largest_error() {
+        let diff = channel_diff(&[(0., 5.), (1., f64::NAN), (2., 9.)], &[(0., 0.), (2., 0.)]);
+        assert!(diff.max.unwrap().is_nan());
+        assert_eq!(diff.time_of_max, Some(1.));
+    }
+
+    #[test]
+    fn partial_overlap() {
+        let diff = channel_diff(
+            &[(0., 0.), (1., 1.), (2., 2.)],
+            &[(1., 1.), (2., 3.), (3., 3.)],
+        );
+        assert_close(diff.overlap, 1. / 3.);
+        // Only the samples of a in [1, 2] are compared.
+        assert_eq!(diff.max, Some(1.));
+        assert_close(diff.rms, 0.5f64.sqrt());
+    }
+
+    #[test]
+    fn no_overlap() {
+        let diff = channel_diff(&[(0., 0.), (1., 1.)], &[(2., 0.), (3., 1.)]);
+        assert_eq!(diff.overlap, Some(0.));
+        assert_eq!(diff.rms, None);
+        assert_eq!(diff.max, None);
+        assert_eq!(diff.relative, None);
+        assert_eq!(diff.note(), Some("no overlap"));
+    }
+
+    #[test]
+    fn single_sample_overlap() {
+        let diff = channel_diff(&[(1., 2.)], &[(1., 1.)]);
+        assert_eq!(diff.overlap, Some(1.));
+        assert_eq!(diff.max, Some(1.));
+    }
+
+    #[test]
+    fn empty_channel() {
+        let diff = channel_diff(&[], &[(0., 1.)]);
+        assert_eq!(diff.overlap, None);
+        assert_eq!(diff.max, None);
+    }
+
+    #[test]
+    fn compares_channels_by_label() {
+        let mut a = Data::new();
+        let mut b = Data::new();
+        for (label, value) in [("both", 1.), ("only_a", 1.)] {
+            a.add_sample(label, TimeAndValue { time: 0., value })
+                .unwrap();
+        }
+        for (label, value) in [("only_b", 1.), ("both", 3.)] {
+            b.add_sample(label, TimeAndValue { time: 0., value })
+                .unwrap();
+        }
+
+        let diff = Diff::new(&a, &b);
+        let notes = diff
+            .channels
+            .iter()
+            .map(|c| (c.label.as_str(), c.note()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            [
+                ("both", None),
+                ("only_a", Some("missing in b")),
+                ("only_b", Some("missing in a"))
+            ]
+        );
+        assert_eq!(diff.channels[0].max, Some(2.));
+        assert_close(diff.max_relative(), 2. / 3.);
+    }
+
+    #[test]
+    fn max_relative_without_overlap() {
+        let mut a = Data::new();
+        a.add_sample(
+            "q",
+            TimeAndValue {
+                time: 0.,
+                value: 1.,
+            },
+        )
+        .unwrap();
+        let diff = Diff::new(&a, &Data::new());
+        assert_eq!(diff.max_relative(), None);
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+/// Header of a storage, motion or marker file.
+#[derive(Debug, Default, Clone)]
+pub struct Header {
+    /// Name on the first line of the header, if any.
+    pub name: Option<String>,
+    /// The `key=value` (or `key<TAB>value` for `.trc`) entries of the header.
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Header {
+    /// Parses the header lines, excluding `endheader`.
+    pub(crate) fn parse<'a>(lines: impl Iterator<Item = &'a str>) -> Self {
+        let mut header = Self::default();
+        for (i, line) in lines.map(str::trim).enumerate() {
+            if let Some((key, value)) = line.split_once('=') {
+                header
+                    .metadata
+                    .insert(key.trim().to_owned(), value.trim().to_owned());
+            } else if i == 0 && !line.is_empty() {
+                header.name = Some(line.to_owned());
+            }
+        }
+        header
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    pub fn version(&self) -> Option<u32> {
+        self.get("version")?.parse().ok()
+    }
+
+    /// Number of rows, from `nRows` (or `NumFrames` for `.trc`).
+    pub fn n_rows(&self) -> Option<usize> {
+        self.get("nRows")
+            .or_else(|| self.get("NumFrames"))?
+            .parse()
+            .ok()
+    }
+
+    /// Number of columns including the time column, from `nColumns`.
+    pub fn n_columns(&self) -> Option<usize> {
+        self.get("nColumns")?.parse().ok()
+    }
+
+    /// Whether the angles are in degrees, from `inDegrees`.
+    pub fn in_degrees(&self) -> Option<bool> {
+        match self.get("inDegrees")?.to_lowercase().as_str() {
+            "yes" | "true" => Some(true),
+            "no" | "false" => Some(false),
+            _ => None,
+        }
+    }
+}
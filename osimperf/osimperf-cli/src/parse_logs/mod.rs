@@ -1,7 +1,13 @@
 mod time_and_value;
 mod data;
 mod channel;
+mod accuracy;
+mod header;
+mod diff;
 
 pub use time_and_value::TimeAndValue;
 pub use channel::Channel;
-pub use data::{Data, Diff};
+pub use data::{Data, FileFormat};
+pub use diff::{ChannelDiff, Diff};
+pub use header::Header;
+pub use accuracy::{Divergence, Tolerance, Tolerances};
@@ -0,0 +1,118 @@
+use crate::{Command, CommandTrait};
+use anyhow::{anyhow, ensure, Context, Result};
+use std::{io::Write, path::Path, path::PathBuf, time::Duration};
+
+/// The cmake configuration step.
+pub struct CMakeConfigurerer<I> {
+    pub source: PathBuf,
+    pub build: PathBuf,
+    pub install: PathBuf,
+    pub dependency: Option<PathBuf>,
+    pub args: I,
+}
+
+impl<I, S> CMakeConfigurerer<I>
+where
+    I: Iterator<Item = S>,
+    S: ToString,
+{
+    pub fn into_cmd(self) -> Command {
+        let mut cmd = Command::new("cmake");
+        cmd.add_arg("-B");
+        cmd.add_arg(self.build.to_str().unwrap());
+        cmd.add_arg("-S");
+        cmd.add_arg(self.source.to_str().unwrap());
+        if let Some(dir) = self.dependency.as_ref() {
+            cmd.add_arg(format!("-DCMAKE_PREFIX_PATH={}", dir.to_str().unwrap()));
+        }
+        cmd.add_arg(format!(
+            "-DCMAKE_INSTALL_PREFIX={}",
+            self.install.to_str().unwrap()
+        ));
+        cmd.add_args(self.args);
+        cmd
+    }
+}
+
+/// The cmake build step.
+pub struct CMakeBuilder<S> {
+    pub build: PathBuf,
+    pub target: Option<S>,
+    pub num_jobs: usize,
+}
+
+impl<S: ToString> CMakeBuilder<S> {
+    pub fn into_cmd(self) -> Command {
+        let mut cmd = Command::new("cmake");
+        cmd.add_arg("--build");
+        cmd.add_arg(self.build.to_str().unwrap());
+        if let Some(t) = self.target {
+            cmd.add_arg("--target");
+            cmd.add_arg(t.to_string());
+        }
+        cmd.add_arg(format!("-j{}", self.num_jobs));
+        cmd
+    }
+}
+
+/// The configure and build commands of a compilation target.
+pub struct CMakeCmds {
+    configure: Command,
+    build: Command,
+}
+
+impl CMakeCmds {
+    pub fn new<I, S>(configure: CMakeConfigurerer<I>, build: CMakeBuilder<S>) -> Self
+    where
+        I: Iterator,
+        I::Item: ToString,
+        S: ToString,
+    {
+        Self {
+            configure: configure.into_cmd(),
+            build: build.into_cmd(),
+        }
+    }
+
+    /// Sets the env var of the configure and build step.
+    pub fn add_env(&mut self, key: impl ToString, value: impl ToString) {
+        self.configure.add_env(key.to_string(), value.to_string());
+        self.build.add_env(key, value);
+    }
+
+    /// Kills the configure or build step if it runs longer than the timeout.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.configure.set_timeout(timeout);
+        self.build.set_timeout(timeout);
+    }
+
+    /// Runs both steps, streaming stdout to log and writing the logs of each step to log_dir.
+    pub fn run(&self, log: &mut impl Write, log_dir: &Path) -> Result<Duration> {
+        let config_output = self.configure.run_and_stream(log)?;
+        config_output.write_stdout(&log_dir.join("osimperf-configure-stdout.log"))?;
+        config_output.write_stderr(&log_dir.join("osimperf-configure-stderr.log"))?;
+        ensure!(!config_output.timed_out, "configuration step timed out");
+        if !config_output.success() {
+            Err(anyhow!("configuration step failed"))
+                .with_context(|| format!("stderr = {}", config_output.stderr_str_clone()))?
+        }
+
+        let build_output = self.build.run_and_stream(log)?;
+        build_output.write_stdout(&log_dir.join("osimperf-build-stdout.log"))?;
+        build_output.write_stderr(&log_dir.join("osimperf-build-stderr.log"))?;
+        ensure!(!build_output.timed_out, "build step timed out");
+        if !build_output.success() {
+            Err(anyhow!("build step failed"))
+                .with_context(|| format!("stderr = {}", build_output.stderr_str_clone()))?
+        }
+        Ok(config_output.duration + build_output.duration)
+    }
+
+    pub fn print_pretty(&self) -> String {
+        format!(
+            "configure command: {}\nbuild command: {}",
+            self.configure.print_command_with_delim(" \\\n    "),
+            self.build.print_command_with_delim(" \\\n    ")
+        )
+    }
+}
@@ -0,0 +1,156 @@
+use super::CompilationTarget;
+use anyhow::{Context, Result};
+use chrono::NaiveDate as Date;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+/// The cmake flags of each compilation target, read from `compile-flags/`.
+#[derive(Deserialize, Serialize, Debug, Clone, Hash)]
+pub struct CMakeConfig {
+    common: Vec<String>,
+    opensim: Vec<String>,
+    dependencies: Vec<String>,
+    opensim_and_dependencies: Vec<String>,
+    tests: Vec<String>,
+    pub num_jobs: usize,
+    /// Compile using ccache.
+    #[serde(default)]
+    pub ccache: bool,
+}
+
+impl CMakeConfig {
+    /// Adds cmake flags to all targets.
+    pub fn add_flags(&mut self, flags: &[String]) {
+        self.common.extend(flags.iter().cloned());
+    }
+
+    pub fn cmake_args(&self, target: CompilationTarget) -> Vec<String> {
+        let mut args = match target {
+            CompilationTarget::OpenSimCore => self
+                .common
+                .iter()
+                .chain(self.opensim_and_dependencies.iter())
+                .chain(self.opensim.iter())
+                .cloned()
+                .collect::<Vec<String>>(),
+            CompilationTarget::Dependencies => self
+                .common
+                .iter()
+                .chain(self.opensim_and_dependencies.iter())
+                .chain(self.dependencies.iter())
+                .cloned()
+                .collect::<Vec<String>>(),
+            CompilationTarget::TestsSource => self
+                .common
+                .iter()
+                .chain(self.tests.iter())
+                .cloned()
+                .collect::<Vec<String>>(),
+        };
+        if self.ccache {
+            args.push("-DCMAKE_C_COMPILER_LAUNCHER=ccache".to_string());
+            args.push("-DCMAKE_CXX_COMPILER_LAUNCHER=ccache".to_string());
+        }
+        args
+    }
+}
+
+impl Default for CMakeConfig {
+    fn default() -> Self {
+        Self {
+            common: vec!["-DCMAKE_BUILD_TYPE=RelWithDebInfo".to_string()],
+            opensim: vec![
+                "-DOPENSIM_BUILD_INDIVIDUAL_APPS=OFF".to_string(),
+                "-DOPENSIM_INSTALL_UNIX_FHS=ON".to_string(),
+                "-DBUILD_API_ONLY=OFF".to_string(),
+                "-DBUILD_API_EXAMPLES=OFF".to_string(),
+                "-DBUILD_JAVA_WRAPPING=OFF".to_string(),
+                "-DBUILD_PYTHON_WRAPPING=OFF".to_string(),
+                "-DBUILD_TESTING=OFF".to_string(),
+                "-DOPENSIM_DOXYGEN_USE_MATHJAX=OFF".to_string(),
+            ],
+            opensim_and_dependencies: vec![
+                "-DOPENSIM_WITH_CASADI=OFF".to_string(),
+                "-DOPENSIM_WITH_TROPTER=OFF".to_string(),
+            ],
+            num_jobs: 3,
+            ccache: false,
+            dependencies: vec![],
+            tests: vec![],
+        }
+    }
+}
+
+/// Reads the default cmake config, and the configs for commits before a date.
+#[derive(Debug)]
+pub struct CMakeConfigReader {
+    default_config: CMakeConfig,
+    dated_configs: Vec<(Date, CMakeConfig)>,
+}
+
+/// The default file name containing the cmake compiler flags.
+static DEFAULT_CMAKE_CONFIG_FILE_NAME: &str = "osimperf-cmake.conf";
+
+static DATED_CMAKE_CONFIG_FILE_NAME_PREFIX: &str = "osimperf-cmake-before-";
+
+/// The subfolder of the osimperf home containing the cmake compile flags.
+static DEFAULT_CMAKE_CONFIG_DIR: &str = "compile-flags";
+
+impl CMakeConfigReader {
+    /// Reads `compile-flags/osimperf-cmake.conf` and the dated
+    /// `compile-flags/osimperf-cmake-before-YYYY_MM_DD.conf` files in home.
+    pub fn read(home: &Path) -> Result<Self> {
+        let dir = home.join(DEFAULT_CMAKE_CONFIG_DIR);
+        let mut out = Self {
+            default_config: read_config(&dir.join(DEFAULT_CMAKE_CONFIG_FILE_NAME))
+                .context("failed to read default cmake config")?,
+            dated_configs: Vec::new(),
+        };
+
+        for entry in std::fs::read_dir(&dir)? {
+            if let Some(config) = try_read_as_dated_cmake_config(&entry?.path())? {
+                out.dated_configs.push(config);
+            }
+        }
+        out.dated_configs.sort_by_key(|(date, _)| *date);
+        Ok(out)
+    }
+
+    /// Returns the first config dated after the given date, or the default config if there is
+    /// none.
+    pub fn get(&self, date: &Date) -> &CMakeConfig {
+        self.dated_configs
+            .iter()
+            .find(|(stamp, _)| date < stamp)
+            .map(|(_, config)| config)
+            .unwrap_or(&self.default_config)
+    }
+}
+
+fn try_read_as_dated_cmake_config(path: &PathBuf) -> Result<Option<(Date, CMakeConfig)>> {
+    let Some(stamp) = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(DATED_CMAKE_CONFIG_FILE_NAME_PREFIX))
+    else {
+        return Ok(None);
+    };
+    debug!("Found cmake config {:?}", path);
+    let date = stamp
+        .get(..10)
+        .and_then(|s| Date::parse_from_str(s, "%Y_%m_%d").ok())
+        .with_context(|| format!("failed to parse date of cmake config file {:?}", path))?;
+    let config = read_config(path).context("failed to read cmake config file")?;
+    Ok(Some((date, config)))
+}
+
+fn read_config(path: &Path) -> Result<CMakeConfig> {
+    let file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse cmake config {:?}", path))
+}
@@ -0,0 +1,11 @@
+//! The cmake configure and build steps of opensim-core, its dependencies, and the tests.
+
+mod cmake_cmds;
+mod config;
+mod progress;
+mod target;
+
+pub use cmake_cmds::{CMakeBuilder, CMakeCmds, CMakeConfigurerer};
+pub use config::{CMakeConfig, CMakeConfigReader};
+pub use progress::CMakeProgressStreamer;
+pub use target::CompilationTarget;
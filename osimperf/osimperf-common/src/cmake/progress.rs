@@ -0,0 +1,86 @@
+use anyhow::Result;
+use log::trace;
+use regex::Regex;
+use std::io::{self, Write};
+
+/// Reports the build progress by parsing the `[ 42%]` percentages of the cmake output.
+///
+/// The callback is called each time the percentage changes, and the output lines are logged at
+/// trace level.
+pub struct CMakeProgressStreamer<F> {
+    buffer: String,
+    percentage: Option<u32>,
+    on_progress: F,
+    re: Regex,
+}
+
+impl<F: FnMut(u32) -> Result<()>> CMakeProgressStreamer<F> {
+    pub fn new(on_progress: F) -> Self {
+        Self {
+            buffer: String::new(),
+            percentage: None,
+            on_progress,
+            re: Regex::new(r"\[\s*(\d+)%\]").unwrap(),
+        }
+    }
+
+    fn pop_lines(&mut self) -> Result<()> {
+        // Keep the last incomplete line in the buffer.
+        while let Some(end) = self.buffer.find('\n') {
+            let line = self.buffer.drain(..=end).collect::<String>();
+            let line = line.trim_end();
+            trace!("{line}");
+
+            let percentage = self
+                .re
+                .captures(line)
+                .and_then(|captures| captures[1].parse::<u32>().ok());
+            if let Some(p) = percentage.filter(|p| Some(*p) != self.percentage) {
+                self.percentage = Some(p);
+                (self.on_progress)(p)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: FnMut(u32) -> Result<()>> Write for CMakeProgressStreamer<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.push_str(&String::from_utf8_lossy(buf));
+        self.pop_lines()
+            .map_err(|err| io::Error::other(format!("{err:#}")))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_changed_percentages() {
+        let mut reported = Vec::new();
+        let mut streamer = CMakeProgressStreamer::new(|p| {
+            reported.push(p);
+            Ok(())
+        });
+        streamer
+            .write_all(b"-- Configuring done\n[  5%] Building CXX\n[  5%] Linking\n[ 4")
+            .unwrap();
+        streamer
+            .write_all(b"2%] Building CXX\n[100%] Built target\n")
+            .unwrap();
+        drop(streamer);
+        assert_eq!(reported, vec![5, 42, 100]);
+    }
+
+    #[test]
+    fn callback_error_fails_write() {
+        let mut streamer = CMakeProgressStreamer::new(|_| anyhow::bail!("failed to write status"));
+        assert!(streamer.write_all(b"[ 10%] Building CXX\n").is_err());
+    }
+}
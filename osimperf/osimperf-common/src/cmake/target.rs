@@ -1,5 +1,8 @@
+use std::fmt;
+
+/// The projects that are compiled, in order.
 #[repr(usize)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum CompilationTarget {
     Dependencies = 0,
     OpenSimCore = 1,
@@ -7,7 +10,8 @@ pub enum CompilationTarget {
 }
 
 impl CompilationTarget {
-    pub fn to_str(&self) -> &str {
+    /// Name of the build, install and log subdirectory.
+    pub fn to_str(&self) -> &'static str {
         match self {
             Self::OpenSimCore => "opensim-core",
             Self::Dependencies => "dependencies",
@@ -15,7 +19,7 @@ impl CompilationTarget {
         }
     }
 
-    pub fn short_desc(&self) -> &str {
+    pub fn short_desc(&self) -> &'static str {
         match self {
             Self::OpenSimCore => "osim",
             Self::Dependencies => "deps",
@@ -36,3 +40,9 @@ impl CompilationTarget {
         [Self::Dependencies, Self::OpenSimCore, Self::TestsSource]
     }
 }
+
+impl fmt::Display for CompilationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
@@ -4,8 +4,8 @@ mod single_command;
 pub use piped_command::{PipedCommands, PipedCommandsExecutor};
 pub use single_command::{Command, CommandExecutor};
 
-use crate::common::duration_since_boot;
-use anyhow::{Context, Result};
+use crate::{duration_since_boot, shell_words, Watchdog};
+use anyhow::{ensure, Context, Result};
 use std::io::BufReader;
 use std::thread;
 use std::{
@@ -21,6 +21,8 @@ pub struct CommandOutput {
     pub cmd_str: String,
     pub duration: Duration,
     pub output: std::process::Output,
+    /// The command was killed after exceeding its timeout.
+    pub timed_out: bool,
 }
 
 impl CommandOutput {
@@ -46,7 +48,7 @@ impl CommandOutput {
                 "failed to open file for writing stdout logs at path = {:?}",
                 path
             ))?;
-        file.write_all(&format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
+        file.write_all(format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
         file.write_all(&self.output.stdout)?;
         Ok(())
     }
@@ -61,7 +63,7 @@ impl CommandOutput {
                 "failed to open file for writing stderr logs at path = {:?}",
                 path
             ))?;
-        file.write_all(&format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
+        file.write_all(format!("Executed commands:\n{}\n", self.cmd_str).as_bytes())?;
         file.write_all(&self.output.stderr)?;
         Ok(())
     }
@@ -88,21 +90,32 @@ pub trait CommandTrait {
     fn run_and_time(&self) -> Result<CommandOutput> {
         let cmd = self.create_executor();
         let start = duration_since_boot()?;
-        let output = cmd.execute();
+        let output = match cmd.timeout() {
+            Some(timeout) => execute_with_timeout(cmd, timeout),
+            None => cmd.execute().map(|output| (output, false)),
+        };
         let end = duration_since_boot()?;
         let duration = end - start;
+        let (output, timed_out) = output
+            .with_context(|| format!("failed to execute command: {}", self.print_command()))?;
         Ok(CommandOutput {
             cmd_str: self.print_command(),
             duration,
-            output: output
-                .with_context(|| format!("failed to execute command: {}", self.print_command()))?,
+            output,
+            timed_out,
         })
     }
 
     fn run_stdout(&self) -> Result<Vec<u8>> {
         let output = self.run_and_time()?;
-        Some(())
-            .filter(|_| output.success())
+        ensure!(
+            !output.timed_out,
+            "command timed out: {}",
+            self.print_command()
+        );
+        output
+            .success()
+            .then_some(())
             .with_context(|| format!("stdout: {:#?}", output.stdout_str_clone()))
             .with_context(|| format!("stderr: {:#?}", output.stderr_str_clone()))
             .with_context(|| format!("returned exit code: {:#?}", output.output.status))
@@ -121,10 +134,12 @@ pub trait CommandTrait {
     fn run_and_stream(&self, stream: &mut impl Write) -> Result<CommandOutput> {
         // Construct command.
         let cmd = self.create_executor();
+        let timeout = cmd.timeout();
 
         // Exectute command and start timer.
         let start = duration_since_boot()?;
         let mut child = cmd.start_execute()?;
+        let watchdog = timeout.map(|timeout| Watchdog::start(child.id(), timeout));
 
         // Access the stdout and stderr of the child process.
         let stderr = child.stderr.take().expect("Failed to capture stderr");
@@ -171,6 +186,7 @@ pub trait CommandTrait {
             .wait_with_output()
             .context("error waiting for command output")
             .with_context(|| format!("failed to execute command: {}", self.print_command()))?;
+        let timed_out = watchdog.map(Watchdog::stop).unwrap_or(false);
 
         output.stdout.extend(stdout_buffer);
         output.stderr.extend(stderr_result);
@@ -181,6 +197,7 @@ pub trait CommandTrait {
             cmd_str: self.print_command(),
             duration,
             output,
+            timed_out,
         })
     }
 }
@@ -189,21 +206,32 @@ pub trait CommandExecutorTrait {
     fn execute(self) -> Result<std::process::Output>;
 
     fn start_execute(self) -> Result<std::process::Child>;
+
+    /// Time after which the command is killed.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
 }
 
-pub(crate) fn substitute_if_present(string: &mut String, key: &str, value: &str) -> Option<()> {
-    let start = string.find(key)?;
-    let end = start + key.len();
-    let start = string.find(key)?;
-    string.replace_range(start..end, value);
-    Some(())
+/// Runs the command to completion, killing its process group once the timeout expires.
+///
+/// Returns the output, and whether the command timed out.
+fn execute_with_timeout(
+    cmd: impl CommandExecutorTrait,
+    timeout: Duration,
+) -> Result<(std::process::Output, bool)> {
+    let child = cmd.start_execute()?;
+    let watchdog = Watchdog::start(child.id(), timeout);
+    let output = child.wait_with_output()?;
+    Ok((output, watchdog.stop()))
 }
 
+/// Expands `$KEY` and `${KEY}` of the key-value pairs, see [shell_words::expand_vars].
 pub(crate) fn substitute_all(string: &str, key_value: &[(String, String)]) -> String {
-    let mut out = String::from(string);
-    for (key, value) in key_value.iter() {
-        let dollar_key = format!("${}", key);
-        _ = substitute_if_present(&mut out, &dollar_key, value);
-    }
-    out
+    shell_words::expand_vars(string, |key| {
+        key_value
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    })
 }
@@ -1,20 +1,40 @@
+use super::shell_words::{escape_vars, quote, split_words};
 use super::{substitute_all, CommandExecutorTrait, CommandTrait};
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use serde::{Deserialize, Serialize};
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::os::unix::process::CommandExt;
+use std::time::Duration;
 use std::{path::Path, process::Stdio};
 
-#[derive(Deserialize, Serialize, Debug, Clone, Hash)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Command {
     cmd: String,
     args: Vec<String>,
     envs: Option<Vec<(String, String)>>,
     root: Option<String>,
+    #[serde(default)]
+    timeout: Option<Duration>,
+}
+
+// Hashed as part of the benchmark config. The timeout is only hashed when set, such that commands
+// without a timeout keep their hash.
+impl Hash for Command {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cmd.hash(state);
+        self.args.hash(state);
+        self.envs.hash(state);
+        self.root.hash(state);
+        if let Some(timeout) = self.timeout {
+            ("timeout", timeout).hash(state);
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct CommandExecutor {
     cmd: std::process::Command,
+    timeout: Option<Duration>,
 }
 
 impl CommandExecutor {
@@ -31,9 +51,17 @@ impl CommandExecutorTrait for CommandExecutor {
     fn start_execute(mut self) -> Result<std::process::Child> {
         self.cmd.stdout(Stdio::piped());
         self.cmd.stderr(Stdio::piped());
+        // Start a new process group, such that the child and its descendants can be killed on timeout.
+        if self.timeout.is_some() {
+            self.cmd.process_group(0);
+        }
 
         Ok(self.cmd.spawn()?)
     }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 }
 
 impl Command {
@@ -43,6 +71,7 @@ impl Command {
             args: Vec::new(),
             envs: None,
             root: None,
+            timeout: None,
         }
     }
 
@@ -66,20 +95,58 @@ impl Command {
         self.add_env(key, value.to_str().unwrap());
     }
 
+    pub(super) fn from_words(mut words: Vec<String>) -> Self {
+        let mut cmd = Self::new(if words.is_empty() {
+            String::new()
+        } else {
+            words.remove(0)
+        });
+        cmd.args = words;
+        cmd
+    }
+
+    /// Splits the string into a command and arguments, following shell quoting rules.
+    ///
+    /// Quotes and backslash escapes are handled, and `$KEY` or `${KEY}` are expanded using the
+    /// env vars of this command. Other shell syntax is passed on literally.
     pub fn parse(string: &str) -> Self {
-        let mut split = string.split(' ');
-        let mut cmd = Self::new(split.next().unwrap());
-        for s in split {
-            if !s.is_empty() {
-                cmd.add_arg(s);
-            }
-        }
+        let (mut commands, _) = split_words(string, false);
+        Self::from_words(commands.remove(0))
+    }
+
+    /// Same as [Command::parse], but fails on syntax that requires a shell (e.g. redirects and
+    /// globs), or unterminated quotes.
+    pub fn try_parse(string: &str) -> Result<Self> {
+        let (mut commands, unsupported) = split_words(string, false);
+        ensure!(
+            unsupported.is_empty(),
+            "unsupported syntax in command {:?}: {} (requires a shell)",
+            string,
+            unsupported.join(", ")
+        );
+        let words = commands.remove(0);
+        ensure!(!words.is_empty(), "command is empty");
+        Ok(Self::from_words(words))
+    }
+
+    /// Runs the script using `/bin/sh -c`.
+    ///
+    /// The env vars are not substituted in the script, but are available to the shell.
+    pub fn shell(script: &str) -> Self {
+        let mut cmd = Self::new("/bin/sh");
+        cmd.add_arg("-c");
+        cmd.add_arg(escape_vars(script));
         cmd
     }
 
     pub fn set_run_root(&mut self, root: &Path) {
         self.root = Some(String::from(root.to_str().unwrap()));
     }
+
+    /// Kills the command, and any processes it started, if it runs longer than the timeout.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
 }
 
 impl CommandTrait for Command {
@@ -110,26 +177,26 @@ impl CommandTrait for Command {
                 cmd.env(key, value);
             }
         }
-        CommandExecutor { cmd }
+        CommandExecutor {
+            cmd,
+            timeout: self.timeout,
+        }
     }
 
     fn print_command_with_delim(&self, arg_delim: &str) -> String {
-        let mut msg = then_substitute_all(&self.cmd, &self.envs);
+        let mut msg = quote(&then_substitute_all(&self.cmd, &self.envs));
         for arg in self
             .args
             .iter()
             .map(|arg| then_substitute_all(arg, &self.envs))
         {
             msg.push_str(arg_delim);
-            msg.push_str(&arg);
+            msg.push_str(&quote(&arg));
         }
         msg
     }
 }
 
 pub fn then_substitute_all(string: &str, key_value: &Option<Vec<(String, String)>>) -> String {
-    if let Some(env) = key_value {
-        return substitute_all(string, env);
-    }
-    String::from(string)
+    substitute_all(string, key_value.as_deref().unwrap_or_default())
 }
@@ -0,0 +1,116 @@
+use crate::{Command, CommandTrait};
+use anyhow::{Context, Result};
+use log::trace;
+use std::{
+    fs::{create_dir, create_dir_all, remove_dir_all, rename},
+    path::{Path, PathBuf},
+};
+
+/// Prefix of the temporary name of a folder that is being removed, see [remove_folder].
+pub static REMOVING_FOLDER_PREFIX: &str = ".osimperf-removing-";
+
+/// Returns the temporary path of the folder used while removing it.
+fn removing_path(path: &Path) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("invalid folder to remove: {:?}", path))?;
+    Ok(path.with_file_name(format!("{REMOVING_FOLDER_PREFIX}{name}")))
+}
+
+/// Removes a folder, by first moving it to a temporary name, and then deleting it.
+///
+/// The folder is either complete at its path, or gone: an interrupted removal leaves only the
+/// temporary, which is deleted by the next removal of the same folder.
+pub fn remove_folder(path: &Path) -> Result<()> {
+    let temp_dir = removing_path(path)?;
+    if temp_dir.exists() {
+        trace!("Remove leftover {:?}", temp_dir);
+        remove_dir_all(&temp_dir).with_context(|| format!("failed to remove {:?}", temp_dir))?;
+    }
+    trace!("Move {:?} to {:?}", path, temp_dir);
+    rename(path, &temp_dir).with_context(|| format!("failed to move {:?} to temporary", path))?;
+    trace!("Remove {:?}", temp_dir);
+    remove_dir_all(&temp_dir).with_context(|| format!("failed to remove {:?}", temp_dir))?;
+    Ok(())
+}
+
+/// Replaces a folder by an empty one.
+///
+/// The empty folder is created at the temporary path of [remove_folder], and moved into place.
+pub fn erase_folder(path: &Path) -> Result<()> {
+    if path.exists() {
+        remove_folder(path)?;
+    }
+    let temp_dir = removing_path(path)?;
+    if temp_dir.exists() {
+        trace!("Remove leftover {:?}", temp_dir);
+        remove_dir_all(&temp_dir).with_context(|| format!("failed to remove {:?}", temp_dir))?;
+    }
+    trace!("Create empty folder at {:?}", temp_dir);
+    create_dir(&temp_dir).with_context(|| format!("failed to create directory {:?}", temp_dir))?;
+    trace!("Move empty folder to {:?}", path);
+    rename(&temp_dir, path).with_context(|| format!("failed to move temporary to {:?}", path))?;
+    Ok(())
+}
+
+/// Copies the content of a folder into another, preserving symlinks and permissions.
+pub fn copy_folder(from: &Path, to: &Path) -> Result<()> {
+    create_dir_all(to)?;
+    let mut cmd = Command::new("cp");
+    cmd.add_arg("-a");
+    cmd.add_arg(format!("{}/.", from.to_str().unwrap()));
+    cmd.add_arg(to.to_str().unwrap());
+    cmd.run()
+        .with_context(|| format!("failed to copy {:?} to {:?}", from, to))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read_dir, write};
+
+    /// Returns an empty directory in the system temporary directory.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("osimperf-common-{name}-{}", std::process::id()));
+        if dir.exists() {
+            remove_dir_all(&dir).unwrap();
+        }
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn remove_folder_removes_leftover() {
+        let dir = test_dir("remove");
+        let folder = dir.join("install");
+        create_dir_all(folder.join("lib")).unwrap();
+        create_dir_all(dir.join(".osimperf-removing-install")).unwrap();
+
+        remove_folder(&folder).unwrap();
+        assert_eq!(read_dir(&dir).unwrap().count(), 0);
+        remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn erase_folder_leaves_empty_folder() {
+        let dir = test_dir("erase");
+        let folder = dir.join("install");
+        create_dir_all(&folder).unwrap();
+        write(folder.join("file"), "content").unwrap();
+        // Leftover of an interrupted erase.
+        create_dir_all(dir.join(".osimperf-removing-install")).unwrap();
+
+        erase_folder(&folder).unwrap();
+        assert_eq!(read_dir(&folder).unwrap().count(), 0);
+        assert_eq!(read_dir(&dir).unwrap().count(), 1);
+
+        // Also creates missing folders.
+        let missing = dir.join("missing");
+        erase_folder(&missing).unwrap();
+        assert!(missing.is_dir());
+        remove_dir_all(&dir).unwrap();
+    }
+}
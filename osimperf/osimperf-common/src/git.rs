@@ -0,0 +1,13 @@
+use crate::{Command, CommandTrait};
+use anyhow::Result;
+use std::path::Path;
+
+/// Returns the hash of the tree at path in the commit, which changes only if the content does.
+pub fn read_tree_hash(repo: &Path, hash: &str, path: &str) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.add_arg("-C");
+    cmd.add_arg(repo.to_str().unwrap());
+    cmd.add_arg("rev-parse");
+    cmd.add_arg(format!("{hash}:{path}"));
+    cmd.run_trim()
+}
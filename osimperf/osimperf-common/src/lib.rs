@@ -0,0 +1,17 @@
+//! Command and build helpers, shared by `osimperf-lib` and `osimperf-cli`.
+
+pub mod cmake;
+mod command;
+mod folders;
+pub mod git;
+pub mod shell_words;
+mod time;
+mod timeout;
+
+pub use command::{
+    Command, CommandExecutor, CommandExecutorTrait, CommandOutput, CommandTrait, PipedCommands,
+    PipedCommandsExecutor,
+};
+pub use folders::{copy_folder, erase_folder, remove_folder, REMOVING_FOLDER_PREFIX};
+pub use time::duration_since_boot;
+pub use timeout::Watchdog;
@@ -0,0 +1,282 @@
+//! POSIX shell style splitting of command lines, without running a shell.
+//!
+//! A literal `$` (quoted, or escaped) is stored as `$$` in the words, such that it is not
+//! expanded by [expand_vars].
+
+/// Unquoted characters that only have meaning to a shell.
+const SHELL_ONLY: &[char] = &['<', '>', ';', '&', '`', '*', '?', '(', ')'];
+
+/// Splits a line into words, handling single and double quotes, and backslash escapes.
+///
+/// Unquoted `|` separates commands if `pipes` is set, and every command is returned as a list of
+/// words. Syntax that requires a shell (e.g. redirects, globs, unterminated quotes) is collected in
+/// the second return value, the offending characters are kept as literals.
+pub fn split_words(line: &str, pipes: bool) -> (Vec<Vec<String>>, Vec<String>) {
+    let mut commands = vec![Vec::new()];
+    let mut unsupported = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => {
+                if let Some(w) = word.take() {
+                    commands.last_mut().unwrap().push(w);
+                }
+                continue;
+            }
+            '|' if pipes => {
+                if let Some(w) = word.take() {
+                    commands.last_mut().unwrap().push(w);
+                }
+                commands.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+
+        let w = word.get_or_insert_with(String::new);
+        match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some('$') => w.push_str("$$"),
+                    Some(c) => w.push(c),
+                    None => {
+                        unsupported.push("unterminated single quote".to_owned());
+                        break;
+                    }
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.peek() {
+                        Some('$') => {
+                            chars.next();
+                            w.push_str("$$");
+                        }
+                        Some(&c @ ('"' | '\\' | '`')) => {
+                            chars.next();
+                            w.push(c);
+                        }
+                        Some('\n') => {
+                            chars.next();
+                        }
+                        _ => w.push('\\'),
+                    },
+                    Some('$') if chars.peek() == Some(&'(') => {
+                        unsupported.push("command substitution `$(`".to_owned());
+                        w.push('$');
+                    }
+                    Some('`') => {
+                        unsupported.push("command substitution '`'".to_owned());
+                        w.push('`');
+                    }
+                    Some(c) => w.push(c),
+                    None => {
+                        unsupported.push("unterminated double quote".to_owned());
+                        break;
+                    }
+                }
+            },
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('$') => w.push_str("$$"),
+                Some(c) => w.push(c),
+                None => w.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'(') => {
+                unsupported.push("command substitution `$(`".to_owned());
+                w.push('$');
+            }
+            '#' if w.is_empty() => {
+                unsupported.push("unquoted comment `#`".to_owned());
+                w.push('#');
+            }
+            c if c == '|' || SHELL_ONLY.contains(&c) => {
+                unsupported.push(format!("unquoted `{c}`"));
+                w.push(c);
+            }
+            c => w.push(c),
+        }
+    }
+    if let Some(w) = word.take() {
+        commands.last_mut().unwrap().push(w);
+    }
+
+    (commands, unsupported)
+}
+
+/// Expands `$KEY` and `${KEY}` using the lookup, and unescapes `$$` to `$`.
+///
+/// Variables that are not found are left as is.
+pub fn expand_vars<'a>(string: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut out = String::with_capacity(string.len());
+    let mut rest = string;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+
+        if let Some(r) = rest.strip_prefix('$') {
+            out.push('$');
+            rest = r;
+            continue;
+        }
+
+        if let Some(r) = rest.strip_prefix('{') {
+            if let Some(value) = r.find('}').and_then(|end| Some((end, lookup(&r[..end])?))) {
+                out.push_str(value.1);
+                rest = &r[value.0 + 1..];
+                continue;
+            }
+        } else {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            if let Some(value) = Some(&rest[..len])
+                .filter(|key| key.starts_with(|c: char| !c.is_ascii_digit()))
+                .and_then(&lookup)
+            {
+                out.push_str(value);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        out.push('$');
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escapes a literal string, such that [expand_vars] returns it unchanged.
+pub fn escape_vars(string: &str) -> String {
+    string.replace('$', "$$")
+}
+
+/// Quotes the word for printing, if it contains characters that the shell would interpret.
+pub fn quote(word: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%^".contains(c);
+    if !word.is_empty() && word.chars().all(safe) {
+        return word.to_owned();
+    }
+    format!("'{}'", word.replace('\'', r#"'\''"#))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(line: &str) -> Vec<String> {
+        let (mut commands, unsupported) = split_words(line, false);
+        assert!(unsupported.is_empty(), "{line}: {unsupported:?}");
+        assert_eq!(commands.len(), 1);
+        commands.pop().unwrap()
+    }
+
+    fn lookup(key: &str) -> Option<&'static str> {
+        match key {
+            "HOME" => Some("/home/user"),
+            "EMPTY" => Some(""),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(split("a  b\tc\nd "), ["a", "b", "c", "d"]);
+        assert!(split("   ").is_empty());
+    }
+
+    #[test]
+    fn quotes_and_escapes() {
+        assert_eq!(split(r#"'a b' "c d" e\ f"#), ["a b", "c d", "e f"]);
+        assert_eq!(split(r#"a'b'"c"d"#), ["abcd"]);
+        assert_eq!(split(r#"'' """#), ["", ""]);
+        assert_eq!(split(r#"'a\b' "a\"b\\c\d""#), [r"a\b", r#"a"b\c\d"#]);
+        assert_eq!(split(r#"\'x "it's""#), ["'x", "it's"]);
+        assert_eq!(split("a\\\nb"), ["ab"]);
+        assert_eq!(split("a#b"), ["a#b"]);
+    }
+
+    #[test]
+    fn escapes_literal_dollar() {
+        assert_eq!(
+            split(r#"'$HOME' "\$HOME" \$HOME $HOME "$HOME""#),
+            ["$$HOME", "$$HOME", "$$HOME", "$HOME", "$HOME"]
+        );
+    }
+
+    #[test]
+    fn splits_pipes() {
+        let (commands, unsupported) = split_words("a 'b|c' | d e|f", true);
+        assert_eq!(commands, [vec!["a", "b|c"], vec!["d", "e"], vec!["f"]]);
+        assert!(unsupported.is_empty());
+
+        let (commands, unsupported) = split_words("a | b", false);
+        assert_eq!(commands, [vec!["a", "|", "b"]]);
+        assert_eq!(unsupported, ["unquoted `|`"]);
+    }
+
+    #[test]
+    fn reports_shell_syntax() {
+        let unsupported = |line| split_words(line, true).1;
+        assert_eq!(unsupported("a > b"), ["unquoted `>`"]);
+        assert_eq!(unsupported("a; b"), ["unquoted `;`"]);
+        assert_eq!(unsupported("*.txt"), ["unquoted `*`"]);
+        assert_eq!(unsupported("# comment"), ["unquoted comment `#`"]);
+        assert_eq!(unsupported("'abc"), ["unterminated single quote"]);
+        assert_eq!(unsupported("\"abc"), ["unterminated double quote"]);
+        assert!(unsupported("$(ls)").contains(&"command substitution `$(`".to_owned()));
+        assert_eq!(unsupported("\"`ls`\"").len(), 2);
+        assert!(unsupported("'a > b; *'").is_empty());
+
+        // The offending characters are kept as literals.
+        assert_eq!(split_words("a>b", true).0, [vec!["a>b"]]);
+    }
+
+    #[test]
+    fn expands_vars() {
+        assert_eq!(expand_vars("$HOME/x", lookup), "/home/user/x");
+        assert_eq!(expand_vars("${HOME}x", lookup), "/home/userx");
+        assert_eq!(expand_vars("a${EMPTY}b$EMPTY", lookup), "ab");
+        assert_eq!(expand_vars("$HOME_DIR", lookup), "$HOME_DIR");
+        assert_eq!(
+            expand_vars("${MISSING} $MISSING", lookup),
+            "${MISSING} $MISSING"
+        );
+        assert_eq!(expand_vars("$1 ${HOME", lookup), "$1 ${HOME");
+        assert_eq!(expand_vars("a$", lookup), "a$");
+    }
+
+    #[test]
+    fn unescapes_dollar() {
+        assert_eq!(expand_vars("$$HOME", lookup), "$HOME");
+        assert_eq!(expand_vars("$$$HOME", lookup), "$/home/user");
+        assert_eq!(expand_vars("$$$$", lookup), "$$");
+    }
+
+    #[test]
+    fn escape_vars_round_trips() {
+        for string in ["$HOME", "${HOME}", "$$", "a$", "plain"] {
+            assert_eq!(expand_vars(&escape_vars(string), lookup), string);
+        }
+        assert_eq!(expand_vars(&split(r"'$HOME' $HOME")[0], lookup), "$HOME");
+    }
+
+    #[test]
+    fn quotes_for_printing() {
+        assert_eq!(quote("abc-1.0/x=y"), "abc-1.0/x=y");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("a b"), "'a b'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn quote_round_trips() {
+        for word in ["a b", "it's", "\"x\"", "a|b;c", "tab\there", ""] {
+            assert_eq!(split(&quote(word)), [word]);
+        }
+    }
+}
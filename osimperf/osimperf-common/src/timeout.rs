@@ -0,0 +1,50 @@
+use log::warn;
+use nix::libc;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Time between asking the process group to terminate, and killing it.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Terminates a child, and any processes it started, if it runs longer than the timeout.
+///
+/// The child must be the leader of its own process group. On timeout the process group receives
+/// SIGTERM, followed by SIGKILL if it did not exit within a grace period.
+#[derive(Debug)]
+pub struct Watchdog {
+    stop: Sender<()>,
+    handle: JoinHandle<bool>,
+}
+
+impl Watchdog {
+    pub fn start(pgid: u32, timeout: Duration) -> Self {
+        let (stop, stopped) = channel::<()>();
+        let handle = thread::spawn(move || {
+            if stopped.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+                return false;
+            }
+            warn!("Command timed out after {timeout:?}, terminating process group {pgid}");
+            signal_group(pgid, libc::SIGTERM);
+            if stopped.recv_timeout(KILL_GRACE_PERIOD) == Err(RecvTimeoutError::Timeout) {
+                warn!("Process group {pgid} did not terminate, killing it");
+                signal_group(pgid, libc::SIGKILL);
+            }
+            true
+        });
+        Self { stop, handle }
+    }
+
+    /// Stops the watchdog after the child was reaped, returns true if the child timed out.
+    pub fn stop(self) -> bool {
+        let _ = self.stop.send(());
+        self.handle.join().expect("Failed to join watchdog thread")
+    }
+}
+
+fn signal_group(pgid: u32, signal: libc::c_int) {
+    // SAFETY: Sending a signal has no memory safety requirements.
+    unsafe {
+        libc::kill(-(pgid as libc::pid_t), signal);
+    }
+}
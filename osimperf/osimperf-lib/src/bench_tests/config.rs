@@ -1,10 +1,11 @@
 use crate::common::{find_file_by_name, read_config};
 use crate::Command;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 // Go over subfolders of tests/ to find "osimperf-test.conf"
 static TEST_SETUP_FILE_NAME: &str = "osimperf-test.conf";
@@ -20,6 +21,10 @@ struct ReadBenchTestSetup {
     post_benchmark_cmds: Option<Vec<String>>,
     /// Will search in OSIMPERF_HOME/tests/opensim-models/* for files with the same name.
     files: Option<Vec<String>>,
+    /// Run the commands using `/bin/sh -c`, to allow for redirects, globs, etc.
+    shell: Option<bool>,
+    /// Timeout in seconds of each command, after which it is killed.
+    timeout: Option<u64>,
 }
 
 #[derive(Clone, Debug, Hash)]
@@ -35,32 +40,116 @@ pub struct BenchTestSetup {
     pub model_files: Vec<String>,
 }
 
-fn parse_commands(cmds: &Option<Vec<String>>) -> Vec<Command> {
-    if let Some(c) = cmds {
-        c.iter().map(|cmd| Command::parse(cmd)).collect()
+fn parse_command(cmd: &str, shell: bool, timeout: Option<Duration>) -> Result<Command> {
+    let mut cmd = if shell {
+        Command::shell(cmd)
     } else {
-        Vec::new()
-    }
+        Command::try_parse(cmd)?
+    };
+    cmd.set_timeout(timeout);
+    Ok(cmd)
+}
+
+fn parse_commands(
+    cmds: &Option<Vec<String>>,
+    shell: bool,
+    timeout: Option<Duration>,
+) -> Result<Vec<Command>> {
+    cmds.iter()
+        .flatten()
+        .map(|cmd| parse_command(cmd, shell, timeout))
+        .collect()
 }
 
 impl BenchTestSetup {
-    fn new(config: ReadBenchTestSetup, path: PathBuf) -> Self {
-        Self {
+    fn new(config: ReadBenchTestSetup, path: PathBuf, timeout: Option<Duration>) -> Result<Self> {
+        let shell = config.shell.unwrap_or(false);
+        let timeout = config.timeout.map(Duration::from_secs).or(timeout);
+        Ok(Self {
             test_setup_file: path,
             name: config.name,
-            benchmark_cmd: Command::parse(&config.benchmark_cmd),
-            pre_benchmark_cmds: parse_commands(&config.pre_benchmark_cmds),
-            post_benchmark_cmds: parse_commands(&config.post_benchmark_cmds),
+            benchmark_cmd: parse_command(&config.benchmark_cmd, shell, timeout)?,
+            pre_benchmark_cmds: parse_commands(&config.pre_benchmark_cmds, shell, timeout)?,
+            post_benchmark_cmds: parse_commands(&config.post_benchmark_cmds, shell, timeout)?,
             model_files: config.files.unwrap_or_default(),
-        }
+        })
     }
 
-    pub fn find_all(path: &Path) -> Result<Vec<Self>> {
+    /// Reads all test configs in the directory.
+    ///
+    /// The timeout is used for tests that do not specify their own.
+    pub fn find_all(path: &Path, timeout: Option<Duration>) -> Result<Vec<Self>> {
         let mut tests = Vec::new();
         for p in find_file_by_name(path, TEST_SETUP_FILE_NAME) {
             let c = read_config::<ReadBenchTestSetup>(&p)?;
-            tests.push(BenchTestSetup::new(c, p));
+            tests.push(
+                BenchTestSetup::new(c, p.clone(), timeout)
+                    .with_context(|| format!("failed to parse commands in {:?}", p))?,
+            );
         }
         Ok(tests)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use osimperf_results::config_hash;
+
+    /// The command before the timeout was added.
+    #[derive(Hash)]
+    struct LegacyCommand {
+        cmd: String,
+        args: Vec<String>,
+        envs: Option<Vec<(String, String)>>,
+        root: Option<String>,
+    }
+
+    /// The test setup before the shell and timeout options were added.
+    #[derive(Hash)]
+    struct LegacySetup {
+        name: String,
+        benchmark_cmd: LegacyCommand,
+        pre_benchmark_cmds: Vec<LegacyCommand>,
+        post_benchmark_cmds: Vec<LegacyCommand>,
+        test_setup_file: PathBuf,
+        model_files: Vec<String>,
+    }
+
+    static CONFIG: &str = r#"{
+        "name": "Arm26",
+        "pre_benchmark_cmds": ["cp model.osim setup.xml ."],
+        "benchmark_cmd": "opensim-cmd run-tool setup.xml",
+        "files": ["model.osim"]
+    }"#;
+
+    fn legacy_command(cmd: &str, args: &[&str]) -> LegacyCommand {
+        LegacyCommand {
+            cmd: cmd.to_owned(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            envs: None,
+            root: None,
+        }
+    }
+
+    fn read_setup(config: &str, timeout: Option<Duration>) -> BenchTestSetup {
+        let config: ReadBenchTestSetup = serde_json::from_str(config).unwrap();
+        BenchTestSetup::new(config, PathBuf::from("tests/Arm26"), timeout).unwrap()
+    }
+
+    #[test]
+    fn setup_without_timeout_keeps_hash() {
+        let legacy = LegacySetup {
+            name: "Arm26".to_owned(),
+            benchmark_cmd: legacy_command("opensim-cmd", &["run-tool", "setup.xml"]),
+            pre_benchmark_cmds: vec![legacy_command("cp", &["model.osim", "setup.xml", "."])],
+            post_benchmark_cmds: Vec::new(),
+            test_setup_file: PathBuf::from("tests/Arm26"),
+            model_files: vec!["model.osim".to_owned()],
+        };
+        assert_eq!(config_hash(&read_setup(CONFIG, None)), config_hash(&legacy));
+
+        let timeout = read_setup(CONFIG, Some(Duration::from_secs(60)));
+        assert_ne!(config_hash(&timeout), config_hash(&legacy));
+    }
+}
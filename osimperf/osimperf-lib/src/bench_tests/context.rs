@@ -13,7 +13,7 @@ pub fn flattened_path(file: &Path, flat_dir: &Path) -> PathBuf {
 }
 
 pub fn copy_file_to_flat(file: &Path, flat_dir: &Path) -> Result<()> {
-    let to = flattened_path(file, &flat_dir);
+    let to = flattened_path(file, flat_dir);
     trace!("Context setup: Copy {:?} to {:?}", file, to);
     let _ = copy(file, &to)?;
     Ok(())
@@ -33,7 +33,7 @@ pub fn setup_context(
     models_dir: &Path,
 ) -> Result<()> {
     // Erase scratch dir.
-    erase_folder(&context_dir)?;
+    erase_folder(context_dir)?;
 
     // Copy all files from setup directory.
     trace!("setup dir = {:?}", setup_dir);
@@ -42,7 +42,7 @@ pub fn setup_context(
     })?;
 
     for required_file in required_files.iter() {
-        let file = find_modeling_file(required_file, &models_dir)?;
+        let file = find_modeling_file(required_file, models_dir)?;
         copy_file_to_flat(&file, context_dir)?;
     }
 
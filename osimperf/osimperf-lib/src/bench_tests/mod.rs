@@ -3,12 +3,13 @@ mod run_cmds;
 mod node;
 mod result;
 mod context;
-mod durations;
 mod plot;
+mod store;
 
 pub use config::BenchTestSetup;
 pub use node::TestNode;
 pub use result::BenchTestResult;
 pub use context::setup_context;
-pub use durations::Durations;
+pub use osimperf_results::{Durations, Estimator, Outcome, OutlierMethod, OutlierPolicy, Percentile};
 pub use plot::print_csv;
+pub use store::ResultStore;
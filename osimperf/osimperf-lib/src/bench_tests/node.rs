@@ -2,25 +2,24 @@ use super::{
     run_cmds::{run_post_test_cmds, run_pre_test_cmds, run_test_bench_cmd, FileEnvVars},
     BenchTestResult, BenchTestSetup,
 };
-use crate::{CommandOutput, CompilationNode, Folder, Home, NodeFile, ResultsFolder, CommandTrait};
+use crate::{CommandOutput, CompilationNode, Folder, Home, NodeFile, ResultsFolder};
 use anyhow::Result;
-use log::{trace, warn, info};
-use std::hash::{Hash, Hasher};
-use std::{collections::hash_map::DefaultHasher, path::PathBuf};
+use log::{trace, warn};
+use osimperf_results::config_hash;
+use std::path::PathBuf;
 
 // TODO rename to TestNodeRunner
 #[derive(Debug)]
-pub struct TestNode<'a, 'b, 'c, 'd> {
+pub struct TestNode<'a, 'b, 'c> {
     test: &'a BenchTestSetup,
     compiler: &'b CompilationNode,
     home: &'c Home,
-    results: &'d ResultsFolder,
     result: BenchTestResult,
     last_command_output: Option<CommandOutput>,
     warm_start_buffer: usize,
 }
 
-impl<'a, 'b, 'c, 'd> TestNode<'a, 'b, 'c, 'd> {
+impl<'a, 'b, 'c> TestNode<'a, 'b, 'c> {
     pub fn name(&self) -> &str {
         &self.test.name
     }
@@ -29,14 +28,13 @@ impl<'a, 'b, 'c, 'd> TestNode<'a, 'b, 'c, 'd> {
         test: &'a BenchTestSetup,
         compiler: &'b CompilationNode,
         home: &'c Home,
-        results: &'d ResultsFolder,
+        results: &ResultsFolder,
         warm_start_buffer: usize,
     ) -> Result<Self> {
         Ok(Self {
             test,
             compiler,
             home,
-            results,
             result: BenchTestResult::new(results, &compiler.id(), &test.name)?,
             last_command_output: None,
             warm_start_buffer,
@@ -47,7 +45,7 @@ impl<'a, 'b, 'c, 'd> TestNode<'a, 'b, 'c, 'd> {
         test: &'a BenchTestSetup,
         compiler: &'b CompilationNode,
         home: &'c Home,
-        results: &'d ResultsFolder,
+        results: &ResultsFolder,
         warm_start_buffer: usize,
     ) -> Result<Option<Self>> {
         let mut out = Self::new_helper(test, compiler, home, results, warm_start_buffer)?;
@@ -87,7 +85,7 @@ impl<'a, 'b, 'c, 'd> TestNode<'a, 'b, 'c, 'd> {
         );
 
         // Add the hash of the current bench config.
-        let hash = compute_test_node_hash(&self.test, &self.compiler);
+        let hash = config_hash(&(&self.test, &self.compiler));
         self.result.update_hash(hash);
 
         if out.is_err() {
@@ -135,10 +133,15 @@ impl<'a, 'b, 'c, 'd> TestNode<'a, 'b, 'c, 'd> {
     pub fn run(&mut self) -> Result<&BenchTestResult> {
         let env_vars = self.env_vars()?;
 
-        self.last_command_output = Some(run_test_bench_cmd(&self.test.benchmark_cmd, &env_vars)?);
+        let output = run_test_bench_cmd(&self.test.benchmark_cmd, &env_vars)?;
+        let timed_out = output.timed_out;
+        self.last_command_output = Some(output);
 
         self.warm_start_buffer = self.warm_start_buffer.saturating_sub(1);
-        if self.warm_start_buffer == 0 {
+        if timed_out {
+            warn!("Benchmark {} timed out", self.test.name);
+            self.result.update_timed_out();
+        } else if self.warm_start_buffer == 0 {
             self.result.update_result(
                 self.last_command_output
                     .as_ref()
@@ -156,14 +159,7 @@ impl<'a, 'b, 'c, 'd> TestNode<'a, 'b, 'c, 'd> {
     }
 }
 
-fn compute_test_node_hash(test: &BenchTestSetup, compiler: &CompilationNode) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    test.hash(&mut hasher);
-    compiler.hash(&mut hasher);
-    hasher.finish()
-}
-
-impl<'a, 'b, 'c, 'd> Drop for TestNode<'a, 'b, 'c, 'd> {
+impl<'a, 'b, 'c> Drop for TestNode<'a, 'b, 'c> {
     fn drop(&mut self) {
         // Write to the backing file.
         self.try_write().expect("failed to write result to file");
@@ -28,7 +28,7 @@ pub fn print_csv(
     info!("First date = {}", first_date);
     for node in nodes.iter() {
         if let Some(result) = BenchTestResult::read(results, &node.id(), &test.name)? {
-            for duration in result.durations.get() {
+            for duration in result.info.durations.get() {
                 let days = (node.commit.date()? - first_date).num_days();
                 info!("{} - {} = {} days", node.commit.date()?, first_date, days);
                 file.write_all(format!("{days},").as_bytes())
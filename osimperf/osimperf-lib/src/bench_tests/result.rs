@@ -1,16 +1,19 @@
-use super::Durations;
 use anyhow::Result;
 use log::debug;
+use osimperf_results::{
+    migrate::LEGACY_MONITOR_FILE_NAME, Outcome, ResultInfo, RESULT_INFO_FILE_NAME,
+};
 use serde::{Deserialize, Serialize};
 use std::{path::PathBuf, time::Duration};
 
 use crate::{Folder, Id, NodeFile, ResultsFolder};
 
-#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+/// Result of a benchmark of an install, using the schema shared with `osimperf-cli`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct BenchTestResult {
-    pub hash: Option<u64>,
-    pub failed_count: usize,
-    pub durations: Durations,
+    #[serde(flatten)]
+    pub info: ResultInfo,
+    #[serde(skip)]
     pub path_to_self: PathBuf,
 }
 
@@ -20,11 +23,28 @@ impl NodeFile for BenchTestResult {
     fn path_to_self(&self) -> PathBuf {
         self.path_to_self.clone()
     }
+
+    fn try_write(&self) -> Result<()> {
+        self.info.write(&self.path_to_self)
+    }
+
+    /// Reads the result of any schema version, and moves results that use the old file name.
+    fn try_read(&mut self) -> Result<()> {
+        let legacy = self.path_to_self.with_file_name(LEGACY_MONITOR_FILE_NAME);
+        if !self.path_to_self.exists() && legacy.exists() {
+            self.info = ResultInfo::read(&legacy)?;
+            debug!("Moving {:?} to {:?}", legacy, self.path_to_self);
+            std::fs::rename(&legacy, &self.path_to_self)?;
+        } else {
+            self.info = ResultInfo::read(&self.path_to_self)?;
+        }
+        Ok(())
+    }
 }
 
 impl BenchTestResult {
-    pub(crate) const fn magic_file() -> &'static str {
-        ".osimperf-result.node"
+    fn magic_file() -> &'static str {
+        RESULT_INFO_FILE_NAME
     }
 
     /// Returns the path that this result would be stored at.
@@ -39,8 +59,13 @@ impl BenchTestResult {
 
     fn new_helper<'a>(results: &ResultsFolder, id: &Id<'a>, name: &str) -> Self {
         Self {
+            info: ResultInfo::new(
+                name.to_owned(),
+                id.name.to_owned(),
+                id.hash.to_owned(),
+                id.date.to_owned(),
+            ),
             path_to_self: Self::path_to_node(results, id, name),
-            ..Default::default()
         }
     }
 
@@ -57,32 +82,45 @@ impl BenchTestResult {
     }
 
     pub(crate) fn should_run(&self, max_iter: usize, max_failures: usize) -> bool {
-        self.durations.len() < max_iter && self.failed_count < max_failures
+        self.info.durations.len() < max_iter && self.info.failed_count < max_failures
     }
 
     pub(crate) fn update_hash(&mut self, hash: u64) {
-        if self.hash == Some(hash) {
+        if self.info.config_hash == hash {
             return;
         }
         debug!("Changed config detected! Reset test result");
-        self.failed_count = 0;
-        self.durations.clear();
-        self.hash = Some(hash);
+        self.info.failed_count = 0;
+        self.info.durations.clear();
+        self.info.outcome = Outcome::Completed;
+        self.info.config_hash = hash;
     }
 
     pub(crate) fn update_result(&mut self, cmd_output: Option<Duration>) {
         if let Some(duration) = cmd_output {
             // If the command was succesfully executed:
-            self.durations.add_sample(duration);
-            self.failed_count = 0;
+            self.info.durations.add_sample(duration);
+            self.info.failed_count = 0;
+            self.info.outcome = Outcome::Completed;
         } else {
             // If the command was failed.
-            self.durations.clear();
-            self.failed_count += 1;
+            self.info.durations.clear();
+            self.info.failed_count += 1;
+            self.info.outcome = Outcome::Failed;
         }
     }
 
+    /// Same as failing the benchmark, but recorded as timed out.
+    pub(crate) fn update_timed_out(&mut self) {
+        self.update_result(None);
+        self.info.outcome = Outcome::TimedOut;
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.info.timed_out()
+    }
+
     pub fn failed(&self) -> bool {
-        self.failed_count > 0
+        self.info.failed()
     }
 }
@@ -0,0 +1,47 @@
+use super::BenchTestResult;
+use crate::Id;
+use anyhow::{Context, Result};
+use osimperf_results::ResultInfo;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+/// Read-only access to the result store written by `osimperf-cli`.
+///
+/// The tables are defined by [osimperf_results::STORE_SCHEMA].
+///
+/// See `osimperf-cli import` for adding existing result files to the store.
+#[derive(Debug)]
+pub struct ResultStore {
+    conn: Connection,
+}
+
+impl ResultStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("failed to open result store at {:?}", path))?;
+        Ok(Self { conn })
+    }
+
+    /// Reads the most recently stored result of a benchmark for the given install.
+    pub fn read<'a>(&self, id: &Id<'a>, name: &str) -> Result<Option<BenchTestResult>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT path, info FROM results
+                WHERE opensim_name = ?1 AND commit_hash = ?2 AND name = ?3
+                ORDER BY id DESC LIMIT 1",
+                params![id.name, id.hash, name],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .with_context(|| format!("failed to query result store for {name}"))?;
+        let Some((path, info)) = row else {
+            return Ok(None);
+        };
+
+        let path_to_self = PathBuf::from(path);
+        let info = ResultInfo::from_json(&info, &path_to_self)
+            .with_context(|| format!("failed to parse stored result {:?}", path_to_self))?;
+        Ok(Some(BenchTestResult { info, path_to_self }))
+    }
+}
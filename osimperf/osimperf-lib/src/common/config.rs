@@ -21,7 +21,7 @@ pub fn read_config<T: DeserializeOwned>(path: &Path) -> Result<T> {
 pub fn write_config<T: Serialize>(path: &Path, config: &T) -> Result<()> {
     let serialized = json!(config);
     let mut file = File::create(path)?;
-    let string = format!("{}", serde_json::to_string_pretty(&serialized).unwrap());
+    let string = serde_json::to_string_pretty(&serialized).unwrap();
     file.write_all(string.as_bytes())?;
     Ok(())
 }
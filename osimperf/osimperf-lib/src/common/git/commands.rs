@@ -1,19 +1,7 @@
 use crate::{Command, CommandTrait, PipedCommands};
 use anyhow::{ensure, Context, Result};
-use serde::{Deserialize, Serialize};
-use std::hash::Hash;
-use std::io::Lines;
 use std::path::Path;
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize, Hash)]
-// Can be created from the [Repository]
-pub struct Commit {
-    /// The commit we are checking out.
-    pub hash: String,
-    /// The date is for ordering results.
-    pub date: String,
-}
-
 pub fn read_current_branch(repo: &Path) -> Result<String> {
     // git rev-parse --abbrev-ref HEAD
     let mut cmd = Command::new("git");
@@ -22,12 +10,12 @@ pub fn read_current_branch(repo: &Path) -> Result<String> {
     cmd.add_arg("rev-parse");
     cmd.add_arg("--abbrev-ref");
     cmd.add_arg("HEAD");
-    Ok(cmd.run_trim()?)
+    cmd.run_trim()
 }
 
 pub fn commit_merged_to(repo: &Path, hash: &str) -> Result<String> {
     PipedCommands::parse(&format!(
-        r#"git -C {} branch --contains {} --no-color|sed -E s/\*//"#,
+        r#"git -C {} branch --contains {} --no-color|sed -E 's/\*//'"#,
         repo.to_str().unwrap(),
         hash
     ))
@@ -45,7 +33,7 @@ pub fn read_current_commit(repo: &Path) -> Result<String> {
     cmd.add_arg(repo.to_str().unwrap());
     cmd.add_arg("rev-parse");
     cmd.add_arg("HEAD");
-    Ok(cmd.run_trim()?)
+    cmd.run_trim()
 }
 
 pub fn checkout_commit(repo: &Path, hash: &str) -> Result<()> {
@@ -53,7 +41,7 @@ pub fn checkout_commit(repo: &Path, hash: &str) -> Result<()> {
     cmd.add_arg("-C");
     cmd.add_arg(repo.to_str().unwrap());
     cmd.add_arg("checkout");
-    cmd.add_arg(&hash);
+    cmd.add_arg(hash);
     cmd.run()?;
     Ok(())
 }
@@ -86,12 +74,10 @@ pub fn get_last_commit(repo: &Path, branch: &str) -> Result<(String, String)> {
 
     let mut split = output.lines().next().unwrap().split(',');
     let hash = String::from(split.next().context("failed to read hash")?);
-    let date = String::from(
-        split
-            .next()
-            .context("failed to read date")?
-            .replace('-', "_"),
-    );
+    let date = split
+        .next()
+        .context("failed to read date")?
+        .replace('-', "_");
     Ok((hash, date))
 }
 
@@ -118,12 +104,10 @@ pub fn get_commits_since(
     for line in output.lines() {
         let mut split = line.split(',');
         let hash = String::from(split.next().context("failed to read hash")?);
-        let date = String::from(
-            split
-                .next()
-                .context("failed to read date")?
-                .replace('-', "_"),
-        );
+        let date = split
+            .next()
+            .context("failed to read date")?
+            .replace('-', "_");
         commits.push((hash, date));
     }
 
@@ -1,8 +1,10 @@
 mod commands;
 
 pub use commands::{
-    checkout_commit, pull, read_current_commit, read_repo_url, was_commit_merged_to_branch, verify_repository, get_date
+    checkout_commit, get_date, pull, read_current_branch, read_current_commit, read_repo_url,
+    switch_branch, verify_repository, was_commit_merged_to_branch,
 };
+pub use osimperf_common::git::read_tree_hash;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -21,7 +23,7 @@ pub struct Commit {
 }
 
 pub fn get_last_commit(repo: &Path, branch: &str) -> Result<Commit> {
-    commands::get_last_commit(repo, branch).map(|x| Commit::from_hash_and_date_tuple(x))
+    commands::get_last_commit(repo, branch).map(Commit::from_hash_and_date_tuple)
 }
 
 /// returns Vec<(hash, date)>
@@ -34,7 +36,7 @@ pub fn get_commits_since(
     Ok(
         commands::get_commits_since(repo, branch, after_date, before_date)?
             .drain(..)
-            .map(|x| Commit::from_hash_and_date_tuple(x))
+            .map(Commit::from_hash_and_date_tuple)
             .collect(),
     )
 }
@@ -49,7 +51,7 @@ pub fn get_commit_from_hash(
     Ok(
         commands::get_commits_since(repo, branch, after_date, before_date)?
             .drain(..)
-            .map(|x| Commit::from_hash_and_date_tuple(x))
+            .map(Commit::from_hash_and_date_tuple)
             .collect(),
     )
 }
@@ -64,8 +66,8 @@ impl Commit {
 
     // TODO keep as chrono in struct itself.
     pub fn date(&self) -> anyhow::Result<Date> {
-        Ok(Date::parse_from_str(&self.date, "%Y_%m_%d")
-            .with_context(|| format!("failed to parse date {} to NaiveDate", self.date))?)
+        Date::parse_from_str(&self.date, "%Y_%m_%d")
+            .with_context(|| format!("failed to parse date {} to NaiveDate", self.date))
     }
 
     pub fn new_last_at_date(repo: &Path, branch: &str, date: &Date) -> Result<Option<Self>> {
@@ -74,16 +76,16 @@ impl Commit {
         Ok(
             commands::get_commits_since(repo, branch, Some(&after_date), Some(&before_date))?
                 .drain(..)
-                .map(|x| Commit::from_hash_and_date_tuple(x))
+                .map(Commit::from_hash_and_date_tuple)
                 .next(),
         )
     }
 
     pub fn new_last_commit(repo: &Path, branch: &str) -> Result<Self> {
-        commands::get_last_commit(repo, branch).map(|x| Commit::from_hash_and_date_tuple(x))
+        commands::get_last_commit(repo, branch).map(Commit::from_hash_and_date_tuple)
     }
 
-    pub fn new_from_hash(repo: &Path, branch: &str, hash: String) -> Result<Self> {
+    pub fn new_from_hash(repo: &Path, _branch: &str, hash: String) -> Result<Self> {
         let date = commands::get_date(repo, &hash)?;
         Ok(Self::from_hash_and_date_tuple((hash, date)))
     }
@@ -1,9 +1,8 @@
 mod config;
-mod time;
 mod find;
 
 pub use config::{read_config, write_config, write_default_config};
 pub use find::{find_file_by_name, collect_configs, visit_dirs};
-pub use time::duration_since_boot;
+pub use osimperf_common::duration_since_boot;
 
 pub mod git;
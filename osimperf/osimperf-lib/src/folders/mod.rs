@@ -1,10 +1,10 @@
 use std::{
-    fs::{self, remove_dir_all, rename, OpenOptions},
+    fs::{self, remove_dir_all, OpenOptions},
     path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result};
-use log::trace;
+pub use osimperf_common::{copy_folder, erase_folder, remove_folder, REMOVING_FOLDER_PREFIX};
 
 pub static ARCHIVE_TOUCH_FILE: &str = ".osimperf-archive";
 pub static RESULTS_TOUCH_FILE: &str = ".osimperf-results";
@@ -12,8 +12,14 @@ pub static RESULTS_TOUCH_FILE: &str = ".osimperf-results";
 pub trait Folder: Sized {
     const TOUCH_FILE: &'static str;
 
+    /// # Safety
+    ///
+    /// The folder is not verified to contain the touch file.
     unsafe fn path_unchecked(&self) -> &Path;
 
+    /// # Safety
+    ///
+    /// The path is not verified to contain the touch file.
     unsafe fn new_unchecked(path: PathBuf) -> Self;
 
     fn path(&self) -> Result<&Path> {
@@ -52,31 +58,13 @@ pub trait Folder: Sized {
 unsafe fn create_magic_file(folder: &impl Folder) -> Result<()> {
     OpenOptions::new()
         .create(true)
+        .truncate(false)
         .write(true)
         .open(folder.magic_file())
         .with_context(|| format!("failed to create magic file at {:?}", folder.magic_file()))?;
     Ok(())
 }
 
-pub fn erase_folder(path: &Path) -> Result<()> {
-    let temp_dir = path.parent().unwrap().join("osimperf-temporary");
-    if path.exists() {
-        rename(&path, &temp_dir).context("unable to move install dir to temporary")?;
-        trace!("erase_folder fn: moving {:?} to {:?}", &path, &temp_dir);
-        trace!("erase_folder fn: remove {:?}", &temp_dir);
-        remove_dir_all(&temp_dir).with_context(|| format!("Failed to remove {:?}", temp_dir))?;
-    }
-    trace!(
-        "erase_folder fn: Create new empty folder at: {:?}",
-        &temp_dir
-    );
-    fs::create_dir(&temp_dir)
-        .with_context(|| format!("Failed to create directory: {:?}", temp_dir))?;
-    trace!("erase_folder fn: move empty folder to {:?}", path);
-    rename(&temp_dir, &path).context("unable to move temporary to path")?;
-    Ok(())
-}
-
 pub trait EraseableFolder: Folder {
     fn erase_folder(&self) -> Result<()> {
         let dir = PathBuf::from(self.path()?);
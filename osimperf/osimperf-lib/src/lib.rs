@@ -6,11 +6,10 @@ pub static BIO_LAB_URL: &str = "git@github.com:ComputationalBiomechanicsLab/open
 pub mod bench_tests;
 pub mod common;
 
-mod command;
 mod folders;
 mod node;
 
-pub use command::{
+pub use osimperf_common::{
     Command, CommandExecutor, CommandExecutorTrait, CommandOutput, CommandTrait, PipedCommands,
     PipedCommandsExecutor,
 };
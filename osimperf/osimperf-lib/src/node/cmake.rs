@@ -0,0 +1,61 @@
+pub use osimperf_common::cmake::{
+    CMakeBuilder, CMakeCmds, CMakeConfig, CMakeConfigReader, CMakeConfigurerer,
+    CMakeProgressStreamer, CompilationTarget,
+};
+
+use anyhow::Result;
+use osimperf_results::config_hash;
+
+use super::Id;
+use crate::{path_to_build, path_to_install, path_to_source, BuildFolder, Home, RepositoryState};
+
+/// Returns the cmake commands compiling the target of the node, in the archive and build folder.
+pub fn cmake_cmds<'a>(
+    id: &Id<'a>,
+    repo: &RepositoryState,
+    home: &Home,
+    build: &BuildFolder,
+    config: &CMakeConfig,
+    cmp_target: CompilationTarget,
+) -> Result<CMakeCmds> {
+    let dependency = match cmp_target {
+        CompilationTarget::Dependencies => None,
+        CompilationTarget::OpenSimCore => {
+            Some(path_to_install(CompilationTarget::Dependencies, id))
+        }
+        CompilationTarget::TestsSource => Some(path_to_install(CompilationTarget::OpenSimCore, id)),
+    };
+
+    let source = path_to_source(cmp_target, home, repo)?;
+    let build = path_to_build(cmp_target, build, id.branch, config_hash(config))?;
+
+    let target = match cmp_target {
+        CompilationTarget::Dependencies => None,
+        CompilationTarget::OpenSimCore => Some("install"),
+        CompilationTarget::TestsSource => Some("install"),
+    };
+
+    let mut args = config.cmake_args(cmp_target);
+    if let CompilationTarget::OpenSimCore = cmp_target {
+        let add_arg = format!(
+            "-DOPENSIM_DEPENDENCIES_DIR={}",
+            dependency.clone().unwrap().to_str().unwrap()
+        );
+        args.push(add_arg);
+    }
+
+    Ok(CMakeCmds::new(
+        CMakeConfigurerer {
+            source,
+            build: build.clone(),
+            install: path_to_install(cmp_target, id),
+            args: args.iter(),
+            dependency,
+        },
+        CMakeBuilder {
+            build,
+            target,
+            num_jobs: config.num_jobs,
+        },
+    ))
+}
@@ -1,7 +0,0 @@
-mod cmake_cmds;
-mod config;
-mod progress;
-
-pub use cmake_cmds::CMakeCmds;
-pub use config::{CMakeConfig, CMakeConfigReader};
-pub use progress::CMakeProgressStreamer;
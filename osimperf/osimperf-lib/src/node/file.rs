@@ -1,10 +1,11 @@
-use log::{warn, trace};
+use log::{trace, warn};
 use std::{fmt::Debug, fs::create_dir};
 use std::path::PathBuf;
 
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::common::{read_config, write_config};
+use crate::folders::remove_folder;
 
 // fn get_temp_file(path: &Path) -> PathBuf {
 //     path.parent().unwrap().join("temp-conf")
@@ -24,7 +25,7 @@ pub trait NodeFile: Serialize + DeserializeOwned + Debug {
         // trace!("moving temporary to {:?}", self.path_to_self());
         // rename(&temp, self.path_to_self())?;
 
-        write_config::<Self>(&self.path_to_self(), &self)?;
+        write_config::<Self>(&self.path_to_self(), self)?;
         Ok(())
     }
 
@@ -60,7 +61,7 @@ pub trait NodeFile: Serialize + DeserializeOwned + Debug {
             create_dir(&parent)?;
         }
 
-        if let Ok(_) = self.try_read() {
+        if self.try_read().is_ok() {
             // overwrites self.
             trace!("found previous node: {:#?}", self);
         } else {
@@ -71,12 +72,11 @@ pub trait NodeFile: Serialize + DeserializeOwned + Debug {
         Ok(())
     }
 
+    /// Removes the directory containing this node, see [remove_folder].
     fn delete_folder(self) -> anyhow::Result<()> {
         let file = self.path_to_self();
         let dir = file.parent().unwrap();
         warn!("Removing directory {:?}", dir);
-        warn!("Miising directory removal implementation");
-        // remove_dir_all(&dir).context(format!("Failed to remove directory: {:?}", dir))?;
-        Ok(())
+        remove_folder(dir)
     }
 }
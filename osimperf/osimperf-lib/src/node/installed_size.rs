@@ -9,10 +9,10 @@ pub fn folder_size(dir: &Path) -> Result<usize> {
     let word = output
         .split('\t')
         .next()
-        .with_context(|| format!("{}", cmd.print_command()))
+        .with_context(|| cmd.print_command())
         .context("failed to get first argument from command output")?;
     info!("Running command to get size: {}", cmd.print_command());
-    Ok(word
+    word
         .parse::<usize>()
-        .with_context(|| format!("Failed to parse word: {}", word))?)
+        .with_context(|| format!("Failed to parse word: {}", word))
 }
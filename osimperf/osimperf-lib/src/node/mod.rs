@@ -3,14 +3,12 @@ mod file;
 mod installed_size;
 mod repo;
 mod status;
-mod target;
 
 use anyhow::{Context, Result};
 pub use cmake::*;
 pub use file::NodeFile;
 pub use repo::*;
 pub use status::{Progress, State, Status};
-pub use target::CompilationTarget;
 
 use chrono::NaiveDate;
 
@@ -18,13 +16,14 @@ use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use crate::common::collect_configs;
-use crate::git::Commit;
-use crate::{erase_folder, Archive, BuildFolder, Folder, Home};
+use crate::git::{self, Commit};
+use crate::{copy_folder, erase_folder, Archive, BuildFolder, Folder, Home};
 
 use self::installed_size::folder_size;
-use log::{trace, warn};
+use log::{info, trace, warn};
 
 pub fn path_to_install<'a>(target: CompilationTarget, id: &Id<'a>) -> PathBuf {
     id.path().join(target.to_str())
@@ -42,15 +41,26 @@ pub fn path_to_source(
     })
 }
 
-pub fn path_to_build(target: CompilationTarget, build: &BuildFolder) -> Result<PathBuf> {
-    Ok(build.path()?.join(target.to_str()))
+/// Build directory of the target, kept between the commits of the branch for incremental builds.
+///
+/// Each cmake config has its own build directory, such that the cmake caches are not mixed.
+pub fn path_to_build(
+    target: CompilationTarget,
+    build: &BuildFolder,
+    branch: &str,
+    config_hash: u64,
+) -> Result<PathBuf> {
+    Ok(build
+        .path()?
+        .join(format!("{}-{:x}", branch.replace('/', "_"), config_hash))
+        .join(target.to_str()))
 }
 
 ///
 ///
 /// Stored at:
 /// archive/ID/.compilation-node.osimperf
-#[derive(Clone, Debug, Serialize, Deserialize, Hash)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CompilationNode {
     pub repo: Repository,
     pub commit: Commit,
@@ -60,6 +70,21 @@ pub struct CompilationNode {
     pub archive: PathBuf,
     /// Used to detect changes in cmake config.
     pub config_hash: Option<u64>,
+    /// Git tree hash of the dependencies source, for reusing the dependencies of other commits.
+    #[serde(default)]
+    pub dependencies_tree: Option<String>,
+}
+
+// Hashed as part of the benchmark config. The dependencies tree hash is only used for caching the
+// build, and is not hashed.
+impl Hash for CompilationNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.repo.hash(state);
+        self.commit.hash(state);
+        self.state.hash(state);
+        self.archive.hash(state);
+        self.config_hash.hash(state);
+    }
 }
 
 impl NodeFile for CompilationNode {
@@ -98,6 +123,7 @@ impl CompilationNode {
             commit,
             state: State::default(),
             config_hash: None,
+            dependencies_tree: None,
         };
         out.read_or_write_new()?;
         Ok(out)
@@ -107,7 +133,14 @@ impl CompilationNode {
         self.id().path().join(target.to_str())
     }
 
-    pub fn run(&mut self, home: &Home, build: &BuildFolder, config: &CMakeConfig) -> Result<bool> {
+    /// Compiles the targets, killing cmake if a step runs longer than the timeout.
+    pub fn run(
+        &mut self,
+        home: &Home,
+        build: &BuildFolder,
+        config: &CMakeConfig,
+        timeout: Option<Duration>,
+    ) -> Result<bool> {
         // Returns whether there was any compilation attempted.
         let already_compiled = self.state.get().iter().all(|x| x.is_done());
         if already_compiled {
@@ -150,25 +183,54 @@ impl CompilationNode {
                     .set(target, Status::Compiling(Progress { percentage: 0. }));
                 self.try_write()?;
 
-                // Setup cmake commands.
-                let cmd = CMakeCmds::new(&self.id(), &checked_out_token, home, build, config, target)?;
-                trace!("CMAKE COMMAND:\n{}", cmd.print_pretty());
+                // Reuse the dependencies of another commit if the source did not change.
+                let mut reuse = None;
+                if let CompilationTarget::Dependencies = target {
+                    self.dependencies_tree = git::read_tree_hash(
+                        checked_out_token.path(),
+                        &self.commit.hash,
+                        "dependencies",
+                    )
+                    .map_err(|err| warn!("Failed to read dependencies tree hash: {:?}", err))
+                    .ok();
+                    reuse = self.find_compiled_dependencies()?;
+                }
 
                 // Erase the install dir.
                 erase_folder(&install_dir)
                     .with_context(|| format!("failed to erase install dir: {:?}", install_dir))?;
 
-                // Erase the build dir.
-                erase_folder(&build.path()?.join(target.to_str()))
-                    .with_context(|| format!("failed to erase build dir"))?;
-
-                // Setup something to keep track of the progres (for the UI).
-                let mut progress = CMakeProgressStreamer::new(self, target);
-
-                // Start compilation.
-                let output = cmd
-                    .run(&mut progress, &install_dir)
-                    .with_context(|| format!("cmake failed: {:#?}", cmd.print_pretty()));
+                let output = if let Some(other) = reuse {
+                    info!("Reusing dependencies from {:?}", other);
+                    copy_folder(&other, &install_dir).map(|_| Duration::ZERO)
+                } else {
+                    // Setup cmake commands.
+                    let mut cmd = cmake_cmds(
+                        &self.id(),
+                        &checked_out_token,
+                        home,
+                        build,
+                        config,
+                        target,
+                    )?;
+                    cmd.set_timeout(timeout);
+                    trace!("CMAKE COMMAND:\n{}", cmd.print_pretty());
+
+                    // Setup something to keep track of the progres (for the UI).
+                    let mut progress = CMakeProgressStreamer::new(|percentage| {
+                        self.state.set(
+                            target,
+                            Status::Compiling(Progress {
+                                percentage: percentage as f64,
+                            }),
+                        );
+                        self.try_write()
+                    });
+
+                    // Start compilation.
+                    cmd.run(&mut progress, &install_dir)
+                        .with_context(|| format!("cmake failed: {:#?}", cmd.print_pretty()))
+                };
 
                 // Update the status.
                 self.state.set(target, Status::from_output(output));
@@ -187,6 +249,23 @@ impl CompilationNode {
         Ok(true)
     }
 
+    /// Returns the install dir of compiled dependencies with the same source and cmake config as
+    /// this node.
+    fn find_compiled_dependencies(&self) -> Result<Option<PathBuf>> {
+        let Some(tree) = self.dependencies_tree.as_ref() else {
+            return Ok(None);
+        };
+        let target = CompilationTarget::Dependencies;
+        Ok(collect_configs::<Self>(&self.archive, Self::magic_file())?
+            .iter()
+            .filter(|other| other.commit.hash != self.commit.hash)
+            .filter(|other| other.dependencies_tree.as_ref() == Some(tree))
+            .filter(|other| other.config_hash == self.config_hash)
+            .filter(|other| other.state.status(target).is_done())
+            .map(|other| other.install_dir(target))
+            .find(|dir| dir.exists()))
+    }
+
     /// This id is used to create a file name that is discernable from the others.
     pub fn id<'a>(&'a self) -> Id<'a> {
         Id {
@@ -200,8 +279,51 @@ impl CompilationNode {
 
     /// Returns the size of the installed targets.
     pub fn read_disk_size(&self) -> [usize; 3] {
-        [0, 1, 2].map(|i| CompilationTarget::from(i)).map(|target| {
+        [0, 1, 2].map(CompilationTarget::from).map(|target| {
             folder_size(&self.install_dir(target)).expect("failed to get size of install")
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use osimperf_results::config_hash;
+
+    /// The node before the dependencies tree hash was added.
+    #[derive(Hash)]
+    struct LegacyNode {
+        repo: Repository,
+        commit: Commit,
+        state: State,
+        archive: PathBuf,
+        config_hash: Option<u64>,
+    }
+
+    #[test]
+    fn dependencies_tree_does_not_change_hash() {
+        let commit = Commit {
+            hash: "abc123".to_owned(),
+            date: "2023_09_01".to_owned(),
+        };
+        let legacy = LegacyNode {
+            repo: Repository::default(),
+            commit: commit.clone(),
+            state: State::default(),
+            archive: PathBuf::from("archive"),
+            config_hash: Some(42),
+        };
+        let mut node = CompilationNode {
+            repo: Repository::default(),
+            commit,
+            state: State::default(),
+            archive: PathBuf::from("archive"),
+            config_hash: Some(42),
+            dependencies_tree: None,
+        };
+        assert_eq!(config_hash(&node), config_hash(&legacy));
+
+        node.dependencies_tree = Some("def456".to_owned());
+        assert_eq!(config_hash(&node), config_hash(&legacy));
+    }
+}
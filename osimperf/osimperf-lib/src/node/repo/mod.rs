@@ -1,4 +1,5 @@
 mod id;
+#[allow(clippy::module_inception)]
 mod repo;
 
 pub use id::Id;
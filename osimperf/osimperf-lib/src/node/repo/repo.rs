@@ -124,7 +124,7 @@ impl Repository {
         before_date: Option<&str>,
     ) -> anyhow::Result<Vec<Commit>> {
         let mut commits = Vec::<Commit>::new();
-        for c in Self::commits_between(&self, after_date, before_date)?.drain(..) {
+        for c in Self::commits_between(self, after_date, before_date)?.drain(..) {
             if let Some(last) = commits.last() {
                 let d0 = c.date.as_str().split_at(7).0;
                 let d1 = last.date.as_str().split_at(7).0;
@@ -146,7 +146,7 @@ impl Repository {
         before_date: Option<&str>,
     ) -> anyhow::Result<Vec<Commit>> {
         let mut commits = Vec::<Commit>::new();
-        for c in Self::commits_between(&self, after_date, before_date)?.drain(..) {
+        for c in Self::commits_between(self, after_date, before_date)?.drain(..) {
             if let Some(last) = commits.last() {
                 trace!("comparing {:?} to {:?}", c.date, last.date,);
                 if c.date == last.date {
@@ -163,7 +163,7 @@ impl Repository {
     pub fn checkout(&self, commit: &Commit) -> Result<RepositoryState> {
         git::was_commit_merged_to_branch(&self.path, &self.branch, &commit.hash)?;
         let hash = git::read_current_commit(&self.path)?;
-        if &hash != &commit.hash {
+        if hash != commit.hash {
             git::checkout_commit(&self.path, &commit.hash)?;
             ensure!(
                 git::read_current_commit(&self.path)? == commit.hash,
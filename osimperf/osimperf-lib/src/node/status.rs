@@ -6,8 +6,9 @@ use super::CompilationTarget;
 
 // TODO status improvements
 // size of install
-#[derive(Clone, Debug, Serialize, Deserialize, Hash)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Hash)]
 pub enum Status {
+    #[default]
     Idle,
     Compiling(Progress),
     Error(String),
@@ -48,12 +49,6 @@ impl Status {
     }
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Self::Idle
-    }
-}
-
 /// The three compilation targets:
 #[derive(Clone, Debug, Default, Serialize, Deserialize, Hash)]
 pub struct State {
@@ -6,12 +6,12 @@ use osimperf_lib::{
     bench_tests::{BenchTestSetup, TestNode},
     common::{duration_since_boot, read_config, write_default_config},
     Archive, BioLabRepositoryConfig, CMakeConfig, CMakeConfigReader, CompilationNode, Folder, Home,
-    NodeFile, Repository, RepositoryConfig, CompilationTarget,
+    NodeFile, Repository, RepositoryConfig, CompilationTarget, ResultsFolder, remove_folder,
 };
 use rand::prelude::*;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::collections::HashSet;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 pub struct Args {
@@ -54,6 +54,14 @@ pub struct Args {
     /// Number of test cycles that are ignored, before recording results.
     #[arg(long, default_value_t = 2)]
     pub warm_start_buffer: usize,
+
+    /// Timeout in seconds of benchmark commands, for tests that do not specify their own.
+    #[arg(long)]
+    pub test_timeout: Option<u64>,
+
+    /// Timeout in minutes of the cmake configure and build steps.
+    #[arg(long)]
+    pub compile_timeout: Option<u64>,
 }
 
 fn main() -> Result<()> {
@@ -81,13 +89,13 @@ fn do_main(args: Args) -> Result<()> {
 
 fn do_main_loop(args: &Args) -> Result<()> {
     // Setup folders, read configs etc.
-    let home = Home::new_or_current(args.home.as_ref().map(|p| p.as_str()))?;
+    let home = Home::new_or_current(args.home.as_deref())?;
     let build = home.default_build()?;
     let archive = home.default_archive()?;
     let results_dir = home.default_results()?;
     let tests_dir = home.path()?.join("tests");
 
-    let cmake_config = CMakeConfigReader::read(&home)?;
+    let cmake_config = CMakeConfigReader::read(home.path()?)?;
     info!("compile flags = {:#?}", cmake_config);
 
     let mut repo = RepositoryConfig::default().take(&home)?;
@@ -101,32 +109,35 @@ fn do_main_loop(args: &Args) -> Result<()> {
             .join("osimperf-biolab-targets.conf"),
     )
     .map(|x| x.take(&home).expect("failed to verify repository"))
-    .unwrap_or(Vec::new());
+    .unwrap_or_default();
 
     // Loop:
     // 1. Warm start.
     // 2. Do X benchmark tests.
     // 3. Do one compilation.
     // 4. Goto step 1.
+    let test_timeout = args.test_timeout.map(Duration::from_secs);
+    let compile_timeout = args.compile_timeout.map(|minutes| Duration::from_secs(minutes * 60));
+
     let mut last_pull = None;
     let mut rng = rand::thread_rng();
     loop {
         // Run the benchmark tests.
         let nodes = CompilationNode::collect_archived(&archive)?;
-        let test_setups = BenchTestSetup::find_all(&tests_dir)?;
+        let test_setups = BenchTestSetup::find_all(&tests_dir, test_timeout)?;
         for node in nodes.iter() {
             let mut tests = Vec::new();
             for setup in test_setups.iter() {
                 // Creating the test node also sets up the context.
                 if let Some(test) =
-                    TestNode::new(&setup, &node, &home, &results_dir, args.warm_start_buffer)?
+                    TestNode::new(setup, node, &home, &results_dir, args.warm_start_buffer)?
                 {
                     tests.push(test);
                 }
             }
 
             let mut count = 0;
-            while tests.len() > 0 {
+            while !tests.is_empty() {
                 // Dropping tests triggers post benchmark cmds.
                 tests.retain(|t| t.should_run(args.test_repeats, args.max_test_fail));
                 tests.shuffle(&mut rng);
@@ -137,7 +148,7 @@ fn do_main_loop(args: &Args) -> Result<()> {
                     info!("running = {}", test.name());
                     trace!("Start bench test: {:#?}", test);
                     let res = test.run()?;
-                    if res.failed_count > 0 {
+                    if res.failed() {
                         trace!("Failed bench test: {:#?}", test);
                     }
                     if args.write_intermediate_results {
@@ -160,7 +171,7 @@ fn do_main_loop(args: &Args) -> Result<()> {
                     info!("{}", r.pull()?);
                 }
 
-                garbage_collector(&archive, &repo)?;
+                garbage_collector(&archive, &results_dir, &repo)?;
             }
         }
 
@@ -170,12 +181,12 @@ fn do_main_loop(args: &Args) -> Result<()> {
         let mut compiled_a_node = false;
 
         // First consider any external biolab repo.
-        for i in 0..biolab.len() {
-            let commit = biolab[i].last_commit()?;
-            let mut node = CompilationNode::new(biolab[i].clone(), commit, &archive)?;
+        for repo in biolab.iter() {
+            let commit = repo.last_commit()?;
+            let mut node = CompilationNode::new(repo.clone(), commit, &archive)?;
 
             let config = cmake_config.get(&node.commit.date()?);
-            compiled_a_node |= node.run(&home, &build, config)?;
+            compiled_a_node |= node.run(&home, &build, config, compile_timeout)?;
 
             // Stop after a single compilation.
             if compiled_a_node {
@@ -201,7 +212,7 @@ fn do_main_loop(args: &Args) -> Result<()> {
 
             debug!("Start compiling monthly {:#?}", node);
             let config = cmake_config.get(&node.commit.date()?);
-            compiled_a_node |= node.run(&home, &build, &config)?;
+            compiled_a_node |= node.run(&home, &build, config, compile_timeout)?;
 
             // Stop compiling if we failed compiling opensim-core X times in a row.
             if !node.state.status(CompilationTarget::OpenSimCore).is_done() {
@@ -243,7 +254,7 @@ fn do_main_loop(args: &Args) -> Result<()> {
 
             debug!("Start compiling daily {:#?}", node);
             let config = cmake_config.get(&node.commit.date()?);
-            compiled_a_node |= node.run(&home, &build, &config)?;
+            compiled_a_node |= node.run(&home, &build, config, compile_timeout)?;
             if compiled_a_node {
                 break;
             }
@@ -251,36 +262,38 @@ fn do_main_loop(args: &Args) -> Result<()> {
     }
 }
 
-fn garbage_collector(archive: &Archive, repo: &Repository) -> Result<()> {
+fn garbage_collector(archive: &Archive, results: &ResultsFolder, repo: &Repository) -> Result<()> {
     // Cleanup archive:
     // We want to have a daily version installed: but we pull periodically per day.
-    // This means that we might install different versions
-    let mut stray_nodes = CompilationNode::collect_archived(&archive)?;
+    // This means that we might install different versions of the same day. Once the daily
+    // commit of a day is installed, the other versions of that day are removed, together with
+    // their test results.
     let daily_commits = repo.collect_daily_commits(None, None)?;
-    // Look for nodes that have a duplicate day, and are not part of the daily commits
-    // list. We do not want to accidentally delete stuff from the daily commit list,
-    // nor do we want to remove something unique.
-    while stray_nodes.len() > 0 {
-        let check_node = stray_nodes.remove(0);
-        // Check if there are multiple nodes on the same day.
-        let mut duplicate_day = stray_nodes
-            .iter()
-            .filter(|n| n.commit.date == check_node.commit.date)
-            .count()
-            > 1;
-        // Check if it is not part of the daily list.
-        duplicate_day &= daily_commits
-            .iter()
-            .filter(|n| n.date == check_node.commit.date)
-            .count()
-            != 1;
-        // This node is OK, we keep it.
-        if !duplicate_day {
+    let is_daily = |node: &CompilationNode| daily_commits.iter().any(|c| c.hash == node.commit.hash);
+    // Only consider nodes of this repository.
+    let nodes = CompilationNode::collect_archived(archive)?
+        .into_iter()
+        .filter(|node| node.repo.name() == repo.name())
+        .collect::<Vec<_>>();
+    let installed_days = nodes
+        .iter()
+        .filter(|node| is_daily(node))
+        .filter(|node| node.state.status(CompilationTarget::OpenSimCore).is_done())
+        .map(|node| node.commit.date.clone())
+        .collect::<HashSet<_>>();
+    for node in nodes {
+        // Never delete a daily commit, nor something unique.
+        if is_daily(&node) || !installed_days.contains(&node.commit.date) {
             continue;
         }
-        // Delete the stray node.
-        check_node.delete_folder()?;
-        // TODO delete stray test results.
+        // Delete the stray node, and its test results.
+        info!("Removing stray node {} ({})", node.commit.hash, node.commit.date);
+        let node_results = results.path()?.join(node.id().subfolder_name());
+        node.delete_folder()?;
+        if node_results.exists() {
+            info!("Removing stray test results {:?}", node_results);
+            remove_folder(&node_results)?;
+        }
     }
     Ok(())
 }
@@ -0,0 +1,385 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Scales the MAD to be a consistent estimator of the standard deviation for normal data.
+const MAD_TO_STDDEV: f64 = 1.4826;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Durations {
+    /// All samples in the order they were recorded, including outliers.
+    durations: Vec<Duration>,
+    mean: Option<f64>,
+    stddev: Option<f64>,
+    median: Option<f64>,
+    /// Median absolute deviation from the median (unscaled).
+    mad: Option<f64>,
+    min: Option<f64>,
+    max: Option<f64>,
+    #[serde(default)]
+    percentiles: Vec<Percentile>,
+    /// Policy used for rejecting outliers before computing the statistics.
+    outlier_policy: Option<OutlierPolicy>,
+    /// Samples that were rejected by the outlier policy.
+    #[serde(default)]
+    outliers: Vec<Duration>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct Percentile {
+    /// Percentile level in range [0, 100].
+    pub level: f64,
+    pub value: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutlierMethod {
+    /// Reject samples outside of [Q1 - k * IQR, Q3 + k * IQR].
+    Iqr,
+    /// Reject samples further than k * (scaled) MAD from the median.
+    Mad,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct OutlierPolicy {
+    pub method: OutlierMethod,
+    pub threshold: f64,
+}
+
+impl OutlierPolicy {
+    pub fn new(method: OutlierMethod, threshold: Option<f64>) -> Self {
+        Self {
+            method,
+            threshold: threshold.unwrap_or(match method {
+                OutlierMethod::Iqr => 1.5,
+                OutlierMethod::Mad => 3.,
+            }),
+        }
+    }
+
+    /// Returns the range of accepted values, given the sorted samples.
+    fn bounds(&self, sorted: &[f64]) -> Option<(f64, f64)> {
+        match self.method {
+            OutlierMethod::Iqr => {
+                let q1 = percentile(sorted, 25.)?;
+                let q3 = percentile(sorted, 75.)?;
+                let iqr = q3 - q1;
+                Some((q1 - self.threshold * iqr, q3 + self.threshold * iqr))
+            }
+            OutlierMethod::Mad => {
+                let median = percentile(sorted, 50.)?;
+                let mad = median_absolute_deviation(sorted, median)? * MAD_TO_STDDEV;
+                // Identical samples would reject everything that deviates.
+                if mad <= 0. {
+                    return None;
+                }
+                Some((median - self.threshold * mad, median + self.threshold * mad))
+            }
+        }
+    }
+}
+
+/// The statistic used for summarizing the durations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum Estimator {
+    /// Mean, with standard deviation as spread.
+    #[default]
+    Mean,
+    /// Median, with MAD as spread.
+    Median,
+    /// Fastest sample.
+    Min,
+    /// Slowest sample.
+    Max,
+}
+
+/// Linearly interpolated percentile of sorted samples.
+fn percentile(sorted: &[f64], level: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = level.clamp(0., 100.) / 100. * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+fn median_absolute_deviation(sorted: &[f64], median: f64) -> Option<f64> {
+    let mut deviations = sorted
+        .iter()
+        .map(|x| (x - median).abs())
+        .collect::<Vec<f64>>();
+    deviations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    percentile(&deviations, 50.)
+}
+
+impl Durations {
+    /// Recomputes all statistics from the inliers.
+    pub(crate) fn update(&mut self) {
+        let mut all = self
+            .durations
+            .iter()
+            .map(|dt| dt.as_secs_f64())
+            .collect::<Vec<f64>>();
+        all.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let bounds = self.outlier_policy.and_then(|p| p.bounds(&all));
+        let is_inlier = |dt: f64| {
+            bounds
+                .map(|(lo, hi)| (lo..=hi).contains(&dt))
+                .unwrap_or(true)
+        };
+        self.outliers = self
+            .durations
+            .iter()
+            .filter(|dt| !is_inlier(dt.as_secs_f64()))
+            .cloned()
+            .collect();
+        let samples = all
+            .iter()
+            .cloned()
+            .filter(|&dt| is_inlier(dt))
+            .collect::<Vec<f64>>();
+
+        self.set_mean(&samples);
+        self.set_stddev(&samples);
+        self.median = percentile(&samples, 50.);
+        self.mad = self
+            .median
+            .and_then(|median| median_absolute_deviation(&samples, median));
+        self.min = samples.first().cloned();
+        self.max = samples.last().cloned();
+        for p in self.percentiles.iter_mut() {
+            p.value = percentile(&samples, p.level);
+        }
+    }
+
+    fn set_mean(&mut self, samples: &[f64]) {
+        self.mean =
+            (!samples.is_empty()).then(|| samples.iter().sum::<f64>() / samples.len() as f64)
+    }
+
+    fn set_stddev(&mut self, samples: &[f64]) {
+        self.stddev = self
+            .mean
+            .map(|mean| {
+                samples
+                    .iter()
+                    .map(|dt| dt - mean)
+                    .map(|diff| diff.powi(2))
+                    .sum::<f64>()
+                    / (samples.len() as f64 - 1.)
+            })
+            .map(|var| var.sqrt())
+            .filter(|_| samples.len() > 1);
+    }
+
+    /// Removes all samples, keeping the outlier policy and percentile levels.
+    pub fn clear(&mut self) {
+        self.durations.clear();
+        self.update();
+    }
+
+    pub fn add_sample(&mut self, duration: Duration) {
+        self.durations.push(duration);
+        self.update();
+    }
+
+    pub fn set_outlier_policy(&mut self, policy: Option<OutlierPolicy>) {
+        self.outlier_policy = policy;
+        self.update();
+    }
+
+    /// Sets the percentile levels (in range [0, 100]) to compute.
+    pub fn set_percentiles(&mut self, levels: &[f64]) {
+        self.percentiles = levels
+            .iter()
+            .map(|&level| Percentile { level, value: None })
+            .collect();
+        self.update();
+    }
+
+    pub fn len(&self) -> usize {
+        self.durations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.durations.is_empty()
+    }
+
+    /// Returns all samples, including outliers.
+    pub fn get(&self) -> &[Duration] {
+        &self.durations
+    }
+
+    /// Returns the samples that were not rejected as outliers.
+    pub fn inliers(&self) -> Vec<Duration> {
+        let mut outliers = self.outliers.clone();
+        let mut out = Vec::with_capacity(self.durations.len());
+        for dt in self.durations.iter() {
+            if let Some(i) = outliers.iter().position(|x| x == dt) {
+                outliers.swap_remove(i);
+            } else {
+                out.push(*dt);
+            }
+        }
+        out
+    }
+
+    pub fn get_outliers(&self) -> &[Duration] {
+        &self.outliers
+    }
+
+    pub fn get_stddev(&self) -> Option<f64> {
+        self.stddev
+    }
+
+    pub fn get_mean(&self) -> Option<f64> {
+        self.mean
+    }
+
+    pub fn get_median(&self) -> Option<f64> {
+        self.median
+    }
+
+    pub fn get_mad(&self) -> Option<f64> {
+        self.mad
+    }
+
+    pub fn get_min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn get_max(&self) -> Option<f64> {
+        self.max
+    }
+
+    pub fn get_percentiles(&self) -> &[Percentile] {
+        &self.percentiles
+    }
+
+    /// Returns the duration summarized by the estimator.
+    pub fn get_estimate(&self, estimator: Estimator) -> Option<f64> {
+        match estimator {
+            Estimator::Mean => self.mean,
+            Estimator::Median => self.median,
+            Estimator::Min => self.min,
+            Estimator::Max => self.max,
+        }
+    }
+
+    /// Returns the spread belonging to the estimator, if any.
+    pub fn get_spread(&self, estimator: Estimator) -> Option<f64> {
+        match estimator {
+            Estimator::Mean => self.stddev,
+            Estimator::Median => self.mad,
+            Estimator::Min | Estimator::Max => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn durations(secs: &[f64]) -> Durations {
+        let mut durations = Durations::default();
+        for &s in secs {
+            durations.add_sample(Duration::from_secs_f64(s));
+        }
+        durations
+    }
+
+    fn assert_close(a: Option<f64>, b: f64) {
+        let a = a.expect("statistic is missing");
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn empty_has_no_statistics() {
+        let d = Durations::default();
+        for estimator in [
+            Estimator::Mean,
+            Estimator::Median,
+            Estimator::Min,
+            Estimator::Max,
+        ] {
+            assert_eq!(d.get_estimate(estimator), None);
+        }
+        assert_eq!(d.get_stddev(), None);
+    }
+
+    #[test]
+    fn single_sample_has_no_stddev() {
+        let d = durations(&[2.]);
+        assert_close(d.get_mean(), 2.);
+        assert_close(d.get_median(), 2.);
+        assert_eq!(d.get_stddev(), None);
+    }
+
+    #[test]
+    fn statistics_of_samples() {
+        let d = durations(&[4., 1., 3., 2.]);
+        assert_close(d.get_mean(), 2.5);
+        // Sample standard deviation: sqrt(5 / 3).
+        assert_close(d.get_stddev(), (5f64 / 3.).sqrt());
+        assert_close(d.get_median(), 2.5);
+        // Absolute deviations are 0.5, 0.5, 1.5, 1.5.
+        assert_close(d.get_mad(), 1.);
+        assert_close(d.get_min(), 1.);
+        assert_close(d.get_max(), 4.);
+        assert_close(d.get_spread(Estimator::Median), 1.);
+        assert_eq!(d.get_spread(Estimator::Min), None);
+    }
+
+    #[test]
+    fn interpolated_percentiles() {
+        let mut d = durations(&[1., 2., 3., 4., 5.]);
+        d.set_percentiles(&[0., 25., 90., 100.]);
+        let values = d
+            .get_percentiles()
+            .iter()
+            .map(|p| p.value.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![1., 2., 4.6, 5.]);
+    }
+
+    #[test]
+    fn iqr_rejects_outlier() {
+        let mut d = durations(&[1., 1.1, 0.9, 1.05, 0.95, 10.]);
+        d.set_outlier_policy(Some(OutlierPolicy::new(OutlierMethod::Iqr, None)));
+        assert_eq!(d.get_outliers(), &[Duration::from_secs(10)]);
+        assert_eq!(d.inliers().len(), 5);
+        assert_close(d.get_max(), 1.1);
+        assert_close(d.get_mean(), 1.);
+        // All samples are kept.
+        assert_eq!(d.len(), 6);
+    }
+
+    #[test]
+    fn mad_rejects_outlier() {
+        let mut d = durations(&[1., 1.1, 0.9, 1.05, 0.95, 10.]);
+        d.set_outlier_policy(Some(OutlierPolicy::new(OutlierMethod::Mad, None)));
+        assert_eq!(d.get_outliers(), &[Duration::from_secs(10)]);
+        assert_close(d.get_median(), 1.);
+    }
+
+    #[test]
+    fn identical_samples_are_not_outliers() {
+        let mut d = durations(&[1., 1., 1.]);
+        d.set_outlier_policy(Some(OutlierPolicy::new(OutlierMethod::Mad, None)));
+        assert!(d.get_outliers().is_empty());
+        assert_close(d.get_mad(), 0.);
+    }
+
+    #[test]
+    fn clear_keeps_percentile_levels() {
+        let mut d = durations(&[1., 2.]);
+        d.set_percentiles(&[50.]);
+        d.clear();
+        assert_eq!(d.len(), 0);
+        assert_eq!(d.get_percentiles()[0].value, None);
+        d.add_sample(Duration::from_secs(3));
+        assert_eq!(d.get_percentiles()[0].value, Some(3.));
+    }
+}
@@ -0,0 +1,117 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Resources used by a child process, as reported by `wait4`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+pub struct ResourceUsage {
+    /// Peak resident set size in kilobytes.
+    pub max_rss: u64,
+    /// Time spent in user mode.
+    pub user_time: Duration,
+    /// Time spent in kernel mode.
+    pub system_time: Duration,
+    /// Page faults serviced without any I/O.
+    pub minor_page_faults: u64,
+    /// Page faults that required I/O.
+    pub major_page_faults: u64,
+    pub voluntary_context_switches: u64,
+    pub involuntary_context_switches: u64,
+}
+
+impl ResourceUsage {
+    /// Total cpu time spent in user and kernel mode.
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
+}
+
+/// Hardware event counts of a process (and its children), as reported by `perf_event_open`.
+///
+/// Counters that are not supported by the hardware are left empty.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+pub struct PerfCounters {
+    pub instructions: Option<u64>,
+    pub cycles: Option<u64>,
+    pub cache_misses: Option<u64>,
+    pub branch_misses: Option<u64>,
+}
+
+/// Cpu pinning and scheduling settings, applied to a command before it starts.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Isolation {
+    /// Cpus the command is pinned to, or any cpu if empty.
+    pub cpus: Vec<usize>,
+    /// Niceness of the command.
+    pub nice: Option<i32>,
+    /// Real-time (SCHED_FIFO) priority of the command.
+    pub realtime_priority: Option<i32>,
+}
+
+impl Isolation {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Cpu frequency scaling settings, read from `/sys/devices/system/cpu`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuFrequency {
+    /// Distinct scaling governors of the used cpus.
+    pub governors: Vec<String>,
+    /// Whether turbo boost is enabled, if known.
+    pub turbo: Option<bool>,
+}
+
+impl CpuFrequency {
+    /// Logs a warning for settings that add noise to the measurements.
+    pub fn warn_if_noisy(&self) {
+        let other = self
+            .governors
+            .iter()
+            .filter(|g| g.as_str() != "performance")
+            .collect::<Vec<&String>>();
+        if !other.is_empty() {
+            warn!("Cpu frequency governor is not `performance`: {:?}", other);
+        }
+        if self.turbo == Some(true) {
+            warn!("Turbo boost is enabled, this adds noise to the measurements");
+        }
+    }
+}
+
+/// Host and build environment of a benchmark result.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    pub hostname: String,
+    pub cpu_model: String,
+    /// Number of logical cpus.
+    pub cpu_cores: usize,
+    /// Total memory in kilobytes.
+    pub memory: u64,
+    pub kernel: String,
+    /// Compiler used to build the install (if known).
+    pub compiler: Option<String>,
+    /// The `CMAKE_BUILD_TYPE` of the install (if known).
+    pub build_type: Option<String>,
+}
+
+impl Fingerprint {
+    /// Returns true if both were recorded on the same hardware.
+    ///
+    /// The kernel, compiler and build type are not compared.
+    pub fn same_machine(&self, other: &Self) -> bool {
+        self.hostname == other.hostname
+            && self.cpu_model == other.cpu_model
+            && self.cpu_cores == other.cpu_cores
+            && self.memory == other.memory
+    }
+
+    /// Name of the machine for display.
+    pub fn machine_name(&self) -> String {
+        format!(
+            "{} ({}, {} cores)",
+            self.hostname, self.cpu_model, self.cpu_cores
+        )
+    }
+}
@@ -0,0 +1,27 @@
+//! Benchmark results, shared by `osimperf-cli`, `osimperf-monitor` and `osimperf-tui`.
+
+mod durations;
+mod environment;
+pub mod migrate;
+mod result;
+mod store;
+
+pub use durations::{Durations, Estimator, OutlierMethod, OutlierPolicy, Percentile};
+pub use environment::{CpuFrequency, Fingerprint, Isolation, PerfCounters, ResourceUsage};
+pub use result::{Outcome, ResultInfo, ValidationFailure};
+pub use store::STORE_SCHEMA;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+pub static RESULT_INFO_FILE_NAME: &str = "osimperf-result-info.json";
+
+/// Version of the [ResultInfo] schema, older versions are upgraded when read.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Hash of a benchmark config, for detecting changes to the config between runs.
+pub fn config_hash<T: Hash + ?Sized>(config: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    config.hash(&mut hasher);
+    hasher.finish()
+}
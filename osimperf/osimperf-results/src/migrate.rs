@@ -0,0 +1,182 @@
+//! Upgrades results written using older versions of the schema.
+//!
+//! Schema versions:
+//! - 0: Files without a `schema_version`. These are either written by `osimperf-cli` (as
+//!   `osimperf-result-info.json`), or by `osimperf-monitor` (as `.osimperf-result.node`).
+//! - 1: The shared [ResultInfo], written by all tools.
+
+use crate::{Durations, Outcome, ResultInfo, SCHEMA_VERSION};
+use anyhow::{bail, ensure, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// File name of the results written by the monitor before schema version 1.
+pub static LEGACY_MONITOR_FILE_NAME: &str = ".osimperf-result.node";
+
+/// Result of a benchmark as written by `osimperf-monitor` before schema version 1.
+#[derive(Debug, Deserialize)]
+struct MonitorResult {
+    hash: Option<u64>,
+    failed_count: usize,
+    durations: Durations,
+}
+
+/// Parses a result of any schema version.
+///
+/// Returns the upgraded result, and the schema version it was written with.
+pub(crate) fn upgrade(json: &str, path: &Path) -> Result<(ResultInfo, u32)> {
+    let mut value: Value = serde_json::from_str(json)?;
+    let version = match value.get("schema_version") {
+        Some(version) => version.as_u64().context("schema_version is not a number")? as u32,
+        None => 0,
+    };
+    ensure!(
+        version <= SCHEMA_VERSION,
+        "unsupported schema version {version}, the latest supported version is {SCHEMA_VERSION}"
+    );
+
+    if version == 0 {
+        // Only the monitor stored the path of the result.
+        let mut result = if value.get("path_to_self").is_some() {
+            from_monitor_result(value, path)?
+        } else {
+            // Fields added to the results of the CLI have defaults.
+            value["schema_version"] = SCHEMA_VERSION.into();
+            serde_json::from_value::<ResultInfo>(value)?
+        };
+        // Only the mean and standard deviation were stored, the other statistics are missing.
+        result.durations.update();
+        return Ok((result, version));
+    }
+    Ok((serde_json::from_value(value)?, version))
+}
+
+/// Converts a monitor result, taking the names, date and commit from its path.
+///
+/// Monitor results are stored at `<results>/<install>-<date>-<commit>/<benchmark>/`.
+fn from_monitor_result(value: Value, path: &Path) -> Result<ResultInfo> {
+    let node: MonitorResult = serde_json::from_value(value)?;
+    let file_name = |path: Option<&Path>| {
+        path.and_then(|p| p.file_name())
+            .and_then(|f| f.to_str())
+            .map(String::from)
+            .with_context(|| format!("unexpected location of monitor result {:?}", path))
+    };
+    let benchmark_dir = path.parent();
+    let name = file_name(benchmark_dir)?;
+    let install = file_name(benchmark_dir.and_then(|p| p.parent()))?;
+
+    // The install name may contain dashes, the date and commit do not.
+    let mut parts = install.rsplitn(3, '-');
+    let (Some(commit), Some(date), Some(opensim_name)) = (parts.next(), parts.next(), parts.next())
+    else {
+        bail!("failed to parse install name, date and commit from {install}");
+    };
+
+    let mut result = ResultInfo::new(
+        name,
+        opensim_name.to_owned(),
+        commit.to_owned(),
+        date.to_owned(),
+    );
+    result.setup = true;
+    result.durations = node.durations;
+    result.config_hash = node.hash.unwrap_or_default();
+    result.failed_count = node.failed_count;
+    result.outcome = Outcome::default();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RESULT_INFO_FILE_NAME;
+
+    static CLI_V0: &str = r#"{
+        "name": "Arm26",
+        "cell_name": null,
+        "opensim_name": "opensim-core",
+        "commit": "abc123",
+        "date": "2023-09-01",
+        "setup": true,
+        "durations": {
+            "durations": [
+                {"secs": 3, "nanos": 0},
+                {"secs": 1, "nanos": 0},
+                {"secs": 2, "nanos": 0}
+            ],
+            "mean": 2.0,
+            "stddev": 1.0
+        },
+        "grind": null,
+        "config_hash": 42,
+        "opensim_log": null
+    }"#;
+
+    static MONITOR_V0: &str = r#"{
+        "hash": 42,
+        "failed_count": 1,
+        "durations": {
+            "durations": [{"secs": 1, "nanos": 0}, {"secs": 4, "nanos": 0}],
+            "mean": 2.5,
+            "stddev": 2.1213203435596424
+        },
+        "path_to_self": "results/opensim-core-main-2023_09_01-abc123/Arm26/.osimperf-result.node"
+    }"#;
+
+    #[test]
+    fn upgrades_cli_result() {
+        let (result, version) = upgrade(CLI_V0, Path::new(RESULT_INFO_FILE_NAME)).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(result.schema_version, SCHEMA_VERSION);
+        assert_eq!(result.name, "Arm26");
+        assert_eq!(result.config_hash, 42);
+        assert_eq!(result.durations.len(), 3);
+        assert_eq!(result.durations.get_mean(), Some(2.));
+        assert_eq!(result.durations.get_median(), Some(2.));
+        assert_eq!(result.durations.get_min(), Some(1.));
+        assert_eq!(result.durations.get_max(), Some(3.));
+    }
+
+    #[test]
+    fn upgrades_monitor_result() {
+        let path = Path::new("results/opensim-core-main-2023_09_01-abc123/Arm26")
+            .join(LEGACY_MONITOR_FILE_NAME);
+        let (result, version) = upgrade(MONITOR_V0, &path).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(result.name, "Arm26");
+        assert_eq!(result.opensim_name, "opensim-core-main");
+        assert_eq!(result.date, "2023_09_01");
+        assert_eq!(result.commit, "abc123");
+        assert_eq!(result.config_hash, 42);
+        assert_eq!(result.failed_count, 1);
+        assert_eq!(result.durations.get_median(), Some(2.5));
+        assert_eq!(result.durations.get_min(), Some(1.));
+        assert_eq!(result.durations.get_max(), Some(4.));
+    }
+
+    #[test]
+    fn keeps_current_result() {
+        let mut result = ResultInfo::new(
+            String::from("Arm26"),
+            String::from("opensim-core"),
+            String::from("abc123"),
+            String::from("2023-09-01"),
+        );
+        result
+            .durations
+            .add_sample(std::time::Duration::from_secs(2));
+        let json = serde_json::to_string(&result).unwrap();
+
+        let (upgraded, version) = upgrade(&json, Path::new(RESULT_INFO_FILE_NAME)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        assert_eq!(upgraded.durations.get_median(), Some(2.));
+    }
+
+    #[test]
+    fn rejects_newer_schema() {
+        let json = format!(r#"{{"schema_version": {}}}"#, SCHEMA_VERSION + 1);
+        assert!(upgrade(&json, Path::new(RESULT_INFO_FILE_NAME)).is_err());
+    }
+}
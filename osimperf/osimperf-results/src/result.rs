@@ -0,0 +1,222 @@
+use crate::{
+    migrate, CpuFrequency, Durations, Fingerprint, Isolation, PerfCounters, ResourceUsage,
+    RESULT_INFO_FILE_NAME, SCHEMA_VERSION,
+};
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResultInfo {
+    /// Version of the schema of this file, see [crate::migrate].
+    pub schema_version: u32,
+    /// Test case name.
+    pub name: String,
+    /// Cell name.
+    pub cell_name: Option<String>,
+    /// Opensim install name.
+    pub opensim_name: String,
+    /// Build variant of the install, if any.
+    #[serde(default)]
+    pub variant: Option<String>,
+    /// Opensim-core commit hash.
+    pub commit: String,
+    /// Opensim-core commit date.
+    pub date: String,
+    /// Already ran the pre_benchmark_cmds.
+    pub setup: bool,
+    /// Benchmark durations.
+    pub durations: Durations,
+    /// Benchmark resource usage, one sample per iteration.
+    #[serde(default)]
+    pub resource_usage: Vec<ResourceUsage>,
+    /// Benchmark hardware event counts, one sample per iteration (if recorded).
+    ///
+    /// Iterations for which the counters could not be attached have no sample.
+    #[serde(default)]
+    pub perf_counters: Vec<Option<PerfCounters>>,
+    /// Benchmark grind result.
+    pub grind: Option<std::time::Duration>,
+    /// Hash of the test config, see [crate::config_hash].
+    pub config_hash: u64,
+    /// Number of consecutive failed benchmark runs.
+    #[serde(default)]
+    pub failed_count: usize,
+    /// Output opensim log file.
+    pub opensim_log: Option<PathBuf>,
+    /// Failed post-benchmark validations, the result is invalid if any.
+    #[serde(default)]
+    pub validation_failures: Vec<ValidationFailure>,
+    /// Outcome of the benchmark runs.
+    #[serde(default)]
+    pub outcome: Outcome,
+    /// Cpu pinning and scheduling of the benchmark.
+    #[serde(default)]
+    pub isolation: Isolation,
+    /// Cpu frequency scaling during the benchmark.
+    #[serde(default)]
+    pub cpu_frequency: CpuFrequency,
+    /// Machine and build environment of the benchmark.
+    #[serde(default)]
+    pub fingerprint: Option<Fingerprint>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// All benchmark iterations ran to completion.
+    #[default]
+    Completed,
+    /// The benchmark command failed.
+    Failed,
+    /// The benchmark command was killed after exceeding the timeout.
+    TimedOut,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidationFailure {
+    /// The failed check, e.g. the post-benchmark command.
+    pub check: String,
+    /// Benchmark iteration that was validated.
+    pub iteration: usize,
+    /// Reason of failure.
+    pub message: String,
+}
+
+impl ResultInfo {
+    /// An empty result of a benchmark of an install.
+    pub fn new(name: String, opensim_name: String, commit: String, date: String) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            name,
+            cell_name: None,
+            opensim_name,
+            variant: None,
+            commit,
+            date,
+            setup: false,
+            durations: Durations::default(),
+            resource_usage: Vec::new(),
+            perf_counters: Vec::new(),
+            grind: None,
+            config_hash: 0,
+            failed_count: 0,
+            opensim_log: None,
+            validation_failures: Vec::new(),
+            outcome: Outcome::Completed,
+            isolation: Isolation::default(),
+            cpu_frequency: CpuFrequency::default(),
+            fingerprint: None,
+        }
+    }
+
+    pub fn filename() -> &'static str {
+        RESULT_INFO_FILE_NAME
+    }
+
+    /// Reads a result file of any schema version, upgrading it to the current version.
+    ///
+    /// Files with an older schema are rewritten using the current schema, if possible.
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read result file {:?}", path))?;
+        let (result, version) = migrate::upgrade(&json, path)
+            .with_context(|| format!("failed to parse result file {:?}", path))?;
+        if version < SCHEMA_VERSION {
+            debug!(
+                "Upgrading {:?} from schema version {version} to {SCHEMA_VERSION}",
+                path
+            );
+            if let Err(err) = result.write(path) {
+                warn!("Failed to upgrade result file {:?}: {err:#}", path);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Parses a result of any schema version, without rewriting it.
+    ///
+    /// The path is only used for results of the monitor, which are named after their location.
+    pub fn from_json(json: &str, path: &Path) -> Result<Self> {
+        Ok(migrate::upgrade(json, path)?.0)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("failed to write result file {:?}", path))
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.outcome == Outcome::TimedOut
+    }
+
+    pub fn failed(&self) -> bool {
+        self.failed_count > 0
+    }
+
+    /// Returns false if any post-benchmark validation failed.
+    pub fn is_valid(&self) -> bool {
+        self.validation_failures.is_empty()
+    }
+
+    /// Peak resident set size over all iterations in kilobytes.
+    pub fn max_rss(&self) -> Option<u64> {
+        self.resource_usage.iter().map(|u| u.max_rss).max()
+    }
+
+    /// Mean cpu time (user and system) per iteration in seconds.
+    pub fn mean_cpu_time(&self) -> Option<f64> {
+        if self.resource_usage.is_empty() {
+            return None;
+        }
+        let total = self
+            .resource_usage
+            .iter()
+            .map(|u| u.cpu_time().as_secs_f64())
+            .sum::<f64>();
+        Some(total / self.resource_usage.len() as f64)
+    }
+
+    /// Mean instruction count per iteration.
+    pub fn mean_instructions(&self) -> Option<f64> {
+        let samples = self
+            .perf_counters
+            .iter()
+            .flatten()
+            .filter_map(|c| c.instructions)
+            .collect::<Vec<u64>>();
+        if samples.is_empty() {
+            return None;
+        }
+        Some(samples.iter().sum::<u64>() as f64 / samples.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_perf_counters_keep_iterations_in_step() {
+        let mut result = ResultInfo::new(
+            String::from("test"),
+            String::from("opensim"),
+            String::from("abc"),
+            String::from("2023-01-01"),
+        );
+        let counters = |instructions| PerfCounters {
+            instructions: Some(instructions),
+            ..Default::default()
+        };
+        result.perf_counters = vec![Some(counters(10)), None, Some(counters(20))];
+        assert_eq!(result.mean_instructions(), Some(15.));
+
+        // Missing samples are stored as null.
+        let mut json = serde_json::to_value(&result).unwrap();
+        json["perf_counters"] = serde_json::json!([{"instructions": 1}, null]);
+        let result = ResultInfo::from_json(&json.to_string(), Path::new("")).unwrap();
+        assert_eq!(result.perf_counters.len(), 2);
+        assert!(result.perf_counters[1].is_none());
+    }
+}
@@ -0,0 +1,29 @@
+/// Tables of the result store, written by `osimperf-cli` and read by `osimperf-lib`.
+///
+/// Each result is identified by the path of its result file, and keeps the complete result as json.
+/// The samples of each iteration are stored separately for querying.
+pub static STORE_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS results (
+    id           INTEGER PRIMARY KEY,
+    path         TEXT NOT NULL UNIQUE,
+    name         TEXT NOT NULL,
+    opensim_name TEXT NOT NULL,
+    commit_hash  TEXT NOT NULL,
+    date         TEXT NOT NULL,
+    hostname     TEXT,
+    outcome      TEXT NOT NULL,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    info         TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_by_install ON results (opensim_name, commit_hash, name);
+CREATE TABLE IF NOT EXISTS iterations (
+    result_id    INTEGER NOT NULL REFERENCES results (id) ON DELETE CASCADE,
+    iteration    INTEGER NOT NULL,
+    duration     REAL NOT NULL,
+    max_rss      INTEGER,
+    cpu_time     REAL,
+    instructions INTEGER,
+    PRIMARY KEY (result_id, iteration)
+);
+PRAGMA foreign_keys = ON;
+";
@@ -6,7 +6,7 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use osimperf_lib::{
-    bench_tests::{BenchTestResult, BenchTestSetup},
+    bench_tests::{BenchTestResult, BenchTestSetup, Estimator, ResultStore},
     Archive, CompilationNode, CompilationTarget, Folder, Home, Progress, ResultsFolder, Status,
 };
 use ratatui::{prelude::*, widgets::*};
@@ -22,6 +22,14 @@ pub struct Args {
     /// Specify path to osimperf home dir. If not, current directory will be used as home.
     #[arg(long)]
     pub home: Option<String>,
+
+    /// Statistic of the benchmark durations to display.
+    #[arg(long, value_enum, default_value_t = Estimator::Mean)]
+    pub estimator: Estimator,
+
+    /// Read the results from the result store at this path, instead of the result files.
+    #[arg(long)]
+    pub db: Option<PathBuf>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -64,11 +72,8 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<()> {
 
         if event::poll(Duration::from_millis(250))? {
             if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') => return Ok(()),
-                        _ => {}
-                    }
+                if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q') {
+                    return Ok(());
                 }
             }
         }
@@ -81,7 +86,7 @@ fn wrap_ui<B: Backend>(f: &mut Frame<B>, app: &mut App, result: &mut Result<()>)
 
 fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> Result<()> {
     let nodes = CompilationNode::collect_archived(&app.archive)?;
-    let tests = BenchTestSetup::find_all(&app.tests_dir)?;
+    let tests = BenchTestSetup::find_all(&app.tests_dir, None)?;
 
     let rects = Layout::default()
         .constraints([Constraint::Percentage(100)].as_ref())
@@ -109,12 +114,12 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> Result<()> {
         cells.push(Cell::from(node.repo.name()));
         cells.push(Cell::from(node.commit.date.as_str()));
         // Fill cell in case that it was not yet done compiling.
-        for (i, state) in node
+        if let Some((i, state)) = node
             .state
             .get()
             .iter()
             .enumerate()
-            .filter(|(_, s)| !s.is_done())
+            .find(|(_, s)| !s.is_done())
         {
             if i != 2 {
                 prev_was_done = false;
@@ -135,7 +140,6 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> Result<()> {
                     .set_style(Style::default().bg(Color::Red)),
                 _ => panic!(),
             });
-            break;
         }
 
         // Skip rows that are consecutively done, after the Xth node.
@@ -165,12 +169,23 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) -> Result<()> {
             }
         } else {
             for t in tests.iter() {
-                let result = BenchTestResult::read(&app.results_dir, &node.id(), &t.name)?;
-                let dt_mean = result.as_ref().and_then(|x| x.durations.get_mean());
-                let dt_stddev = result.as_ref().and_then(|x| x.durations.get_stddev());
-                let iteration = result.as_ref().map(|x| x.durations.len());
-                let failed_count = result.as_ref().map(|x| x.failed_count);
+                let result = match app.store.as_ref() {
+                    Some(store) => store.read(&node.id(), &t.name)?,
+                    None => BenchTestResult::read(&app.results_dir, &node.id(), &t.name)?,
+                };
+                let dt_mean = result
+                    .as_ref()
+                    .and_then(|x| x.info.durations.get_estimate(app.estimator));
+                let dt_stddev = result
+                    .as_ref()
+                    .and_then(|x| x.info.durations.get_spread(app.estimator));
+                let iteration = result.as_ref().map(|x| x.info.durations.len());
+                let failed_count = result.as_ref().map(|x| x.info.failed_count);
+                let timed_out = result.as_ref().is_some_and(|x| x.timed_out());
                 cells.push(match (dt_mean, dt_stddev, iteration, failed_count) {
+                    _ if timed_out => {
+                        Cell::from("Timeout").style(Style::default().fg(Color::Red))
+                    }
                     (_, _, _, Some(i)) if i > 0 => {
                         Cell::from("Failed").style(Style::default().fg(Color::Red))
                     }
@@ -238,15 +253,19 @@ struct App {
     archive: Archive,
     results_dir: ResultsFolder,
     tests_dir: PathBuf,
+    estimator: Estimator,
+    store: Option<ResultStore>,
 }
 
 impl App {
     fn new(args: &Args) -> Result<App> {
-        let home = Home::new_or_current(args.home.as_ref().map(|p| p.as_str()))?;
+        let home = Home::new_or_current(args.home.as_deref())?;
         Ok(App {
             archive: home.default_archive()?,
             results_dir: home.default_results()?,
             tests_dir: home.path()?.join("tests"),
+            estimator: args.estimator,
+            store: args.db.as_deref().map(ResultStore::open).transpose()?,
         })
     }
 }
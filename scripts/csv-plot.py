@@ -13,7 +13,7 @@ print("file = ", file)
 print("out = ", out)
 file_name = os.path.splitext(file)[0]
 
-df = pd.read_csv(file, names=['name', 'time', 'label', 'value'])
+df = pd.read_csv(file, names=['name', 'time', 'label', 'value', 'max_rss', 'cpu_time'])
 
 df['label']=pd.to_datetime(df['label'], format='%Y-%m-%d')
 
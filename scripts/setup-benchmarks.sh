@@ -17,12 +17,12 @@ set -eo pipefail
 #
 # osimperf-cli plot --results .
 
-echo "Setup benchmarks for $(osimperf-install-info commit) ($(osimperf-install-info date))."
+echo "Setup benchmarks for $(osimperf-cli install-info commit) ($(osimperf-cli install-info date))."
 
 # todo move this to config.
 OSIMPERF_HOME=$(dirname $(dirname $(realpath "$0")))
 
-root_dir="$(osimperf-install-info root)"
+root_dir="$(osimperf-cli install-info root)"
 build_dir="$root_dir/build/osimperf-tools"
 
 mkdir -p $build_dir
@@ -1,7 +1,7 @@
 #!/bin/bash
 set -eo pipefail
 
-root_dir="$(osimperf-install-info path)"
+root_dir="$(osimperf-cli install-info root)"
 build_dir="$root_dir/build"
 
 mkdir -p $build_dir